@@ -9,7 +9,7 @@ use glfw::{
     fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
 };
 
-use crate::shaders::Shader;
+use crate::{sampler::SamplerDesc, shaders::Shader};
 
 pub fn main_1_4_1() -> Result<()> {
     let mut glfw = glfw::init(fail_on_errors!())?;
@@ -109,16 +109,10 @@ pub fn main_1_4_1() -> Result<()> {
         let mut texture: GLuint = 0;
         unsafe {
             gl::GenTextures(1, &mut texture);
-            gl::BindTexture(gl::TEXTURE_2D, texture); // work on this texture
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint); // wrap texture with GL_REPEAT
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-            // set texture filtering parameters
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                gl::LINEAR_MIPMAP_LINEAR as GLint,
-            );
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            // work on this texture
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            // the default sampler repeats the texture and filters linearly between mipmaps
+            SamplerDesc::default().apply(gl::TEXTURE_2D);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
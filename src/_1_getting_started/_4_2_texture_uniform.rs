@@ -3,16 +3,20 @@ use std::{
     mem, ptr,
 };
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use gl::{
-    types::{GLfloat, GLint, GLsizei, GLsizeiptr, GLuint},
+    types::{GLfloat, GLsizei, GLsizeiptr, GLuint},
     ARRAY_BUFFER,
 };
 use glfw::{
     fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
 };
 
-use crate::shaders::Shader;
+use crate::{
+    sampler::{SamplerCache, SamplerDesc},
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+};
 
 pub fn main_1_4_2() -> Result<()> {
     let mut glfw = glfw::init(fail_on_errors!())?;
@@ -105,66 +109,23 @@ pub fn main_1_4_2() -> Result<()> {
             gl::EnableVertexAttribArray(2);
         }
 
-        let image1 = image::open("resources/textures/container.jpg").context("opening texture")?;
-        let image1_data = image1.as_bytes().to_vec();
-        let image2 = image::open("resources/textures/awesomeface.png")?.flipv();
-        let image2_data = image2.as_bytes().to_vec();
+        // load textures, both flipped so that they match the texture coords
+        let texture1 = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+        // awesomeface.png has an alpha channel, the loader picks RGBA for it
+        let texture2 = Texture2D::from_path(
+            "resources/textures/awesomeface.png",
+            TextureOptions::default(),
+        )?;
 
-        // load textures
-        let mut texture1: GLuint = 0;
-        let mut texture2: GLuint = 0;
-        unsafe {
-            // texture 1
-            gl::GenTextures(1, &mut texture1);
-            gl::BindTexture(gl::TEXTURE_2D, texture1); // work on this texture
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint); // wrap texture with GL_REPEAT
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-            // set texture filtering parameters
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                gl::LINEAR_MIPMAP_LINEAR as GLint,
-            );
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGB as GLint,
-                image1.width() as GLint,
-                image1.height() as GLint,
-                0,
-                gl::RGB,
-                gl::UNSIGNED_BYTE,
-                // instead of doing image_data[0], use .as_ptr()
-                image1_data.as_ptr() as *const c_void,
-            );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
-
-            // texture 2
-            gl::GenTextures(1, &mut texture2);
-            gl::BindTexture(gl::TEXTURE_2D, texture2); // work on this texture
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint); // wrap texture with GL_REPEAT
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
-            // set texture filtering parameters
-            gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                gl::LINEAR_MIPMAP_LINEAR as GLint,
-            );
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
-            gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGB as GLint,
-                image2.width() as GLint,
-                image2.height() as GLint,
-                0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                image2_data.as_ptr() as *const c_void,
-            );
-            gl::GenerateMipmap(gl::TEXTURE_2D);
+        // both textures use the same description, so they share one sampler object
+        let mut samplers = SamplerCache::default();
+        let sampler1 = samplers.get(&SamplerDesc::linear_repeat());
+        let sampler2 = samplers.get(&SamplerDesc::default());
 
+        unsafe {
             shader.use_program();
             shader.set_int(&CString::new("texture1")?, 0);
             shader.set_int(&CString::new("texture2")?, 1);
@@ -181,10 +142,10 @@ pub fn main_1_4_2() -> Result<()> {
 
                 shader.use_program();
 
-                gl::ActiveTexture(gl::TEXTURE0);
-                gl::BindTexture(gl::TEXTURE_2D, texture1);
-                gl::ActiveTexture(gl::TEXTURE1);
-                gl::BindTexture(gl::TEXTURE_2D, texture2);
+                texture1.bind(0);
+                sampler1.bind(0);
+                texture2.bind(1);
+                sampler2.bind(1);
 
                 gl::BindVertexArray(vao);
                 gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
@@ -0,0 +1,170 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Rad};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
+};
+
+use crate::{
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+};
+
+pub fn main_1_5_2() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(800, 600, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        #[rustfmt::skip]
+        let vertices: [f32; 20] = [
+             // positions     // texture coords
+             0.5,  0.5, 0.0,  1.0, 1.0, // top right
+             0.5, -0.5, 0.0,  1.0, 0.0, // bottom right
+            -0.5, -0.5, 0.0,  0.0, 0.0, // bottom left
+            -0.5,  0.5, 0.0,  0.0, 1.0, // top left
+        ];
+
+        let indices: [u32; 6] = [
+            0, 1, 3, // first triangle
+            1, 2, 3, // second triangle
+        ];
+
+        let shader = Shader::new(
+            "src/_1_getting_started/shaders/5.1.transform.vs",
+            "src/_1_getting_started/shaders/5.1.transform.fs",
+        )?;
+
+        let mut vbo: GLuint = 0;
+        let mut vao: GLuint = 0;
+        let mut ebo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::GenBuffers(1, &mut ebo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                mem::size_of_val(&indices) as GLsizeiptr,
+                indices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            // texture coords
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+        }
+
+        let texture1 = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+        let texture2 = Texture2D::from_path(
+            "resources/textures/awesomeface.png",
+            TextureOptions::default(),
+        )?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+            shader.set_int(&CString::new("texture2")?, 1);
+        }
+
+        while !window.should_close() {
+            // all events including input
+            process_events(&mut window, &events);
+
+            // first container: bottom right, spinning around z
+            let time = glfw.get_time() as f32;
+            let spinning = Transform::from_translation(vec3(0.5, -0.5, 0.0))
+                * Transform::from_axis_angle(vec3(0.0, 0.0, 1.0), Rad(time));
+            // second container: top left, pulsing between 0 and 1 times its size
+            let pulsing = Transform {
+                translation: vec3(-0.5, 0.5, 0.0),
+                ..Transform::from_scale(time.sin().abs())
+            };
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+
+                texture1.bind(0);
+                texture2.bind(1);
+
+                shader.use_program();
+                gl::BindVertexArray(vao);
+
+                // same vertices drawn twice, only the transform changes
+                for transform in [spinning, pulsing] {
+                    shader.set_mat4(&CString::new("transform")?, &transform.to_matrix());
+                    gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+            gl::DeleteBuffers(1, &ebo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(window: &mut glfw::Window, events: &GlfwReceiver<(f64, glfw::WindowEvent)>) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Deg, Matrix4, Rad, Vector3};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
+};
+
+use crate::{
+    projection::{aspect_ratio, DepthMode, Projection},
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+const PERSPECTIVE: Projection = Projection::Perspective {
+    fovy: Rad(std::f32::consts::FRAC_PI_4),
+    near: 0.1,
+    far: 100.0,
+};
+const ORTHOGRAPHIC: Projection = Projection::Orthographic {
+    height: 6.0,
+    near: 0.1,
+    far: 100.0,
+};
+
+/// what can be switched at runtime
+struct ViewSettings {
+    projection: Projection,
+    depth: DepthMode,
+    /// take the aspect ratio from the framebuffer instead of the initial window size
+    follow_resize: bool,
+    framebuffer_size: (i32, i32),
+}
+
+impl ViewSettings {
+    fn projection_matrix(&self) -> Matrix4<f32> {
+        let aspect = if self.follow_resize {
+            aspect_ratio(self.framebuffer_size.0, self.framebuffer_size.1)
+        } else {
+            SCR_WIDTH as f32 / SCR_HEIGHT as f32
+        };
+        self.projection.matrix(aspect, self.depth)
+    }
+}
+
+pub fn main_1_6_3() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        #[rustfmt::skip]
+        let vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+
+        let shader = Shader::new(
+            "src/_1_getting_started/shaders/6.1.coordinate_systems.vs",
+            "src/_1_getting_started/shaders/5.1.transform.fs",
+        )?;
+
+        let mut vbo: GLuint = 0;
+        let mut vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            // texture coords
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+        }
+
+        let texture1 = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+        let texture2 = Texture2D::from_path(
+            "resources/textures/awesomeface.png",
+            TextureOptions::default(),
+        )?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+            shader.set_int(&CString::new("texture2")?, 1);
+
+            // without depth testing the cube's back faces get drawn over its front faces
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        // world space positions of the cubes
+        let cube_positions: [Vector3<f32>; 10] = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(2.0, 5.0, -15.0),
+            vec3(-1.5, -2.2, -2.5),
+            vec3(-3.8, -2.0, -12.3),
+            vec3(2.4, -0.4, -3.5),
+            vec3(-1.7, 3.0, -7.5),
+            vec3(1.3, -2.0, -2.5),
+            vec3(1.5, 2.0, -2.5),
+            vec3(1.5, 0.2, -1.5),
+            vec3(-1.3, 1.0, -1.5),
+        ];
+
+        let view = Matrix4::from_translation(vec3(0.0, 0.0, -3.0));
+        let mut settings = ViewSettings {
+            projection: PERSPECTIVE,
+            depth: DepthMode::Standard,
+            follow_resize: true,
+            framebuffer_size: window.get_framebuffer_size(),
+        };
+        println!("P: perspective/orthographic, Z: reversed-Z depth, R: follow window resizes");
+
+        while !window.should_close() {
+            // all events including input
+            process_events(&mut window, &events, &mut settings);
+            let time = glfw.get_time() as f32;
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                texture1.bind(0);
+                texture2.bind(1);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &settings.projection_matrix());
+
+                gl::BindVertexArray(vao);
+                for (i, position) in cube_positions.iter().enumerate() {
+                    // every cube starts at a different angle, all of them keep turning
+                    let angle = Deg(20.0 * i as f32 + 25.0 * time);
+                    let model = Transform {
+                        translation: *position,
+                        ..Transform::from_axis_angle(vec3(1.0, 0.3, 0.5), angle)
+                    };
+                    shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    settings: &mut ViewSettings,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                settings.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::P, _, Action::Press, _) => {
+                settings.projection = match settings.projection {
+                    Projection::Perspective { .. } => ORTHOGRAPHIC,
+                    Projection::Orthographic { .. } => PERSPECTIVE,
+                };
+            }
+            glfw::WindowEvent::Key(Key::Z, _, Action::Press, _) => unsafe {
+                if DepthMode::reversed_z_supported() {
+                    settings.depth = settings.depth.toggled();
+                    settings.depth.apply();
+                    println!("depth mode: {:?}", settings.depth);
+                } else {
+                    println!("reversed-Z needs OpenGL 4.5 or ARB_clip_control");
+                }
+            },
+            glfw::WindowEvent::Key(Key::R, _, Action::Press, _) => {
+                settings.follow_resize = !settings.follow_resize;
+                println!("follow window resizes: {}", settings.follow_resize);
+            }
+            _ => {}
+        }
+    }
+}
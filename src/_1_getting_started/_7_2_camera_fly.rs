@@ -0,0 +1,283 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Deg, Point3, Vector3};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    projection::aspect_ratio,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+}
+
+pub fn main_1_7_2() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        #[rustfmt::skip]
+        let vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+
+        let shader = Shader::new(
+            "src/_1_getting_started/shaders/6.1.coordinate_systems.vs",
+            "src/_1_getting_started/shaders/5.1.transform.fs",
+        )?;
+
+        let mut vbo: GLuint = 0;
+        let mut vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            // texture coords
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+        }
+
+        let texture1 = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+        let texture2 = Texture2D::from_path(
+            "resources/textures/awesomeface.png",
+            TextureOptions::default(),
+        )?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+            shader.set_int(&CString::new("texture2")?, 1);
+
+            // without depth testing the cube's back faces get drawn over its front faces
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        // world space positions of the cubes
+        let cube_positions: [Vector3<f32>; 10] = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(2.0, 5.0, -15.0),
+            vec3(-1.5, -2.2, -2.5),
+            vec3(-3.8, -2.0, -12.3),
+            vec3(2.4, -0.4, -3.5),
+            vec3(-1.7, 3.0, -7.5),
+            vec3(1.3, -2.0, -2.5),
+            vec3(1.5, 2.0, -2.5),
+            vec3(1.5, 0.2, -1.5),
+            vec3(-1.3, 1.0, -1.5),
+        ];
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+        };
+        println!("WASD: move, space/ctrl: up/down, mouse: look, scroll: zoom, tab: release cursor");
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            // all events including input
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                texture1.bind(0);
+                texture2.bind(1);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+
+                gl::BindVertexArray(vao);
+                for (i, position) in cube_positions.iter().enumerate() {
+                    let angle = Deg(20.0 * i as f32);
+                    let model = Transform {
+                        translation: *position,
+                        ..Transform::from_axis_angle(vec3(1.0, 0.3, 0.5), angle)
+                    };
+                    shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::Tab, _, Action::Press, _) => {
+                let mode = match window.get_cursor_mode() {
+                    CursorMode::Disabled => CursorMode::Normal,
+                    _ => CursorMode::Disabled,
+                };
+                window.set_cursor_mode(mode);
+                // the cursor jumps while released, don't turn that into a camera rotation
+                input.cursor.reset();
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                if window.get_cursor_mode() == CursorMode::Disabled {
+                    input
+                        .camera
+                        .process_mouse_movement(x_offset, y_offset, true);
+                }
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame instead of handled as events, so holding a key moves
+/// the camera smoothly instead of at the key repeat rate
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Deg, EuclideanSpace, Point3, Vector3};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, MouseButton,
+    WindowHint, WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker, OrbitCamera},
+    projection::aspect_ratio,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// half the diagonal of a unit cube, the furthest a rotated cube reaches from its centre
+const CUBE_EXTENT: f32 = 0.87;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CameraMode {
+    Fly,
+    Orbit,
+}
+
+/// everything the event handler changes
+struct InputState {
+    mode: CameraMode,
+    camera: Camera,
+    orbit: OrbitCamera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// corners of the box around all cubes, framed with F
+    bounds: (Point3<f32>, Point3<f32>),
+}
+
+impl InputState {
+    fn aspect(&self) -> f32 {
+        aspect_ratio(self.framebuffer_size.0, self.framebuffer_size.1)
+    }
+}
+
+pub fn main_1_7_3() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // the orbit camera is dragged with a visible cursor
+        window.set_cursor_mode(CursorMode::Normal);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        #[rustfmt::skip]
+        let vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+
+        let shader = Shader::new(
+            "src/_1_getting_started/shaders/6.1.coordinate_systems.vs",
+            "src/_1_getting_started/shaders/5.1.transform.fs",
+        )?;
+
+        let mut vbo: GLuint = 0;
+        let mut vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            // texture coords
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+        }
+
+        let texture1 = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+        let texture2 = Texture2D::from_path(
+            "resources/textures/awesomeface.png",
+            TextureOptions::default(),
+        )?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+            shader.set_int(&CString::new("texture2")?, 1);
+
+            // without depth testing the cube's back faces get drawn over its front faces
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        // world space positions of the cubes
+        let cube_positions: [Vector3<f32>; 10] = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(2.0, 5.0, -15.0),
+            vec3(-1.5, -2.2, -2.5),
+            vec3(-3.8, -2.0, -12.3),
+            vec3(2.4, -0.4, -3.5),
+            vec3(-1.7, 3.0, -7.5),
+            vec3(1.3, -2.0, -2.5),
+            vec3(1.5, 2.0, -2.5),
+            vec3(1.5, 0.2, -1.5),
+            vec3(-1.3, 1.0, -1.5),
+        ];
+
+        let mut bounds = (cube_positions[0], cube_positions[0]);
+        for position in &cube_positions {
+            bounds.0 = bounds.0.zip(*position, |a, b| a.min(b - CUBE_EXTENT));
+            bounds.1 = bounds.1.zip(*position, |a, b| a.max(b + CUBE_EXTENT));
+        }
+        let mut input = InputState {
+            mode: CameraMode::Orbit,
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            orbit: OrbitCamera::new(Point3::new(0.0, 0.0, 0.0), 3.0),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            bounds: (Point3::from_vec(bounds.0), Point3::from_vec(bounds.1)),
+        };
+        println!("orbit: left drag: rotate, middle drag: pan, scroll: dolly, F: frame all cubes");
+        println!("fly: WASD: move, space/ctrl: up/down, mouse: look, scroll: zoom");
+        println!("C: switch between orbit and fly camera");
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            // all events including input
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            let aspect = input.aspect();
+            let (view, projection) = match input.mode {
+                CameraMode::Fly => {
+                    process_input(&window, &mut input.camera, delta_time);
+                    (
+                        input.camera.view_matrix(),
+                        input.camera.projection_matrix(aspect),
+                    )
+                }
+                CameraMode::Orbit => {
+                    input.orbit.update(delta_time);
+                    (
+                        input.orbit.view_matrix(),
+                        input.orbit.projection_matrix(aspect),
+                    )
+                }
+            };
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                texture1.bind(0);
+                texture2.bind(1);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+
+                gl::BindVertexArray(vao);
+                for (i, position) in cube_positions.iter().enumerate() {
+                    let angle = Deg(20.0 * i as f32);
+                    let model = Transform {
+                        translation: *position,
+                        ..Transform::from_axis_angle(vec3(1.0, 0.3, 0.5), angle)
+                    };
+                    shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::C, _, Action::Press, _) => {
+                // hand the current view over so switching doesn't move the camera
+                input.mode = match input.mode {
+                    CameraMode::Fly => {
+                        let distance = input.orbit.goal.distance;
+                        input.orbit.set_from_camera(&input.camera, distance);
+                        window.set_cursor_mode(CursorMode::Normal);
+                        CameraMode::Orbit
+                    }
+                    CameraMode::Orbit => {
+                        input.camera.set_from_orbit(&input.orbit);
+                        window.set_cursor_mode(CursorMode::Disabled);
+                        CameraMode::Fly
+                    }
+                };
+                input.cursor.reset();
+            }
+            glfw::WindowEvent::Key(Key::F, _, Action::Press, _)
+                if input.mode == CameraMode::Orbit =>
+            {
+                let (min, max) = input.bounds;
+                let aspect = input.aspect();
+                input.orbit.frame_bounds(min, max, aspect);
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                match input.mode {
+                    CameraMode::Fly => input
+                        .camera
+                        .process_mouse_movement(x_offset, y_offset, true),
+                    CameraMode::Orbit => {
+                        if window.get_mouse_button(MouseButton::Button1) == Action::Press {
+                            input.orbit.orbit(x_offset, y_offset);
+                        } else if window.get_mouse_button(MouseButton::Button3) == Action::Press {
+                            input.orbit.pan(x_offset, y_offset);
+                        }
+                    }
+                }
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => match input.mode {
+                CameraMode::Fly => input.camera.process_mouse_scroll(y_offset as f32),
+                CameraMode::Orbit => input.orbit.dolly(y_offset as f32),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame instead of handled as events, so holding a key moves
+/// the camera smoothly instead of at the key repeat rate
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
@@ -10,6 +10,14 @@ pub mod _3_2_shaders_more_attributes;
 pub mod _3_3_shader_class;
 pub mod _4_1_textures;
 pub mod _4_2_texture_uniform;
+pub mod _5_1_transformations;
+pub mod _5_2_transformations_ex_scale;
+pub mod _6_1_coordinate_systems;
+pub mod _6_2_coordinate_systems_depth;
+pub mod _6_3_coordinate_systems_multiple;
+pub mod _7_1_camera_circle;
+pub mod _7_2_camera_fly;
+pub mod _7_3_camera_orbit;
 
 pub use _1_1_hello_window::*;
 pub use _2_1_hello_triangle::*;
@@ -22,3 +30,11 @@ pub use _3_2_shaders_more_attributes::*;
 pub use _3_3_shader_class::*;
 pub use _4_1_textures::*;
 pub use _4_2_texture_uniform::*;
+pub use _5_1_transformations::*;
+pub use _5_2_transformations_ex_scale::*;
+pub use _6_1_coordinate_systems::*;
+pub use _6_2_coordinate_systems_depth::*;
+pub use _6_3_coordinate_systems_multiple::*;
+pub use _7_1_camera_circle::*;
+pub use _7_2_camera_fly::*;
+pub use _7_3_camera_orbit::*;
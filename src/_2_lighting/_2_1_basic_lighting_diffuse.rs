@@ -0,0 +1,264 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Point3};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    projection::aspect_ratio,
+    shaders::Shader,
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+}
+
+pub fn main_2_2_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let lighting_shader = Shader::new(
+            "src/_2_lighting/shaders/2.basic_lighting.vs",
+            "src/_2_lighting/shaders/2.1.basic_lighting_diffuse.fs",
+        )?;
+        let light_cube_shader = Shader::new(
+            "src/_2_lighting/shaders/1.light_cube.vs",
+            "src/_2_lighting/shaders/1.light_cube.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let vertices: [f32; 216] = [
+            // positions       // normals
+            -0.5, -0.5, -0.5,  0.0,  0.0, -1.0,
+             0.5, -0.5, -0.5,  0.0,  0.0, -1.0,
+             0.5,  0.5, -0.5,  0.0,  0.0, -1.0,
+             0.5,  0.5, -0.5,  0.0,  0.0, -1.0,
+            -0.5,  0.5, -0.5,  0.0,  0.0, -1.0,
+            -0.5, -0.5, -0.5,  0.0,  0.0, -1.0,
+
+            -0.5, -0.5,  0.5,  0.0,  0.0,  1.0,
+             0.5, -0.5,  0.5,  0.0,  0.0,  1.0,
+             0.5,  0.5,  0.5,  0.0,  0.0,  1.0,
+             0.5,  0.5,  0.5,  0.0,  0.0,  1.0,
+            -0.5,  0.5,  0.5,  0.0,  0.0,  1.0,
+            -0.5, -0.5,  0.5,  0.0,  0.0,  1.0,
+
+            -0.5,  0.5,  0.5, -1.0,  0.0,  0.0,
+            -0.5,  0.5, -0.5, -1.0,  0.0,  0.0,
+            -0.5, -0.5, -0.5, -1.0,  0.0,  0.0,
+            -0.5, -0.5, -0.5, -1.0,  0.0,  0.0,
+            -0.5, -0.5,  0.5, -1.0,  0.0,  0.0,
+            -0.5,  0.5,  0.5, -1.0,  0.0,  0.0,
+
+             0.5,  0.5,  0.5,  1.0,  0.0,  0.0,
+             0.5,  0.5, -0.5,  1.0,  0.0,  0.0,
+             0.5, -0.5, -0.5,  1.0,  0.0,  0.0,
+             0.5, -0.5, -0.5,  1.0,  0.0,  0.0,
+             0.5, -0.5,  0.5,  1.0,  0.0,  0.0,
+             0.5,  0.5,  0.5,  1.0,  0.0,  0.0,
+
+            -0.5, -0.5, -0.5,  0.0, -1.0,  0.0,
+             0.5, -0.5, -0.5,  0.0, -1.0,  0.0,
+             0.5, -0.5,  0.5,  0.0, -1.0,  0.0,
+             0.5, -0.5,  0.5,  0.0, -1.0,  0.0,
+            -0.5, -0.5,  0.5,  0.0, -1.0,  0.0,
+            -0.5, -0.5, -0.5,  0.0, -1.0,  0.0,
+
+            -0.5,  0.5, -0.5,  0.0,  1.0,  0.0,
+             0.5,  0.5, -0.5,  0.0,  1.0,  0.0,
+             0.5,  0.5,  0.5,  0.0,  1.0,  0.0,
+             0.5,  0.5,  0.5,  0.0,  1.0,  0.0,
+            -0.5,  0.5,  0.5,  0.0,  1.0,  0.0,
+            -0.5,  0.5, -0.5,  0.0,  1.0,  0.0,
+        ];
+
+        // the lit cube and the lamp share the vertex buffer, the lamp only needs positions
+        let mut vbo: GLuint = 0;
+        let mut cube_vao: GLuint = 0;
+        let mut light_cube_vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut cube_vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 6 * mem::size_of::<GLfloat>() as GLsizei;
+
+            gl::BindVertexArray(cube_vao);
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            // normal
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            gl::GenVertexArrays(1, &mut light_cube_vao);
+            gl::BindVertexArray(light_cube_vao);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+        }
+
+        let light_pos = vec3(1.2, 1.0, 2.0);
+        let light_color = vec3(1.0, 1.0, 1.0);
+        let model = Transform::default();
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+        };
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                lighting_shader.use_program();
+                lighting_shader.set_vec3(&CString::new("objectColor")?, 1.0, 0.5, 0.31);
+                lighting_shader.set_vector3(&CString::new("lightColor")?, &light_color);
+                lighting_shader.set_vector3(&CString::new("lightPos")?, &light_pos);
+
+                lighting_shader.set_mat4(&CString::new("projection")?, &projection);
+                lighting_shader.set_mat4(&CString::new("view")?, &view);
+                lighting_shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                // the normal matrix keeps normals perpendicular under non-uniform scaling
+                lighting_shader.set_mat3(&CString::new("normalMatrix")?, &model.normal_matrix());
+
+                gl::BindVertexArray(cube_vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+
+                // the lamp: a small cube at the light position
+                let lamp = Transform {
+                    translation: light_pos,
+                    ..Transform::from_scale(0.2)
+                };
+                light_cube_shader.use_program();
+                light_cube_shader.set_vector3(&CString::new("lightColor")?, &light_color);
+                light_cube_shader.set_mat4(&CString::new("projection")?, &projection);
+                light_cube_shader.set_mat4(&CString::new("view")?, &view);
+                light_cube_shader.set_mat4(&CString::new("model")?, &lamp.to_matrix());
+
+                gl::BindVertexArray(light_cube_vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &light_cube_vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
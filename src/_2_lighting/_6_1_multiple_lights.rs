@@ -0,0 +1,395 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::Result;
+use cgmath::{vec3, Deg, EuclideanSpace, Point3, Vector3};
+use gl::types::{GLfloat, GLsizei, GLsizeiptr, GLuint};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    lighting::{
+        upload_array, Attenuation, DirectionalLight, LightColor, LightLimits, MapMaterial,
+        PointLight, SpotLight, UniformStruct,
+    },
+    projection::aspect_ratio,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// the spot light following the camera, toggled with F
+    flashlight: bool,
+}
+
+/// colours of the lamps circling the containers
+const POINT_LIGHT_COLORS: [Vector3<f32>; 4] = [
+    vec3(1.0, 0.6, 0.0),
+    vec3(1.0, 0.0, 0.0),
+    vec3(1.0, 1.0, 0.0),
+    vec3(0.2, 0.2, 1.0),
+];
+
+pub fn main_2_6_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        // the light arrays are sized by the defines, uploading more lights than that fails
+        let limits = LightLimits::default();
+        let lighting_shader = Shader::with_defines(
+            "src/_2_lighting/shaders/4.1.lighting_maps.vs",
+            "src/_2_lighting/shaders/6.multiple_lights.fs",
+            &limits.defines(),
+        )?;
+        let light_cube_shader = Shader::new(
+            "src/_2_lighting/shaders/1.light_cube.vs",
+            "src/_2_lighting/shaders/1.light_cube.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let vertices: [f32; 288] = [
+            // positions       // normals        // texture coords
+            -0.5, -0.5, -0.5,  0.0,  0.0, -1.0,  0.0, 0.0,
+             0.5, -0.5, -0.5,  0.0,  0.0, -1.0,  1.0, 0.0,
+             0.5,  0.5, -0.5,  0.0,  0.0, -1.0,  1.0, 1.0,
+             0.5,  0.5, -0.5,  0.0,  0.0, -1.0,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0,  0.0, -1.0,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0,  0.0, -1.0,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0,  0.0,  1.0,  0.0, 0.0,
+             0.5, -0.5,  0.5,  0.0,  0.0,  1.0,  1.0, 0.0,
+             0.5,  0.5,  0.5,  0.0,  0.0,  1.0,  1.0, 1.0,
+             0.5,  0.5,  0.5,  0.0,  0.0,  1.0,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0,  0.0,  1.0,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0,  0.0,  1.0,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5, -1.0,  0.0,  0.0,  1.0, 0.0,
+            -0.5,  0.5, -0.5, -1.0,  0.0,  0.0,  1.0, 1.0,
+            -0.5, -0.5, -0.5, -1.0,  0.0,  0.0,  0.0, 1.0,
+            -0.5, -0.5, -0.5, -1.0,  0.0,  0.0,  0.0, 1.0,
+            -0.5, -0.5,  0.5, -1.0,  0.0,  0.0,  0.0, 0.0,
+            -0.5,  0.5,  0.5, -1.0,  0.0,  0.0,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0,  0.0,  0.0,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0,  0.0,  0.0,  1.0, 1.0,
+             0.5, -0.5, -0.5,  1.0,  0.0,  0.0,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0,  0.0,  0.0,  0.0, 1.0,
+             0.5, -0.5,  0.5,  1.0,  0.0,  0.0,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0,  0.0,  0.0,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, -1.0,  0.0,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, -1.0,  0.0,  1.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, -1.0,  0.0,  1.0, 0.0,
+             0.5, -0.5,  0.5,  0.0, -1.0,  0.0,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, -1.0,  0.0,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, -1.0,  0.0,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0,  1.0,  0.0,  0.0, 1.0,
+             0.5,  0.5, -0.5,  0.0,  1.0,  0.0,  1.0, 1.0,
+             0.5,  0.5,  0.5,  0.0,  1.0,  0.0,  1.0, 0.0,
+             0.5,  0.5,  0.5,  0.0,  1.0,  0.0,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0,  1.0,  0.0,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0,  1.0,  0.0,  0.0, 1.0,
+        ];
+
+        // the lit cube and the lamp share the vertex buffer, the lamp only needs positions
+        let mut vbo: GLuint = 0;
+        let mut cube_vao: GLuint = 0;
+        let mut light_cube_vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut cube_vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&vertices) as GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            let stride = 8 * mem::size_of::<GLfloat>() as GLsizei;
+
+            gl::BindVertexArray(cube_vao);
+            // position
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            // normal
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(1);
+            // texture coords
+            gl::VertexAttribPointer(
+                2,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (6 * mem::size_of::<GLfloat>()) as *const c_void,
+            );
+            gl::EnableVertexAttribArray(2);
+
+            gl::GenVertexArrays(1, &mut light_cube_vao);
+            gl::BindVertexArray(light_cube_vao);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+        }
+
+        // world space positions of our cubes
+        let cube_positions: [Vector3<f32>; 10] = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(2.0, 5.0, -15.0),
+            vec3(-1.5, -2.2, -2.5),
+            vec3(-3.8, -2.0, -12.3),
+            vec3(2.4, -0.4, -3.5),
+            vec3(-1.7, 3.0, -7.5),
+            vec3(1.3, -2.0, -2.5),
+            vec3(1.5, 2.0, -2.5),
+            vec3(1.5, 0.2, -1.5),
+            vec3(-1.3, 1.0, -1.5),
+        ];
+
+        let dir_light = DirectionalLight {
+            direction: vec3(-0.2, -1.0, -0.3),
+            color: LightColor {
+                ambient: vec3(0.05, 0.05, 0.05),
+                diffuse: vec3(0.4, 0.4, 0.4),
+                specular: vec3(0.5, 0.5, 0.5),
+            },
+        };
+        let material = MapMaterial {
+            diffuse: Texture2D::from_path(
+                "resources/textures/container2.png",
+                TextureOptions::default(),
+            )?,
+            specular: Texture2D::from_path(
+                "resources/textures/container2_specular.png",
+                TextureOptions::default(),
+            )?,
+            emission: Texture2D::solid([0, 0, 0, 255])?,
+            shininess: 32.0,
+        };
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            flashlight: true,
+        };
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            let point_lights = point_lights(current_frame);
+            let spot_lights = if input.flashlight {
+                vec![SpotLight {
+                    position: input.camera.position.to_vec(),
+                    direction: input.camera.front,
+                    inner_cut_off: Deg(12.5),
+                    outer_cut_off: Deg(15.0),
+                    attenuation: Attenuation::for_range(50.0),
+                    color: LightColor {
+                        ambient: vec3(0.0, 0.0, 0.0),
+                        diffuse: vec3(1.0, 1.0, 1.0),
+                        specular: vec3(1.0, 1.0, 1.0),
+                    },
+                }]
+            } else {
+                vec![]
+            };
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                lighting_shader.use_program();
+                dir_light.upload(&lighting_shader, "dirLight")?;
+                upload_array(
+                    &lighting_shader,
+                    "pointLights",
+                    &point_lights,
+                    limits.max_point_lights,
+                )?;
+                upload_array(
+                    &lighting_shader,
+                    "spotLights",
+                    &spot_lights,
+                    limits.max_spot_lights,
+                )?;
+                material.upload(&lighting_shader, "material")?;
+                lighting_shader
+                    .set_vector3(&CString::new("viewPos")?, &input.camera.position.to_vec());
+
+                lighting_shader.set_mat4(&CString::new("projection")?, &projection);
+                lighting_shader.set_mat4(&CString::new("view")?, &view);
+
+                gl::BindVertexArray(cube_vao);
+                for (i, position) in cube_positions.iter().enumerate() {
+                    let angle = 20.0 * i as f32;
+                    let model = Transform {
+                        translation: *position,
+                        ..Transform::from_axis_angle(vec3(1.0, 0.3, 0.5), Deg(angle))
+                    };
+                    lighting_shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    lighting_shader
+                        .set_mat3(&CString::new("normalMatrix")?, &model.normal_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+
+                // a lamp for every point light
+                light_cube_shader.use_program();
+                light_cube_shader.set_mat4(&CString::new("projection")?, &projection);
+                light_cube_shader.set_mat4(&CString::new("view")?, &view);
+
+                gl::BindVertexArray(light_cube_vao);
+                for (light, color) in point_lights.iter().zip(POINT_LIGHT_COLORS) {
+                    let lamp = Transform {
+                        translation: light.position,
+                        ..Transform::from_scale(0.2)
+                    };
+                    light_cube_shader.set_vector3(&CString::new("lightColor")?, &color);
+                    light_cube_shader.set_mat4(&CString::new("model")?, &lamp.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &light_cube_vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::F, _, Action::Press, _) => {
+                input.flashlight = !input.flashlight;
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// the point lights at `time`, each circling its own spot between the containers
+fn point_lights(time: f32) -> Vec<PointLight> {
+    let centers = [
+        vec3(0.7, 0.2, 2.0),
+        vec3(2.3, -3.3, -4.0),
+        vec3(-4.0, 2.0, -12.0),
+        vec3(0.0, 0.0, -3.0),
+    ];
+    centers
+        .iter()
+        .zip(POINT_LIGHT_COLORS)
+        .enumerate()
+        .map(|(i, (center, color))| {
+            // offset every light so they don't move in lockstep
+            let phase = time * (0.5 + 0.25 * i as f32) + i as f32;
+            PointLight {
+                position: center + vec3(phase.cos(), (phase * 0.7).sin() * 0.5, phase.sin()),
+                attenuation: Attenuation::for_range(13.0),
+                color: LightColor {
+                    ambient: color * 0.1,
+                    diffuse: color,
+                    specular: color,
+                },
+            }
+        })
+        .collect()
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
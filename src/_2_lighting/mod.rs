@@ -0,0 +1,18 @@
+// lighting chapter
+pub mod _1_1_colors;
+pub mod _2_1_basic_lighting_diffuse;
+pub mod _2_2_basic_lighting_specular;
+pub mod _2_3_basic_lighting_gouraud;
+pub mod _3_1_materials;
+pub mod _4_1_lighting_maps_diffuse;
+pub mod _4_2_lighting_maps_specular_emission;
+pub mod _6_1_multiple_lights;
+
+pub use _1_1_colors::*;
+pub use _2_1_basic_lighting_diffuse::*;
+pub use _2_2_basic_lighting_specular::*;
+pub use _2_3_basic_lighting_gouraud::*;
+pub use _3_1_materials::*;
+pub use _4_1_lighting_maps_diffuse::*;
+pub use _4_2_lighting_maps_specular_emission::*;
+pub use _6_1_multiple_lights::*;
@@ -0,0 +1,10 @@
+#version 330 core
+out vec4 FragColor;
+
+uniform vec3 lightColor;
+
+void main()
+{
+    // the lamp is drawn unlit in the colour of its light
+    FragColor = vec4(lightColor, 1.0);
+}
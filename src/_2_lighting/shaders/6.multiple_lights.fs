@@ -0,0 +1,131 @@
+#version 330 core
+out vec4 FragColor;
+
+// MAX_POINT_LIGHTS and MAX_SPOT_LIGHTS are defined by the application
+
+struct Material {
+    sampler2D diffuse;
+    sampler2D specular;
+    sampler2D emission;
+    float shininess;
+};
+
+struct DirLight {
+    vec3 direction;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct PointLight {
+    vec3 position;
+
+    float constant;
+    float linear;
+    float quadratic;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct SpotLight {
+    vec3 position;
+    vec3 direction;
+    float cutOff;
+    float outerCutOff;
+
+    float constant;
+    float linear;
+    float quadratic;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+
+uniform vec3 viewPos;
+uniform Material material;
+uniform DirLight dirLight;
+uniform PointLight pointLights[MAX_POINT_LIGHTS];
+uniform int pointLightsCount;
+uniform SpotLight spotLights[MAX_SPOT_LIGHTS];
+uniform int spotLightsCount;
+
+vec3 CalcDirLight(DirLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor);
+vec3 CalcPointLight(PointLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor);
+vec3 CalcSpotLight(SpotLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor);
+
+void main()
+{
+    vec3 norm = normalize(Normal);
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec3 color = texture(material.diffuse, TexCoords).rgb;
+    vec3 specularColor = texture(material.specular, TexCoords).rgb;
+
+    // phase 1: directional lighting
+    vec3 result = CalcDirLight(dirLight, norm, viewDir, color, specularColor);
+    // phase 2: point lights
+    for (int i = 0; i < pointLightsCount; i++)
+        result += CalcPointLight(pointLights[i], norm, viewDir, color, specularColor);
+    // phase 3: spot lights
+    for (int i = 0; i < spotLightsCount; i++)
+        result += CalcSpotLight(spotLights[i], norm, viewDir, color, specularColor);
+    // emission
+    result += texture(material.emission, TexCoords).rgb;
+
+    FragColor = vec4(result, 1.0);
+}
+
+// phong terms shared by every light type, without attenuation
+vec3 CalcPhong(vec3 lightDir, vec3 ambientColor, vec3 diffuseColor, vec3 specularLight,
+               vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor, out vec3 ambient)
+{
+    float diff = max(dot(normal, lightDir), 0.0);
+    vec3 reflectDir = reflect(-lightDir, normal);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
+    ambient = ambientColor * color;
+    return diffuseColor * diff * color + specularLight * spec * specularColor;
+}
+
+float Attenuate(float constant, float linear, float quadratic, vec3 position)
+{
+    float distance = length(position - FragPos);
+    return 1.0 / (constant + linear * distance + quadratic * (distance * distance));
+}
+
+vec3 CalcDirLight(DirLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor)
+{
+    vec3 ambient;
+    vec3 lit = CalcPhong(normalize(-light.direction), light.ambient, light.diffuse,
+                         light.specular, normal, viewDir, color, specularColor, ambient);
+    return ambient + lit;
+}
+
+vec3 CalcPointLight(PointLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor)
+{
+    vec3 ambient;
+    vec3 lit = CalcPhong(normalize(light.position - FragPos), light.ambient, light.diffuse,
+                         light.specular, normal, viewDir, color, specularColor, ambient);
+    float attenuation = Attenuate(light.constant, light.linear, light.quadratic, light.position);
+    return (ambient + lit) * attenuation;
+}
+
+vec3 CalcSpotLight(SpotLight light, vec3 normal, vec3 viewDir, vec3 color, vec3 specularColor)
+{
+    vec3 lightDir = normalize(light.position - FragPos);
+    vec3 ambient;
+    vec3 lit = CalcPhong(lightDir, light.ambient, light.diffuse, light.specular, normal,
+                         viewDir, color, specularColor, ambient);
+    float attenuation = Attenuate(light.constant, light.linear, light.quadratic, light.position);
+    // soft edge between the inner and the outer cone
+    float theta = dot(lightDir, normalize(-light.direction));
+    float epsilon = light.cutOff - light.outerCutOff;
+    float intensity = clamp((theta - light.outerCutOff) / epsilon, 0.0, 1.0);
+    return (ambient + lit * intensity) * attenuation;
+}
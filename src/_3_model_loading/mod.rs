@@ -0,0 +1,6 @@
+// model loading chapter
+pub mod _1_1_model_loading;
+pub mod _1_2_model_loading_gltf;
+
+pub use _1_1_model_loading::*;
+pub use _1_2_model_loading_gltf::*;
@@ -0,0 +1,50 @@
+#version 330 core
+out vec4 FragColor;
+
+struct Material {
+    sampler2D diffuse;
+    sampler2D specular;
+    sampler2D emission;
+    float shininess;
+};
+
+struct DirLight {
+    vec3 direction;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+
+uniform vec3 viewPos;
+uniform Material material;
+uniform DirLight dirLight;
+
+void main()
+{
+    vec3 color = texture(material.diffuse, TexCoords).rgb;
+    vec3 norm = normalize(Normal);
+    vec3 lightDir = normalize(-dirLight.direction);
+
+    // ambient
+    vec3 ambient = dirLight.ambient * color;
+
+    // diffuse
+    float diff = max(dot(norm, lightDir), 0.0);
+    vec3 diffuse = dirLight.diffuse * diff * color;
+
+    // specular
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec3 reflectDir = reflect(-lightDir, norm);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
+    vec3 specular = dirLight.specular * spec * texture(material.specular, TexCoords).rgb;
+
+    // emission
+    vec3 emission = texture(material.emission, TexCoords).rgb;
+
+    FragColor = vec4(ambient + diffuse + specular + emission, 1.0);
+}
@@ -0,0 +1,120 @@
+use std::mem;
+
+use anyhow::Result;
+use cgmath::{vec2, Vector2};
+use gl::types::*;
+use glfw::{fail_on_errors, Action, Context, GlfwReceiver, Key, WindowHint, WindowMode};
+
+use crate::{
+    shaders::Shader,
+    vertex_layout::{AttributeType, InstanceBuffer, VertexLayout},
+};
+
+pub fn main_4_10_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(800, 600, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/10.1.instancing.vs",
+            "src/_4_advanced_opengl/shaders/10.1.instancing.fs",
+        )?;
+
+        // a 10x10 grid of offsets, one per instance
+        let offset = 0.1;
+        let translations: Vec<Vector2<f32>> = (-10..10)
+            .step_by(2)
+            .flat_map(|y| {
+                (-10..10)
+                    .step_by(2)
+                    .map(move |x| vec2(x as f32 / 10.0 + offset, y as f32 / 10.0 + offset))
+            })
+            .collect();
+        let instances = InstanceBuffer::new(
+            &translations,
+            VertexLayout::packed(&[(2, AttributeType::Vec2)], 1),
+        )?;
+
+        #[rustfmt::skip]
+        let quad_vertices: [f32; 30] = [
+            // positions   colors
+            -0.05,  0.05,  1.0, 0.0, 0.0,
+             0.05, -0.05,  0.0, 1.0, 0.0,
+            -0.05, -0.05,  0.0, 0.0, 1.0,
+
+            -0.05,  0.05,  1.0, 0.0, 0.0,
+             0.05, -0.05,  0.0, 1.0, 0.0,
+             0.05,  0.05,  0.0, 1.0, 1.0,
+        ];
+
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&quad_vertices) as GLsizeiptr,
+                quad_vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            VertexLayout::packed(&[(0, AttributeType::Vec2), (1, AttributeType::Vec3)], 0).apply();
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+            // the offsets come from their own buffer, advancing once per quad
+            instances.attach(vao);
+        }
+
+        while !window.should_close() {
+            process_events(&mut window, &events);
+
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+
+                shader.use_program();
+                gl::BindVertexArray(vao);
+                // 100 quads in one draw call
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, instances.len() as GLsizei);
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(window: &mut glfw::Window, events: &GlfwReceiver<(f64, glfw::WindowEvent)>) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::{ffi::CString, mem};
+
+use anyhow::{Context, Result};
+use cgmath::{vec3, Deg, Matrix4, Point3, SquareMatrix};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, SwapInterval,
+    WindowHint, WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    model::Model,
+    projection::aspect_ratio,
+    shaders::Shader,
+    transform::Transform,
+    vertex_layout::{AttributeType, InstanceBuffer, VertexLayout},
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// rocks in the ring unless a count is given after the tutorial id
+const DEFAULT_AMOUNT: usize = 100_000;
+const RING_RADIUS: f32 = 150.0;
+/// how far the rocks scatter around the ring
+const RING_OFFSET: f32 = 25.0;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// one instanced draw call per mesh instead of a draw call per rock
+    instanced: bool,
+}
+
+/// xorshift, plenty for scattering rocks and the same field on every run
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// uniform in [low, high)
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * (self.next() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+pub fn main_4_10_3() -> Result<()> {
+    let amount = match std::env::args().nth(2) {
+        Some(amount) => amount
+            .parse::<usize>()
+            .with_context(|| format!("invalid rock count {:?}", amount))?,
+        None => DEFAULT_AMOUNT,
+    };
+
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        // no vsync, otherwise both ways of drawing show the refresh interval
+        glfw.set_swap_interval(SwapInterval::None);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/10.3.asteroids.vs",
+            "src/_4_advanced_opengl/shaders/10.3.asteroids.fs",
+        )?;
+
+        let rock = Model::from_path("resources/objects/rock/rock.obj")?;
+        let planet = Model::from_path("resources/objects/planet/planet.obj")?;
+        let planet_transform = Transform {
+            translation: vec3(0.0, -3.0, 0.0),
+            ..Transform::from_scale(4.0)
+        };
+
+        // a ring of randomly displaced, scaled and rotated rocks around the planet
+        let mut rng = Rng(0x2545_f491);
+        let model_matrices: Vec<Matrix4<f32>> = (0..amount)
+            .map(|index| {
+                let angle = index as f32 / amount as f32 * std::f32::consts::TAU;
+                let mut displacement = || rng.range(-RING_OFFSET, RING_OFFSET);
+                let x = angle.sin() * RING_RADIUS + displacement();
+                // keep the field flatter than it is wide
+                let y = displacement() * 0.4;
+                let z = angle.cos() * RING_RADIUS + displacement();
+                let scale = rng.range(0.05, 0.25);
+                let rotation = rng.range(0.0, 360.0);
+                Transform {
+                    translation: vec3(x, y, z),
+                    scale: vec3(scale, scale, scale),
+                    ..Transform::from_axis_angle(vec3(0.4, 0.6, 0.8), Deg(rotation))
+                }
+                .to_matrix()
+            })
+            .collect();
+        let instances = InstanceBuffer::new(
+            &model_matrices,
+            VertexLayout::per_instance(mem::size_of::<Matrix4<f32>>()).with(
+                3,
+                AttributeType::Mat4,
+                0,
+            ),
+        )?;
+        unsafe {
+            rock.add_instance_buffer(&instances);
+        }
+
+        let mut camera = Camera::new(Point3::new(0.0, 0.0, 155.0));
+        camera.far = 1000.0;
+        camera.movement_speed = 20.0;
+        let mut input = InputState {
+            camera,
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            instanced: true,
+        };
+        println!(
+            "{} rocks, press I to switch between instanced and naive draws",
+            amount
+        );
+
+        // average frame time, reported in the title once a second
+        let mut last_report = glfw.get_time() as f32;
+        let mut counter_frames = 0;
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("projection")?, &projection);
+                shader.set_mat4(&CString::new("view")?, &view);
+
+                let instanced = CString::new("instanced")?;
+                shader.set_bool(&instanced, false);
+                planet.draw(&shader, "material", &planet_transform.to_matrix())?;
+
+                if input.instanced {
+                    shader.set_bool(&instanced, true);
+                    rock.draw_instanced(
+                        &shader,
+                        "material",
+                        &Matrix4::identity(),
+                        instances.len(),
+                    )?;
+                } else {
+                    for model in &model_matrices {
+                        rock.draw(&shader, "material", model)?;
+                    }
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+
+            counter_frames += 1;
+            let elapsed = glfw.get_time() as f32 - last_report;
+            if elapsed >= 1.0 {
+                window.set_title(&format!(
+                    "LearnOpenGL - {} rocks, {}: {:.2} ms per frame",
+                    amount,
+                    if input.instanced {
+                        "instanced"
+                    } else {
+                        "naive"
+                    },
+                    elapsed * 1000.0 / counter_frames as f32
+                ));
+                counter_frames = 0;
+                last_report += elapsed;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::I, _, Action::Press, _) => {
+                input.instanced = !input.instanced;
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
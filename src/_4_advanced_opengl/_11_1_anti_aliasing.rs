@@ -0,0 +1,316 @@
+use std::{ffi::CString, fmt, mem};
+
+use anyhow::{bail, Context, Result};
+use cgmath::{vec3, Deg, InnerSpace, Matrix4, Point3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
+};
+
+use crate::{
+    camera::Camera,
+    framebuffer::{self, AttachmentStorage, Framebuffer, FramebufferDesc},
+    projection::aspect_ratio,
+    shaders::Shader,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// samples per pixel unless a count is given after the tutorial id
+const DEFAULT_SAMPLES: u32 = 4;
+
+/// where the cube is rendered and how its samples end up in the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolve {
+    /// straight into the window, which is multisampled through the `Samples` hint
+    Window,
+    /// straight into the window with GL_MULTISAMPLE disabled
+    Aliased,
+    /// into a multisampled framebuffer, resolved with glBlitFramebuffer
+    Blit,
+    /// into a multisampled framebuffer, averaged by a shader reading the samples
+    Custom,
+    /// like `Custom`, but only the first sample of every pixel is used
+    FirstSample,
+}
+
+impl fmt::Display for Resolve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Resolve::Window => "multisampled window",
+            Resolve::Aliased => "window without multisampling",
+            Resolve::Blit => "off-screen, resolved by a blit",
+            Resolve::Custom => "off-screen, resolved by a shader",
+            Resolve::FirstSample => "off-screen, first sample only",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// keys selecting the resolve modes
+const RESOLVE_KEYS: [(Key, Resolve); 5] = [
+    (Key::Num1, Resolve::Window),
+    (Key::Num2, Resolve::Aliased),
+    (Key::Num3, Resolve::Blit),
+    (Key::Num4, Resolve::Custom),
+    (Key::Num5, Resolve::FirstSample),
+];
+
+/// everything the event handler changes
+struct InputState {
+    framebuffer_size: (i32, i32),
+    resolve: Resolve,
+    /// of the off-screen target, the window keeps the count it was created with
+    samples: u32,
+}
+
+/// the sample count can be given after the tutorial id, e.g. `4_11_1 8`. the number keys
+/// switch between the ways of resolving, up and down double and halve the samples of the
+/// off-screen target
+pub fn main_4_11_1() -> Result<()> {
+    let samples = match std::env::args().nth(2) {
+        Some(samples) => samples
+            .parse::<u32>()
+            .with_context(|| format!("invalid sample count {:?}", samples))?,
+        None => DEFAULT_SAMPLES,
+    };
+    if samples == 0 {
+        bail!("the sample count has to be at least 1");
+    }
+
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    // a multisampled window, the driver may pick another count
+    glfw.window_hint(WindowHint::Samples(Some(samples)));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let max_samples = unsafe { framebuffer::max_samples() };
+        framebuffer::validate_samples(samples, max_samples).context("choosing the sample count")?;
+        let mut window_samples = 0;
+        unsafe {
+            gl::GetIntegerv(gl::SAMPLES, &mut window_samples);
+        }
+        println!(
+            "the window has {} samples per pixel, off-screen targets can have up to {}",
+            window_samples, max_samples
+        );
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/11.1.anti_aliasing.vs",
+            "src/_4_advanced_opengl/shaders/11.1.anti_aliasing.fs",
+        )?;
+        // copies the blit-resolved target into the window
+        let copy_shader = Shader::new("src/shaders/fullscreen.vs", "src/shaders/post_process.fs")?;
+        let resolve_shader = Shader::new(
+            "src/shaders/fullscreen.vs",
+            "src/_4_advanced_opengl/shaders/11.1.custom_resolve.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 108] = [
+            // positions
+            -0.5, -0.5, -0.5,   0.5, -0.5, -0.5,   0.5,  0.5, -0.5,
+             0.5,  0.5, -0.5,  -0.5,  0.5, -0.5,  -0.5, -0.5, -0.5,
+
+            -0.5, -0.5,  0.5,   0.5, -0.5,  0.5,   0.5,  0.5,  0.5,
+             0.5,  0.5,  0.5,  -0.5,  0.5,  0.5,  -0.5, -0.5,  0.5,
+
+            -0.5,  0.5,  0.5,  -0.5,  0.5, -0.5,  -0.5, -0.5, -0.5,
+            -0.5, -0.5, -0.5,  -0.5, -0.5,  0.5,  -0.5,  0.5,  0.5,
+
+             0.5,  0.5,  0.5,   0.5,  0.5, -0.5,   0.5, -0.5, -0.5,
+             0.5, -0.5, -0.5,   0.5, -0.5,  0.5,   0.5,  0.5,  0.5,
+
+            -0.5, -0.5, -0.5,   0.5, -0.5, -0.5,   0.5, -0.5,  0.5,
+             0.5, -0.5,  0.5,  -0.5, -0.5,  0.5,  -0.5, -0.5, -0.5,
+
+            -0.5,  0.5, -0.5,   0.5,  0.5, -0.5,   0.5,  0.5,  0.5,
+             0.5,  0.5,  0.5,  -0.5,  0.5,  0.5,  -0.5,  0.5, -0.5,
+        ];
+
+        let mut cube_vao: GLuint = 0;
+        let mut cube_vbo: GLuint = 0;
+        // empty, the full screen passes build their triangle from the vertex id
+        let mut screen_vao: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut cube_vao);
+            gl::GenBuffers(1, &mut cube_vbo);
+            gl::BindVertexArray(cube_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, cube_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&cube_vertices) as GLsizeiptr,
+                cube_vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            VertexLayout::packed(&[(0, AttributeType::Vec3)], 0).apply();
+            gl::BindVertexArray(0);
+            gl::GenVertexArrays(1, &mut screen_vao);
+
+            copy_shader.use_program();
+            copy_shader.set_int(&CString::new("screenTexture")?, 0);
+            copy_shader.set_int(&CString::new("effect")?, 0);
+            resolve_shader.use_program();
+            resolve_shader.set_int(&CString::new("screenTexture")?, 0);
+        }
+
+        let mut input = InputState {
+            framebuffer_size: window.get_framebuffer_size(),
+            resolve: Resolve::Window,
+            samples,
+        };
+        let (width, height) = input.framebuffer_size;
+        let mut multisampled = FramebufferDesc::new()
+            .with_color(gl::RGBA8)
+            .with_depth_stencil(gl::DEPTH24_STENCIL8, AttachmentStorage::Renderbuffer)
+            .with_samples(samples)
+            .build(width, height)?;
+        // the blit target, multisampled images can't be drawn to the window directly
+        let mut resolved = FramebufferDesc::new()
+            .with_color(gl::RGBA8)
+            .build(width, height)?;
+        print_state(&input);
+
+        let camera = Camera::new(Point3::new(0.0, 0.0, 3.0));
+
+        while !window.should_close() {
+            let previous = (input.resolve, input.samples);
+            process_events(&mut window, &events, &mut input);
+            if input.samples != multisampled.desc().samples {
+                // an unsupported count keeps the previous one
+                if let Err(error) = unsafe { multisampled.set_samples(input.samples) } {
+                    println!("{:#}", error);
+                    input.samples = multisampled.desc().samples;
+                }
+            }
+            if (input.resolve, input.samples) != previous {
+                print_state(&input);
+            }
+
+            let (width, height) = input.framebuffer_size;
+            let view = camera.view_matrix();
+            let projection = camera.projection_matrix(aspect_ratio(width, height));
+            let model = Matrix4::from_axis_angle(
+                vec3(0.5, 1.0, 0.0).normalize(),
+                Deg(glfw.get_time() as f32 * 20.0),
+            );
+
+            // NOTE: start rendering process here
+            unsafe {
+                multisampled.resize(width, height)?;
+                resolved.resize(width, height)?;
+
+                match input.resolve {
+                    Resolve::Window => {
+                        Framebuffer::bind_default(input.framebuffer_size);
+                        gl::Enable(gl::MULTISAMPLE);
+                    }
+                    Resolve::Aliased => {
+                        Framebuffer::bind_default(input.framebuffer_size);
+                        gl::Disable(gl::MULTISAMPLE);
+                    }
+                    Resolve::Blit | Resolve::Custom | Resolve::FirstSample => {
+                        multisampled.bind();
+                        gl::Enable(gl::MULTISAMPLE);
+                    }
+                }
+                gl::Enable(gl::DEPTH_TEST);
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("projection")?, &projection);
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("model")?, &model);
+                gl::BindVertexArray(cube_vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+
+                // the off-screen modes finish with a full screen pass into the window
+                gl::Disable(gl::DEPTH_TEST);
+                gl::BindVertexArray(screen_vao);
+                match input.resolve {
+                    Resolve::Window | Resolve::Aliased => {}
+                    Resolve::Blit => {
+                        multisampled.blit_to(&resolved, gl::COLOR_BUFFER_BIT, gl::NEAREST);
+                        Framebuffer::bind_default(input.framebuffer_size);
+                        copy_shader.use_program();
+                        resolved.bind_color_texture(0, 0);
+                        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+                    }
+                    Resolve::Custom | Resolve::FirstSample => {
+                        Framebuffer::bind_default(input.framebuffer_size);
+                        resolve_shader.use_program();
+                        let sample_count = match input.resolve {
+                            Resolve::FirstSample => 1,
+                            _ => multisampled.desc().samples as i32,
+                        };
+                        resolve_shader.set_int(&CString::new("sampleCount")?, sample_count);
+                        multisampled.bind_color_texture(0, 0);
+                        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+                    }
+                }
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &screen_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+        }
+    }
+    Ok(())
+}
+
+fn print_state(input: &InputState) {
+    match input.resolve {
+        Resolve::Window | Resolve::Aliased => println!("{}", input.resolve),
+        _ => println!("{}, {} samples", input.resolve, input.samples),
+    }
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // the viewport is set from this every frame, when the target is bound
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::Up, _, Action::Press, _) => {
+                // checked against GL_MAX_SAMPLES when the target is rebuilt
+                input.samples = (input.samples * 2).max(1);
+            }
+            glfw::WindowEvent::Key(Key::Down, _, Action::Press, _) => {
+                input.samples = (input.samples / 2).max(1);
+            }
+            glfw::WindowEvent::Key(key, _, Action::Press, _) => {
+                if let Some((_, resolve)) = RESOLVE_KEYS.iter().find(|(k, _)| *k == key) {
+                    input.resolve = *resolve;
+                }
+            }
+            _ => {}
+        }
+    }
+}
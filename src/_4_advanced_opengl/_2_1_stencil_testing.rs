@@ -0,0 +1,304 @@
+use std::{ffi::CString, mem};
+
+use anyhow::Result;
+use cgmath::{vec3, Matrix4, Point3, SquareMatrix, Vector3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    projection::aspect_ratio,
+    render_state::{DepthState, StencilState},
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// how much larger the outline pass draws the selected objects
+const OUTLINE_SCALE: f32 = 1.1;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// which containers are outlined, toggled with 1 and 2
+    selected: [bool; 2],
+}
+
+pub fn main_4_2_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    // the default framebuffer needs a stencil attachment for the outlines
+    glfw.window_hint(WindowHint::DepthBits(Some(24)));
+    glfw.window_hint(WindowHint::StencilBits(Some(8)));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/2.stencil_testing.vs",
+            "src/_4_advanced_opengl/shaders/2.stencil_testing.fs",
+        )?;
+        let single_color_shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/2.stencil_testing.vs",
+            "src/_4_advanced_opengl/shaders/2.stencil_single_color.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+        // texture coords above 1 repeat the floor texture
+        #[rustfmt::skip]
+        let plane_vertices: [f32; 30] = [
+            // positions       // texture coords
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5,  5.0,  0.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+             5.0, -0.5, -5.0,  2.0, 2.0,
+        ];
+
+        let (cube_vao, cube_vbo) = unsafe { textured_vertex_array(&cube_vertices) };
+        let (plane_vao, plane_vbo) = unsafe { textured_vertex_array(&plane_vertices) };
+
+        let cube_texture =
+            Texture2D::from_path("resources/textures/marble.jpg", TextureOptions::default())?;
+        let floor_texture =
+            Texture2D::from_path("resources/textures/metal.png", TextureOptions::default())?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+            single_color_shader.use_program();
+            single_color_shader.set_vec3(&CString::new("outlineColor")?, 0.04, 0.28, 0.26);
+        }
+
+        let cube_positions: [Vector3<f32>; 2] = [vec3(-1.0, 0.0, -1.0), vec3(2.0, 0.0, 0.0)];
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            selected: [true, true],
+        };
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            unsafe {
+                // the stencil write mask also applies to glClear, so reset it first
+                DepthState::default().apply();
+                StencilState::disabled().apply();
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+
+                for program in [&shader, &single_color_shader] {
+                    program.use_program();
+                    program.set_mat4(&CString::new("view")?, &view);
+                    program.set_mat4(&CString::new("projection")?, &projection);
+                }
+
+                // the floor must not end up in the stencil buffer
+                shader.use_program();
+                StencilState::keep().apply();
+                gl::BindVertexArray(plane_vao);
+                floor_texture.bind(0);
+                shader.set_mat4(&CString::new("model")?, &Matrix4::identity());
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+                // draw the containers, marking the fragments of the selected ones with 1
+                gl::BindVertexArray(cube_vao);
+                cube_texture.bind(0);
+                for (position, selected) in cube_positions.iter().zip(input.selected) {
+                    let state = if selected {
+                        StencilState::write(1)
+                    } else {
+                        StencilState::keep()
+                    };
+                    state.apply();
+                    let model = Transform::from_translation(*position);
+                    shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+
+                // draw slightly scaled up versions of the selected containers in a single
+                // colour, only the border outside the marked fragments passes the stencil test.
+                // the depth test is off so the outline shows through the floor and other cubes
+                single_color_shader.use_program();
+                StencilState::not_equal(1).apply();
+                DepthState::disabled().apply();
+                for (position, selected) in cube_positions.iter().zip(input.selected) {
+                    if !selected {
+                        continue;
+                    }
+                    let model = Transform {
+                        translation: *position,
+                        ..Transform::from_scale(OUTLINE_SCALE)
+                    };
+                    single_color_shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &plane_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+            gl::DeleteBuffers(1, &plane_vbo);
+        }
+    }
+    Ok(())
+}
+
+/// upload interleaved position + texture coordinate vertices, returns the vao and vbo
+unsafe fn textured_vertex_array(vertices: &[f32]) -> (GLuint, GLuint) {
+    let mut vao: GLuint = 0;
+    let mut vbo: GLuint = 0;
+    gl::GenVertexArrays(1, &mut vao);
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindVertexArray(vao);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        mem::size_of_val(vertices) as GLsizeiptr,
+        vertices.as_ptr() as *const _,
+        gl::STATIC_DRAW,
+    );
+    VertexLayout::packed(&[(0, AttributeType::Vec3), (1, AttributeType::Vec2)], 0).apply();
+    gl::BindVertexArray(0);
+    (vao, vbo)
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::Num1, _, Action::Press, _) => {
+                input.selected[0] = !input.selected[0];
+            }
+            glfw::WindowEvent::Key(Key::Num2, _, Action::Press, _) => {
+                input.selected[1] = !input.selected[1];
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::{ffi::CString, mem};
+
+use anyhow::Result;
+use cgmath::{point3, vec3, EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    draw_queue::DrawQueue,
+    gl_state::GlState,
+    projection::aspect_ratio,
+    render_state::{BlendState, DepthState},
+    sampler::SamplerDesc,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// whether the windows are sorted back to front, toggled with T to show what goes wrong
+    /// without it
+    sort_transparent: bool,
+}
+
+/// everything needed to issue one draw call of the scene
+struct Draw<'a> {
+    vao: GLuint,
+    vertex_count: GLsizei,
+    texture: &'a Texture2D,
+    model: Matrix4<f32>,
+}
+
+pub fn main_4_3_2() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/3.2.blending.vs",
+            "src/_4_advanced_opengl/shaders/3.2.blending.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+        // texture coords above 1 repeat the floor texture
+        #[rustfmt::skip]
+        let plane_vertices: [f32; 30] = [
+            // positions       // texture coords
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5,  5.0,  0.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+             5.0, -0.5, -5.0,  2.0, 2.0,
+        ];
+        #[rustfmt::skip]
+        let transparent_vertices: [f32; 30] = [
+            // positions      // texture coords
+            0.0,  0.5,  0.0,  0.0, 1.0,
+            0.0, -0.5,  0.0,  0.0, 0.0,
+            1.0, -0.5,  0.0,  1.0, 0.0,
+
+            0.0,  0.5,  0.0,  0.0, 1.0,
+            1.0, -0.5,  0.0,  1.0, 0.0,
+            1.0,  0.5,  0.0,  1.0, 1.0,
+        ];
+
+        let (cube_vao, cube_vbo) = unsafe { textured_vertex_array(&cube_vertices) };
+        let (plane_vao, plane_vbo) = unsafe { textured_vertex_array(&plane_vertices) };
+        let (transparent_vao, transparent_vbo) =
+            unsafe { textured_vertex_array(&transparent_vertices) };
+
+        let cube_texture =
+            Texture2D::from_path("resources/textures/marble.jpg", TextureOptions::default())?;
+        let floor_texture =
+            Texture2D::from_path("resources/textures/metal.png", TextureOptions::default())?;
+        // repeat wrapping would blend the transparent bottom row into the top border
+        let window_texture = Texture2D::from_path(
+            "resources/textures/window.png",
+            TextureOptions {
+                sampler: SamplerDesc {
+                    wrap_s: gl::CLAMP_TO_EDGE,
+                    wrap_t: gl::CLAMP_TO_EDGE,
+                    ..SamplerDesc::default()
+                },
+                ..TextureOptions::default()
+            },
+        )?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+        }
+
+        let cube_positions: [Vector3<f32>; 2] = [vec3(-1.0, 0.0, -1.0), vec3(2.0, 0.0, 0.0)];
+        let window_positions: [Vector3<f32>; 5] = [
+            vec3(-1.5, 0.0, -0.48),
+            vec3(1.5, 0.0, 0.51),
+            vec3(0.0, 0.0, 0.7),
+            vec3(-0.3, 0.0, -2.3),
+            vec3(0.5, 0.0, -0.6),
+        ];
+
+        let mut queue = DrawQueue::new();
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            sort_transparent: true,
+        };
+
+        // everything above set GL state directly, the cache starts out knowing nothing
+        let mut state = GlState::new();
+        let mut counter_frames = 0;
+        let mut last_report = glfw.get_time() as f32;
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // the queue borrows the textures, so it is rebuilt every frame
+            queue.clear();
+            queue.push_opaque(
+                point3(0.0, -0.5, 0.0),
+                Draw {
+                    vao: plane_vao,
+                    vertex_count: 6,
+                    texture: &floor_texture,
+                    model: Matrix4::identity(),
+                },
+            );
+            for position in cube_positions {
+                queue.push_opaque(
+                    Point3::from_vec(position),
+                    Draw {
+                        vao: cube_vao,
+                        vertex_count: 36,
+                        texture: &cube_texture,
+                        model: Transform::from_translation(position).to_matrix(),
+                    },
+                );
+            }
+            for position in window_positions {
+                // the quad spans x from 0 to 1, its centre is half a unit to the right
+                queue.push_transparent(
+                    Point3::from_vec(position + vec3(0.5, 0.0, 0.0)),
+                    Draw {
+                        vao: transparent_vao,
+                        vertex_count: 6,
+                        texture: &window_texture,
+                        model: Transform::from_translation(position).to_matrix(),
+                    },
+                );
+            }
+            if input.sort_transparent {
+                queue.sort(&view);
+            }
+
+            unsafe {
+                state.set_viewport(0, 0, width, height);
+                // glClear obeys the depth write mask, which the transparent pass turned off
+                state.set_depth(DepthState::default());
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                state.use_program(&shader);
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+
+                state.set_blend(BlendState::disabled());
+                for draw in queue.opaque() {
+                    draw_item(&mut state, &shader, draw)?;
+                }
+
+                // transparent surfaces are depth tested against the opaque scene but don't
+                // write depth, a window must never hide another one drawn after it
+                state.set_depth(DepthState::read_only());
+                state.set_blend(BlendState::alpha());
+                for draw in queue.transparent() {
+                    draw_item(&mut state, &shader, draw)?;
+                }
+            }
+
+            // show the average number of state changes per frame once a second
+            counter_frames += 1;
+            if current_frame - last_report >= 1.0 {
+                let counters = state.end_frame();
+                window.set_title(&format!(
+                    "LearnOpenGL - state changes per frame: {} issued, {} skipped",
+                    counters.issued / counter_frames,
+                    counters.skipped / counter_frames
+                ));
+                counter_frames = 0;
+                last_report = current_frame;
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &plane_vao);
+            gl::DeleteVertexArrays(1, &transparent_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+            gl::DeleteBuffers(1, &plane_vbo);
+            gl::DeleteBuffers(1, &transparent_vbo);
+        }
+    }
+    Ok(())
+}
+
+unsafe fn draw_item(state: &mut GlState, shader: &Shader, draw: &Draw) -> Result<()> {
+    state.bind_vertex_array(draw.vao);
+    state.bind_texture(0, gl::TEXTURE_2D, draw.texture.id);
+    shader.set_mat4(&CString::new("model")?, &draw.model);
+    gl::DrawArrays(gl::TRIANGLES, 0, draw.vertex_count);
+    Ok(())
+}
+
+/// upload interleaved position + texture coordinate vertices, returns the vao and vbo
+unsafe fn textured_vertex_array(vertices: &[f32]) -> (GLuint, GLuint) {
+    let mut vao: GLuint = 0;
+    let mut vbo: GLuint = 0;
+    gl::GenVertexArrays(1, &mut vao);
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindVertexArray(vao);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        mem::size_of_val(vertices) as GLsizeiptr,
+        vertices.as_ptr() as *const _,
+        gl::STATIC_DRAW,
+    );
+    VertexLayout::packed(&[(0, AttributeType::Vec3), (1, AttributeType::Vec2)], 0).apply();
+    gl::BindVertexArray(0);
+    (vao, vbo)
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // the viewport is set from this every frame, through the state cache
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::T, _, Action::Press, _) => {
+                input.sort_transparent = !input.sort_transparent;
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
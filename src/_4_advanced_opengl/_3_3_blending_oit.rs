@@ -0,0 +1,390 @@
+use std::{ffi::CString, mem};
+
+use anyhow::Result;
+use cgmath::{
+    point3, vec3, vec4, Deg, EuclideanSpace, Matrix4, Point3, SquareMatrix, Vector3, Vector4,
+};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    draw_queue::DrawQueue,
+    oit::{TransparencyMode, WeightedBlendedOit},
+    projection::aspect_ratio,
+    render_state::{BlendState, DepthState},
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    /// switched with M
+    transparency: TransparencyMode,
+}
+
+/// the opaque draws of the scene
+struct Draw<'a> {
+    vao: GLuint,
+    vertex_count: GLsizei,
+    texture: &'a Texture2D,
+    model: Matrix4<f32>,
+}
+
+/// a translucent quad, drawn with a single colour
+struct Pane {
+    color: Vector4<f32>,
+    model: Matrix4<f32>,
+}
+
+/// colours of the intersecting panes, premultiplying happens in the shaders
+const PANE_COLORS: [Vector4<f32>; 3] = [
+    vec4(1.0, 0.2, 0.2, 0.5),
+    vec4(0.2, 1.0, 0.2, 0.5),
+    vec4(0.2, 0.4, 1.0, 0.5),
+];
+
+pub fn main_4_3_3() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+    // the transparency pass copies this depth buffer, the formats have to match
+    glfw.window_hint(WindowHint::DepthBits(Some(24)));
+    glfw.window_hint(WindowHint::StencilBits(Some(8)));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/3.2.blending.vs",
+            "src/_4_advanced_opengl/shaders/3.2.blending.fs",
+        )?;
+        let sorted_shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/3.3.oit.vs",
+            "src/_4_advanced_opengl/shaders/3.3.oit_sorted.fs",
+        )?;
+        let accumulate_shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/3.3.oit.vs",
+            "src/_4_advanced_opengl/shaders/3.3.oit_accumulate.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+        // texture coords above 1 repeat the floor texture
+        #[rustfmt::skip]
+        let plane_vertices: [f32; 30] = [
+            // positions       // texture coords
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5,  5.0,  0.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+             5.0, -0.5, -5.0,  2.0, 2.0,
+        ];
+        #[rustfmt::skip]
+        let pane_vertices: [f32; 30] = [
+            // positions       // texture coords
+            -0.5,  0.5,  0.0,  0.0, 1.0,
+            -0.5, -0.5,  0.0,  0.0, 0.0,
+             0.5, -0.5,  0.0,  1.0, 0.0,
+
+            -0.5,  0.5,  0.0,  0.0, 1.0,
+             0.5, -0.5,  0.0,  1.0, 0.0,
+             0.5,  0.5,  0.0,  1.0, 1.0,
+        ];
+
+        let (cube_vao, cube_vbo) = unsafe { textured_vertex_array(&cube_vertices) };
+        let (plane_vao, plane_vbo) = unsafe { textured_vertex_array(&plane_vertices) };
+        let (pane_vao, pane_vbo) = unsafe { textured_vertex_array(&pane_vertices) };
+
+        let cube_texture =
+            Texture2D::from_path("resources/textures/marble.jpg", TextureOptions::default())?;
+        let floor_texture =
+            Texture2D::from_path("resources/textures/metal.png", TextureOptions::default())?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+        }
+
+        let cube_positions: [Vector3<f32>; 2] = [vec3(-1.0, 0.0, -1.0), vec3(2.0, 0.0, 0.0)];
+        // where the three panes cross each other
+        let panes_center = vec3(0.5, 0.25, 0.5);
+
+        let mut queue = DrawQueue::new();
+        let mut panes: Vec<Pane> = Vec::new();
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            transparency: TransparencyMode::WeightedBlended,
+        };
+        let mut oit = WeightedBlendedOit::new(input.framebuffer_size.0, input.framebuffer_size.1)?;
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // the queue borrows the textures, so it is rebuilt every frame
+            queue.clear();
+            queue.push_opaque(
+                point3(0.0, -0.5, 0.0),
+                Draw {
+                    vao: plane_vao,
+                    vertex_count: 6,
+                    texture: &floor_texture,
+                    model: Matrix4::identity(),
+                },
+            );
+            for position in cube_positions {
+                queue.push_opaque(
+                    Point3::from_vec(position),
+                    Draw {
+                        vao: cube_vao,
+                        vertex_count: 36,
+                        texture: &cube_texture,
+                        model: Transform::from_translation(position).to_matrix(),
+                    },
+                );
+            }
+            queue.sort(&view);
+
+            // three panes slowly turning around their common vertical axis, every pane cuts
+            // through the other two so no order of whole panes is correct
+            panes.clear();
+            for (i, color) in PANE_COLORS.into_iter().enumerate() {
+                let angle = Deg(current_frame * 10.0 + 60.0 * i as f32);
+                let model = Transform {
+                    translation: panes_center,
+                    ..Transform::from_axis_angle(vec3(0.0, 1.0, 0.0), angle)
+                };
+                panes.push(Pane {
+                    color,
+                    model: model.to_matrix() * Matrix4::from_scale(1.5),
+                });
+            }
+
+            unsafe {
+                oit.resize(width, height)?;
+
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+
+                DepthState::default().apply();
+                BlendState::disabled().apply();
+                for draw in queue.opaque() {
+                    draw_item(&shader, draw)?;
+                }
+
+                gl::BindVertexArray(pane_vao);
+                match input.transparency {
+                    TransparencyMode::Sorted => {
+                        // all panes share their centre, so sorting them has nothing to go by
+                        DepthState::read_only().apply();
+                        BlendState::alpha().apply();
+                        draw_panes(&sorted_shader, &panes, &view, &projection)?;
+                        DepthState::default().apply();
+                        BlendState::disabled().apply();
+                    }
+                    TransparencyMode::WeightedBlended => {
+                        oit.begin(0);
+                        draw_panes(&accumulate_shader, &panes, &view, &projection)?;
+                        oit.composite(0);
+                    }
+                }
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &plane_vao);
+            gl::DeleteVertexArrays(1, &pane_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+            gl::DeleteBuffers(1, &plane_vbo);
+            gl::DeleteBuffers(1, &pane_vbo);
+        }
+    }
+    Ok(())
+}
+
+unsafe fn draw_item(shader: &Shader, draw: &Draw) -> Result<()> {
+    gl::BindVertexArray(draw.vao);
+    draw.texture.bind(0);
+    shader.set_mat4(&CString::new("model")?, &draw.model);
+    gl::DrawArrays(gl::TRIANGLES, 0, draw.vertex_count);
+    Ok(())
+}
+
+/// draw the panes with the pane vao bound, in the order they are listed
+unsafe fn draw_panes(
+    shader: &Shader,
+    panes: &[Pane],
+    view: &Matrix4<f32>,
+    projection: &Matrix4<f32>,
+) -> Result<()> {
+    shader.use_program();
+    shader.set_mat4(&CString::new("view")?, view);
+    shader.set_mat4(&CString::new("projection")?, projection);
+    for pane in panes {
+        shader.set_vector4(&CString::new("color")?, &pane.color);
+        shader.set_mat4(&CString::new("model")?, &pane.model);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+    }
+    Ok(())
+}
+
+/// upload interleaved position + texture coordinate vertices, returns the vao and vbo
+unsafe fn textured_vertex_array(vertices: &[f32]) -> (GLuint, GLuint) {
+    let mut vao: GLuint = 0;
+    let mut vbo: GLuint = 0;
+    gl::GenVertexArrays(1, &mut vao);
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindVertexArray(vao);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        mem::size_of_val(vertices) as GLsizeiptr,
+        vertices.as_ptr() as *const _,
+        gl::STATIC_DRAW,
+    );
+    VertexLayout::packed(&[(0, AttributeType::Vec3), (1, AttributeType::Vec2)], 0).apply();
+    gl::BindVertexArray(0);
+    (vao, vbo)
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::M, _, Action::Press, _) => {
+                input.transparency = input.transparency.toggled();
+                println!("transparency: {:?}", input.transparency);
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
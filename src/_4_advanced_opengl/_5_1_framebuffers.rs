@@ -0,0 +1,319 @@
+use std::{ffi::CString, mem};
+
+use anyhow::{Context, Result};
+use cgmath::{vec3, Matrix4, Point3, SquareMatrix, Vector3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    post_process::{ChainEntry, Effect, PostProcess, EFFECT_NAMES},
+    projection::aspect_ratio,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// keys switching the effects of the chain on and off, in chain order
+const EFFECT_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+}
+
+/// the effects given after the tutorial id run in that order, e.g.
+/// `4_5_1 grayscale kernel=1,2,1,2,4,2,1,2,1/16`. without any, every built-in effect is in the
+/// chain but switched off. the number keys toggle the effects
+pub fn main_4_5_1() -> Result<()> {
+    let chain = effect_chain(std::env::args().skip(2))?;
+
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/5.1.framebuffers.vs",
+            "src/_4_advanced_opengl/shaders/5.1.framebuffers.fs",
+        )?;
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+        // texture coords above 1 repeat the floor texture
+        #[rustfmt::skip]
+        let plane_vertices: [f32; 30] = [
+            // positions       // texture coords
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5,  5.0,  0.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+
+             5.0, -0.5,  5.0,  2.0, 0.0,
+            -5.0, -0.5, -5.0,  0.0, 2.0,
+             5.0, -0.5, -5.0,  2.0, 2.0,
+        ];
+
+        let (cube_vao, cube_vbo) = unsafe { textured_vertex_array(&cube_vertices) };
+        let (plane_vao, plane_vbo) = unsafe { textured_vertex_array(&plane_vertices) };
+
+        let cube_texture =
+            Texture2D::from_path("resources/textures/marble.jpg", TextureOptions::default())?;
+        let floor_texture =
+            Texture2D::from_path("resources/textures/metal.png", TextureOptions::default())?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+        }
+
+        let cube_positions: [Vector3<f32>; 2] = [vec3(-1.0, 0.0, -1.0), vec3(2.0, 0.0, 0.0)];
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+        };
+        let (width, height) = input.framebuffer_size;
+        let mut post_process = PostProcess::new(chain, width, height)?;
+        print_chain(&post_process);
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input, &mut post_process);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            unsafe {
+                post_process.resize(width, height)?;
+
+                // draw the scene into the off-screen target as usual
+                post_process.begin();
+                gl::Enable(gl::DEPTH_TEST);
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+
+                gl::BindVertexArray(plane_vao);
+                floor_texture.bind(0);
+                shader.set_mat4(&CString::new("model")?, &Matrix4::identity());
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+                gl::BindVertexArray(cube_vao);
+                cube_texture.bind(0);
+                for position in cube_positions {
+                    let model = Transform::from_translation(position);
+                    shader.set_mat4(&CString::new("model")?, &model.to_matrix());
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+                gl::BindVertexArray(0);
+
+                // then run the effects, the last one draws into the window
+                post_process.finish()?;
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteVertexArrays(1, &plane_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+            gl::DeleteBuffers(1, &plane_vbo);
+        }
+    }
+    Ok(())
+}
+
+/// parse the effects from the command line, see `main_4_5_1`
+fn effect_chain(args: impl Iterator<Item = String>) -> Result<Vec<ChainEntry>> {
+    let effects = args
+        .map(|arg| arg.parse::<Effect>())
+        .collect::<Result<Vec<_>>>()
+        .context("parsing the post-processing effects")?;
+    if !effects.is_empty() {
+        return Ok(effects
+            .into_iter()
+            .map(|effect| ChainEntry {
+                effect,
+                enabled: true,
+            })
+            .collect());
+    }
+    EFFECT_NAMES
+        .iter()
+        .map(|name| {
+            Ok(ChainEntry {
+                effect: name.parse()?,
+                enabled: false,
+            })
+        })
+        .collect()
+}
+
+fn print_chain(post_process: &PostProcess) {
+    for (entry, key) in post_process.chain.iter().zip(1..) {
+        let state = if entry.enabled { "on" } else { "off" };
+        println!("{}: {} ({})", key, entry.effect, state);
+    }
+}
+
+/// upload interleaved position + texture coordinate vertices, returns the vao and vbo
+unsafe fn textured_vertex_array(vertices: &[f32]) -> (GLuint, GLuint) {
+    let mut vao: GLuint = 0;
+    let mut vbo: GLuint = 0;
+    gl::GenVertexArrays(1, &mut vao);
+    gl::GenBuffers(1, &mut vbo);
+    gl::BindVertexArray(vao);
+    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+    gl::BufferData(
+        gl::ARRAY_BUFFER,
+        mem::size_of_val(vertices) as GLsizeiptr,
+        vertices.as_ptr() as *const _,
+        gl::STATIC_DRAW,
+    );
+    VertexLayout::packed(&[(0, AttributeType::Vec3), (1, AttributeType::Vec2)], 0).apply();
+    gl::BindVertexArray(0);
+    (vao, vbo)
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+    post_process: &mut PostProcess,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(key, _, Action::Press, _) if EFFECT_KEYS.contains(&key) => {
+                let index = EFFECT_KEYS.iter().position(|effect_key| *effect_key == key);
+                if index.is_some_and(|index| post_process.toggle(index)) {
+                    print_chain(post_process);
+                }
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
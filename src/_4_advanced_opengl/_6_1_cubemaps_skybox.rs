@@ -0,0 +1,220 @@
+use std::{ffi::CString, mem, ptr};
+
+use anyhow::Result;
+use cgmath::{point3, vec3, Deg, Matrix4, SquareMatrix};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, GlfwReceiver, Key, WindowHint, WindowMode,
+};
+
+use crate::{
+    projection::{perspective_projection, DepthMode},
+    shaders::Shader,
+    skybox::Skybox,
+    texture::{Texture2D, TextureCube, TextureOptions},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+pub fn main_4_6_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 180] = [
+            // positions       // texture coords
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+        ];
+
+        let shader = Shader::new(
+            "src/_4_advanced_opengl/shaders/6.1.cubemaps.vs",
+            "src/_4_advanced_opengl/shaders/6.1.cubemaps.fs",
+        )?;
+
+        let mut cube_vao: GLuint = 0;
+        let mut cube_vbo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut cube_vao);
+            gl::GenBuffers(1, &mut cube_vbo);
+            gl::BindVertexArray(cube_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, cube_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&cube_vertices) as GLsizeiptr,
+                cube_vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<GLfloat>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+        }
+
+        let cube_texture = Texture2D::from_path(
+            "resources/textures/container.jpg",
+            TextureOptions::default(),
+        )?;
+
+        // faces in +X, -X, +Y, -Y, +Z, -Z order
+        let cubemap = TextureCube::from_faces(
+            [
+                "resources/textures/skybox/right.jpg",
+                "resources/textures/skybox/left.jpg",
+                "resources/textures/skybox/top.jpg",
+                "resources/textures/skybox/bottom.jpg",
+                "resources/textures/skybox/front.jpg",
+                "resources/textures/skybox/back.jpg",
+            ],
+            TextureOptions::cubemap(),
+        )?;
+        let skybox = Skybox::new(cubemap)?;
+
+        unsafe {
+            shader.use_program();
+            shader.set_int(&CString::new("texture1")?, 0);
+        }
+
+        let mut depth = DepthMode::Standard;
+        println!("Z: reversed-Z depth");
+
+        while !window.should_close() {
+            // all events including input
+            process_events(&mut window, &events, &mut depth);
+
+            // the skybox has to land on the far plane of either depth mode
+            let projection = perspective_projection(
+                Deg(45.0),
+                SCR_WIDTH as f32 / SCR_HEIGHT as f32,
+                0.1,
+                100.0,
+                depth,
+            );
+
+            // slowly circle around the cube so the skybox can be seen from every side
+            let time = glfw.get_time() as f32;
+            let radius = 3.0;
+            let eye = point3(time.sin() * radius, 0.5, time.cos() * radius);
+            let view = Matrix4::look_at_rh(eye, point3(0.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0));
+
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                // draw the scene as normal
+                shader.use_program();
+                shader.set_mat4(&CString::new("model")?, &Matrix4::identity());
+                shader.set_mat4(&CString::new("view")?, &view);
+                shader.set_mat4(&CString::new("projection")?, &projection);
+                gl::BindVertexArray(cube_vao);
+                cube_texture.bind(0);
+                gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                gl::BindVertexArray(0);
+
+                // draw the skybox last
+                skybox.draw(&view, &projection)?;
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    depth: &mut DepthMode,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::Z, _, Action::Press, _) => unsafe {
+                if DepthMode::reversed_z_supported() {
+                    *depth = depth.toggled();
+                    depth.apply();
+                    println!("depth mode: {:?}", depth);
+                } else {
+                    println!("reversed-Z needs OpenGL 4.5 or ARB_clip_control");
+                }
+            },
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{ffi::CString, mem};
+
+use anyhow::Result;
+use cgmath::{vec3, EuclideanSpace, Matrix4, Point3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    projection::aspect_ratio,
+    shaders::Shader,
+    uniform_buffer::{Matrices, MatricesBuffer},
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+}
+
+/// four shaders sharing the camera through the `Matrices` uniform block, which is written
+/// once per frame instead of once per shader
+pub fn main_4_8_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        // the block is linked to its binding point when each of them is compiled
+        let shaders = ["red", "green", "blue", "yellow"]
+            .into_iter()
+            .map(|color| {
+                Shader::new(
+                    "src/_4_advanced_opengl/shaders/8.advanced_glsl.vs",
+                    &format!("src/_4_advanced_opengl/shaders/8.{}.fs", color),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let positions = [
+            vec3(-0.75, 0.75, 0.0),
+            vec3(0.75, 0.75, 0.0),
+            vec3(-0.75, -0.75, 0.0),
+            vec3(0.75, -0.75, 0.0),
+        ];
+
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 108] = [
+            // positions
+            -0.5, -0.5, -0.5,   0.5,  0.5, -0.5,   0.5, -0.5, -0.5,
+             0.5,  0.5, -0.5,  -0.5, -0.5, -0.5,  -0.5,  0.5, -0.5,
+
+            -0.5, -0.5,  0.5,   0.5, -0.5,  0.5,   0.5,  0.5,  0.5,
+             0.5,  0.5,  0.5,  -0.5,  0.5,  0.5,  -0.5, -0.5,  0.5,
+
+            -0.5,  0.5,  0.5,  -0.5,  0.5, -0.5,  -0.5, -0.5, -0.5,
+            -0.5, -0.5, -0.5,  -0.5, -0.5,  0.5,  -0.5,  0.5,  0.5,
+
+             0.5,  0.5,  0.5,   0.5, -0.5, -0.5,   0.5,  0.5, -0.5,
+             0.5, -0.5, -0.5,   0.5,  0.5,  0.5,   0.5, -0.5,  0.5,
+
+            -0.5, -0.5, -0.5,   0.5, -0.5, -0.5,   0.5, -0.5,  0.5,
+             0.5, -0.5,  0.5,  -0.5, -0.5,  0.5,  -0.5, -0.5, -0.5,
+
+            -0.5,  0.5, -0.5,   0.5,  0.5,  0.5,   0.5,  0.5, -0.5,
+             0.5,  0.5,  0.5,  -0.5,  0.5, -0.5,  -0.5,  0.5,  0.5,
+        ];
+
+        let mut cube_vao: GLuint = 0;
+        let mut cube_vbo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut cube_vao);
+            gl::GenBuffers(1, &mut cube_vbo);
+            gl::BindVertexArray(cube_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, cube_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&cube_vertices) as GLsizeiptr,
+                cube_vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            VertexLayout::packed(&[(0, AttributeType::Vec3)], 0).apply();
+            gl::BindVertexArray(0);
+        }
+
+        let matrices_buffer = MatricesBuffer::new();
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 3.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+        };
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+
+            // NOTE: start rendering process here
+            unsafe {
+                // the camera once for all four shaders
+                matrices_buffer.update(&Matrices {
+                    projection: input.camera.projection_matrix(aspect_ratio(width, height)),
+                    view: input.camera.view_matrix(),
+                    camera_position: input.camera.position.to_vec(),
+                    time: current_frame,
+                });
+
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                gl::BindVertexArray(cube_vao);
+                for (shader, position) in shaders.iter().zip(positions) {
+                    shader.use_program();
+                    shader.set_mat4(
+                        &CString::new("model")?,
+                        &Matrix4::from_translation(position),
+                    );
+                    gl::DrawArrays(gl::TRIANGLES, 0, 36);
+                }
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &cube_vao);
+            gl::DeleteBuffers(1, &cube_vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
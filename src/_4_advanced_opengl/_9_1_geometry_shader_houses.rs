@@ -0,0 +1,98 @@
+use std::mem;
+
+use anyhow::Result;
+use gl::types::*;
+use glfw::{fail_on_errors, Action, Context, GlfwReceiver, Key, WindowHint, WindowMode};
+
+use crate::{
+    shaders::Shader,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+pub fn main_4_9_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(800, 600, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        let shader = Shader::with_geometry_shader(
+            "src/_4_advanced_opengl/shaders/9.1.geometry_shader.vs",
+            "src/_4_advanced_opengl/shaders/9.1.geometry_shader.fs",
+            "src/_4_advanced_opengl/shaders/9.1.geometry_shader.gs",
+        )?;
+
+        // one point per house, the geometry shader builds the rest
+        #[rustfmt::skip]
+        let points: [f32; 20] = [
+            // positions  colors
+            -0.5,  0.5,   1.0, 0.0, 0.0, // top-left
+             0.5,  0.5,   0.0, 1.0, 0.0, // top-right
+             0.5, -0.5,   0.0, 0.0, 1.0, // bottom-right
+            -0.5, -0.5,   1.0, 1.0, 0.0, // bottom-left
+        ];
+
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&points) as GLsizeiptr,
+                points.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            VertexLayout::packed(&[(0, AttributeType::Vec2), (1, AttributeType::Vec3)], 0).apply();
+            gl::BindVertexArray(0);
+        }
+
+        while !window.should_close() {
+            process_events(&mut window, &events);
+
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+
+                shader.use_program();
+                gl::BindVertexArray(vao);
+                gl::DrawArrays(gl::POINTS, 0, 4);
+                gl::BindVertexArray(0);
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &vao);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+    Ok(())
+}
+
+fn process_events(window: &mut glfw::Window, events: &GlfwReceiver<(f64, glfw::WindowEvent)>) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            _ => {}
+        }
+    }
+}
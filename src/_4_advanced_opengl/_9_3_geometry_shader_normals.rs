@@ -0,0 +1,185 @@
+use std::ffi::CString;
+
+use anyhow::Result;
+use cgmath::{vec3, EuclideanSpace, InnerSpace, Point3};
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    lighting::{DirectionalLight, LightColor, UniformStruct},
+    model::{Model, ModelData},
+    normal_overlay::{NormalOverlay, NormalSource},
+    projection::aspect_ratio,
+    shaders::Shader,
+    transform::Transform,
+};
+
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    normals: NormalSource,
+}
+
+/// a model path can be given after the tutorial id, e.g.
+/// `4_9_3 resources/objects/crates/crates.glb`. N switches between vertex and face normals
+pub fn main_4_9_3() -> Result<()> {
+    let path = std::env::args()
+        .nth(2)
+        .unwrap_or_else(|| "resources/objects/torus/torus.obj".to_string());
+
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let shader = Shader::new(
+            "src/_3_model_loading/shaders/1.model_loading.vs",
+            "src/_3_model_loading/shaders/1.model_loading.fs",
+        )?;
+
+        // parse on the cpu first, the bounds are needed to place the model
+        let data = ModelData::open(&path)?;
+        let (min, max) = data
+            .bounds()
+            .unwrap_or((vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 0.0)));
+        let model = Model::from_data(&data)?;
+        let mut overlay = NormalOverlay::new()?;
+        // lines a twentieth of the model's diagonal, whatever its scale
+        overlay.length = (max - min).magnitude() * 0.05;
+        // centre the model at the origin
+        let transform = Transform::from_translation(-(min + max) / 2.0);
+
+        let dir_light = DirectionalLight {
+            direction: vec3(-0.2, -1.0, -0.3),
+            color: LightColor {
+                ambient: vec3(0.2, 0.2, 0.2),
+                diffuse: vec3(0.7, 0.7, 0.7),
+                specular: vec3(0.5, 0.5, 0.5),
+            },
+        };
+
+        let mut input = InputState {
+            camera: Camera::new(Point3::new(0.0, 0.0, 4.0)),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            normals: overlay.source,
+        };
+        println!("showing {}, press N to switch", overlay.source);
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            let (width, height) = input.framebuffer_size;
+            let view = input.camera.view_matrix();
+            let projection = input.camera.projection_matrix(aspect_ratio(width, height));
+
+            // NOTE: start rendering process here
+            unsafe {
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                dir_light.upload(&shader, "dirLight")?;
+                shader.set_vector3(&CString::new("viewPos")?, &input.camera.position.to_vec());
+
+                shader.set_mat4(&CString::new("projection")?, &projection);
+                shader.set_mat4(&CString::new("view")?, &view);
+                model.draw(&shader, "material", &transform.to_matrix())?;
+
+                // the same geometry again, as lines
+                if overlay.source != input.normals {
+                    overlay.source = input.normals;
+                    println!("showing {}", overlay.source);
+                }
+                overlay.begin(&view, &projection)?;
+                overlay.draw_model(&model, &transform.to_matrix())?;
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(Key::N, _, Action::Press, _) => {
+                input.normals = input.normals.toggled();
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
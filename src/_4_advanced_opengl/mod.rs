@@ -0,0 +1,26 @@
+// advanced opengl chapter
+pub mod _10_1_instancing_quads;
+pub mod _10_3_asteroids_instanced;
+pub mod _11_1_anti_aliasing;
+pub mod _2_1_stencil_testing;
+pub mod _3_2_blending_sort;
+pub mod _3_3_blending_oit;
+pub mod _5_1_framebuffers;
+pub mod _6_1_cubemaps_skybox;
+pub mod _8_1_advanced_glsl_ubo;
+pub mod _9_1_geometry_shader_houses;
+pub mod _9_2_geometry_shader_exploding;
+pub mod _9_3_geometry_shader_normals;
+
+pub use _10_1_instancing_quads::*;
+pub use _10_3_asteroids_instanced::*;
+pub use _11_1_anti_aliasing::*;
+pub use _2_1_stencil_testing::*;
+pub use _3_2_blending_sort::*;
+pub use _3_3_blending_oit::*;
+pub use _5_1_framebuffers::*;
+pub use _6_1_cubemaps_skybox::*;
+pub use _8_1_advanced_glsl_ubo::*;
+pub use _9_1_geometry_shader_houses::*;
+pub use _9_2_geometry_shader_exploding::*;
+pub use _9_3_geometry_shader_normals::*;
@@ -0,0 +1,21 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoords;
+// a mat4 takes up locations 3 to 6, one column each
+layout (location = 3) in mat4 aInstanceMatrix;
+
+out vec2 TexCoords;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+// false for the naive loop, which sets the whole transform in `model` per rock
+uniform bool instanced;
+
+void main()
+{
+    TexCoords = aTexCoords;
+    mat4 instance = instanced ? aInstanceMatrix : mat4(1.0);
+    gl_Position = projection * view * instance * model * vec4(aPos, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 330 core
+out vec4 FragColor;
+
+// the multisampled colour attachment, read sample by sample without filtering
+uniform sampler2DMS screenTexture;
+// how many samples to average, 1 shows what a single sample per pixel looks like
+uniform int sampleCount;
+
+void main()
+{
+    // the target has the size of the window, so pixels map one to one
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < sampleCount; ++i)
+        color += texelFetch(screenTexture, texel, i);
+    FragColor = color / float(sampleCount);
+}
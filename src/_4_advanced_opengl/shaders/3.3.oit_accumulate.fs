@@ -0,0 +1,17 @@
+#version 330 core
+layout (location = 0) out vec4 accumulation;
+layout (location = 1) out float weight;
+
+in float ViewDepth;
+
+uniform vec4 color;
+
+void main()
+{
+    float alpha = color.a;
+    // equation 7 of the paper, nearer surfaces count for more of the average
+    float w = alpha * clamp(
+        10.0 / (1e-5 + pow(ViewDepth / 5.0, 2.0) + pow(ViewDepth / 200.0, 6.0)), 1e-2, 3e3);
+    accumulation = vec4(color.rgb * alpha * w, alpha);
+    weight = alpha * w;
+}
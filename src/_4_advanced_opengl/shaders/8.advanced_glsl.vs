@@ -0,0 +1,21 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+// filled once per frame by `MatricesBuffer`, shared with every other shader declaring it
+layout (std140) uniform Matrices {
+    mat4 projection;
+    mat4 view;
+    vec3 cameraPosition;
+    float time;
+};
+uniform mat4 model;
+
+out vec3 FragPos;
+
+void main()
+{
+    FragPos = vec3(model * vec4(aPos, 1.0));
+    // bob up and down, each cube a little later than the one to its left
+    FragPos.y += sin(time * 2.0 + model[3].x) * 0.1;
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+}
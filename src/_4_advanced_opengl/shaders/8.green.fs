@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+layout (std140) uniform Matrices {
+    mat4 projection;
+    mat4 view;
+    vec3 cameraPosition;
+    float time;
+};
+
+in vec3 FragPos;
+
+void main()
+{
+    // darker further away from the camera
+    float fade = clamp(3.0 / distance(cameraPosition, FragPos), 0.2, 1.0);
+    FragColor = vec4(vec3(0.0, 1.0, 0.0) * fade, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 FragColor;
+
+struct Material {
+    sampler2D diffuse;
+};
+
+in vec2 TexCoords;
+
+uniform Material material;
+
+void main()
+{
+    FragColor = texture(material.diffuse, TexCoords);
+}
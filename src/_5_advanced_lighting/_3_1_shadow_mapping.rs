@@ -0,0 +1,490 @@
+use std::{ffi::CString, mem};
+
+use anyhow::Result;
+use cgmath::{vec3, Deg, EuclideanSpace, Point3, Vector3};
+use gl::types::*;
+use glfw::{
+    fail_on_errors, Action, Context as GLContext, CursorMode, GlfwReceiver, Key, WindowHint,
+    WindowMode,
+};
+
+use crate::{
+    camera::{Camera, CameraMovement, CursorTracker},
+    lighting::{DirectionalLight, LightColor, MapMaterial, UniformStruct},
+    projection::aspect_ratio,
+    shaders::Shader,
+    shadow::{fit_light_space, ShadowBias, ShadowFilter, ShadowMap},
+    texture::{Texture2D, TextureOptions},
+    transform::Transform,
+    vertex_layout::{AttributeType, VertexLayout},
+};
+
+// settings
+const SCR_WIDTH: u32 = 800;
+const SCR_HEIGHT: u32 = 600;
+const SHADOW_SIZE: i32 = 2048;
+
+/// half the width of the floor, its texture repeats that many times across it
+const FLOOR_EXTENT: f32 = 10.0;
+
+/// glPolygonOffset factor and units used when polygon offset is turned on
+const POLYGON_OFFSET: (f32, f32) = (2.0, 4.0);
+
+/// everything the event handler changes
+struct InputState {
+    camera: Camera,
+    cursor: CursorTracker,
+    framebuffer_size: (i32, i32),
+    filter: ShadowFilter,
+    /// constant and slope-scaled bias in the shader, toggled with B
+    depth_bias: bool,
+    /// multiplies the default depth bias, changed with Up and Down
+    bias_scale: f32,
+    /// toggled with N
+    normal_offset: bool,
+    /// toggled with O
+    polygon_offset: bool,
+    /// toggled with C
+    cull_front_faces: bool,
+    /// the depth map in the lower left corner, toggled with V
+    show_depth_map: bool,
+    /// the light circles the scene, toggled with R
+    orbit_light: bool,
+}
+
+impl InputState {
+    /// the acne and peter-panning mitigations picked with the keys
+    fn bias(&self) -> ShadowBias {
+        let defaults = ShadowBias::default();
+        let scale = if self.depth_bias {
+            self.bias_scale
+        } else {
+            0.0
+        };
+        ShadowBias {
+            constant: defaults.constant * scale,
+            slope: defaults.slope * scale,
+            max: defaults.max * scale,
+            normal_offset: if self.normal_offset {
+                defaults.normal_offset
+            } else {
+                0.0
+            },
+            polygon_offset: self.polygon_offset.then_some(POLYGON_OFFSET),
+            cull_front_faces: self.cull_front_faces,
+        }
+    }
+
+    fn print_settings(&self) {
+        println!(
+            "{}, depth bias {} (x{}), normal offset {}, polygon offset {}, front face culling {}",
+            self.filter,
+            on_off(self.depth_bias),
+            self.bias_scale,
+            on_off(self.normal_offset),
+            on_off(self.polygon_offset),
+            on_off(self.cull_front_faces),
+        );
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// what both passes draw
+struct Scene {
+    floor_vao: GLuint,
+    cube_vao: GLuint,
+    floor: Transform,
+    cubes: [Transform; 3],
+}
+
+impl Scene {
+    /// world space box around the floor and the cubes, the light's projection is fitted to it
+    fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let floor_corners = [-FLOOR_EXTENT, FLOOR_EXTENT].map(|x| {
+            [-FLOOR_EXTENT, FLOOR_EXTENT]
+                .map(|z| self.floor.transform_point(Point3::new(x, 0.0, z)))
+        });
+        let cube_corners = self.cubes.iter().flat_map(|cube| {
+            (0..8).map(|corner| {
+                cube.transform_point(Point3::new(
+                    if corner & 1 == 0 { -0.5 } else { 0.5 },
+                    if corner & 2 == 0 { -0.5 } else { 0.5 },
+                    if corner & 4 == 0 { -0.5 } else { 0.5 },
+                ))
+            })
+        });
+        floor_corners
+            .into_iter()
+            .flatten()
+            .chain(cube_corners)
+            .fold(
+                (
+                    vec3(f32::MAX, f32::MAX, f32::MAX),
+                    vec3(f32::MIN, f32::MIN, f32::MIN),
+                ),
+                |(min, max), point| {
+                    (
+                        vec3(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+                        vec3(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+                    )
+                },
+            )
+    }
+
+    /// draw everything with `shader`, which has to be in use. the depth pass has no use for
+    /// the floor and cube materials
+    unsafe fn draw(
+        &self,
+        shader: &Shader,
+        materials: Option<(&MapMaterial, &MapMaterial)>,
+    ) -> Result<()> {
+        if let Some((floor, _)) = materials {
+            floor.upload(shader, "material")?;
+        }
+        shader.set_mat4(&CString::new("model")?, &self.floor.to_matrix());
+        shader.set_mat3(&CString::new("normalMatrix")?, &self.floor.normal_matrix());
+        gl::BindVertexArray(self.floor_vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+        if let Some((_, cube)) = materials {
+            cube.upload(shader, "material")?;
+        }
+        gl::BindVertexArray(self.cube_vao);
+        for cube in &self.cubes {
+            shader.set_mat4(&CString::new("model")?, &cube.to_matrix());
+            shader.set_mat3(&CString::new("normalMatrix")?, &cube.normal_matrix());
+            gl::DrawArrays(gl::TRIANGLES, 0, 36);
+        }
+        gl::BindVertexArray(0);
+        Ok(())
+    }
+}
+
+/// a directional light casting shadows through a depth map, with the usual fixes for its
+/// artifacts switchable at runtime
+pub fn main_5_3_1() -> Result<()> {
+    let mut glfw = glfw::init(fail_on_errors!())?;
+
+    glfw.window_hint(WindowHint::ContextVersionMajor(3));
+    glfw.window_hint(WindowHint::ContextVersionMinor(3));
+    glfw.window_hint(WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+
+    if let Some((mut window, events)) =
+        glfw.create_window(SCR_WIDTH, SCR_HEIGHT, "LearnOpenGL", WindowMode::Windowed)
+    {
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        // hide the cursor and keep it in the window, mouse movement turns the camera
+        window.set_cursor_mode(CursorMode::Disabled);
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+
+        let shader = Shader::new(
+            "src/_5_advanced_lighting/shaders/3.1.shadow_mapping.vs",
+            "src/_5_advanced_lighting/shaders/3.1.shadow_mapping.fs",
+        )?;
+        let mut shadow_map = ShadowMap::new(SHADOW_SIZE)?;
+
+        // counter-clockwise seen from outside, front face culling needs a consistent winding
+        #[rustfmt::skip]
+        let cube_vertices: [f32; 288] = [
+            // positions        // normals         // texture coords
+             0.5, -0.5, -0.5,   0.0,  0.0, -1.0,  0.0, 0.0,
+            -0.5, -0.5, -0.5,   0.0,  0.0, -1.0,  1.0, 0.0,
+            -0.5,  0.5, -0.5,   0.0,  0.0, -1.0,  1.0, 1.0,
+            -0.5,  0.5, -0.5,   0.0,  0.0, -1.0,  1.0, 1.0,
+             0.5,  0.5, -0.5,   0.0,  0.0, -1.0,  0.0, 1.0,
+             0.5, -0.5, -0.5,   0.0,  0.0, -1.0,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,   0.0,  0.0,  1.0,  0.0, 0.0,
+             0.5, -0.5,  0.5,   0.0,  0.0,  1.0,  1.0, 0.0,
+             0.5,  0.5,  0.5,   0.0,  0.0,  1.0,  1.0, 1.0,
+             0.5,  0.5,  0.5,   0.0,  0.0,  1.0,  1.0, 1.0,
+            -0.5,  0.5,  0.5,   0.0,  0.0,  1.0,  0.0, 1.0,
+            -0.5, -0.5,  0.5,   0.0,  0.0,  1.0,  0.0, 0.0,
+
+            -0.5,  0.5, -0.5,  -1.0,  0.0,  0.0,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  -1.0,  0.0,  0.0,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  -1.0,  0.0,  0.0,  1.0, 1.0,
+            -0.5, -0.5,  0.5,  -1.0,  0.0,  0.0,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  -1.0,  0.0,  0.0,  0.0, 1.0,
+            -0.5,  0.5, -0.5,  -1.0,  0.0,  0.0,  0.0, 0.0,
+
+             0.5, -0.5, -0.5,   1.0,  0.0,  0.0,  0.0, 0.0,
+             0.5,  0.5, -0.5,   1.0,  0.0,  0.0,  1.0, 0.0,
+             0.5,  0.5,  0.5,   1.0,  0.0,  0.0,  1.0, 1.0,
+             0.5,  0.5,  0.5,   1.0,  0.0,  0.0,  1.0, 1.0,
+             0.5, -0.5,  0.5,   1.0,  0.0,  0.0,  0.0, 1.0,
+             0.5, -0.5, -0.5,   1.0,  0.0,  0.0,  0.0, 0.0,
+
+            -0.5, -0.5,  0.5,   0.0, -1.0,  0.0,  0.0, 0.0,
+            -0.5, -0.5, -0.5,   0.0, -1.0,  0.0,  1.0, 0.0,
+             0.5, -0.5, -0.5,   0.0, -1.0,  0.0,  1.0, 1.0,
+             0.5, -0.5, -0.5,   0.0, -1.0,  0.0,  1.0, 1.0,
+             0.5, -0.5,  0.5,   0.0, -1.0,  0.0,  0.0, 1.0,
+            -0.5, -0.5,  0.5,   0.0, -1.0,  0.0,  0.0, 0.0,
+
+            -0.5,  0.5, -0.5,   0.0,  1.0,  0.0,  0.0, 0.0,
+            -0.5,  0.5,  0.5,   0.0,  1.0,  0.0,  1.0, 0.0,
+             0.5,  0.5,  0.5,   0.0,  1.0,  0.0,  1.0, 1.0,
+             0.5,  0.5,  0.5,   0.0,  1.0,  0.0,  1.0, 1.0,
+             0.5,  0.5, -0.5,   0.0,  1.0,  0.0,  0.0, 1.0,
+            -0.5,  0.5, -0.5,   0.0,  1.0,  0.0,  0.0, 0.0,
+        ];
+        let e = FLOOR_EXTENT;
+        #[rustfmt::skip]
+        let floor_vertices: [f32; 48] = [
+            // positions  // normals      // texture coords
+            -e, 0.0, -e,  0.0, 1.0, 0.0,  0.0, e,
+            -e, 0.0,  e,  0.0, 1.0, 0.0,  0.0, 0.0,
+             e, 0.0,  e,  0.0, 1.0, 0.0,  e, 0.0,
+
+             e, 0.0,  e,  0.0, 1.0, 0.0,  e, 0.0,
+             e, 0.0, -e,  0.0, 1.0, 0.0,  e, e,
+            -e, 0.0, -e,  0.0, 1.0, 0.0,  0.0, e,
+        ];
+
+        let layout = VertexLayout::packed(
+            &[
+                (0, AttributeType::Vec3),
+                (1, AttributeType::Vec3),
+                (2, AttributeType::Vec2),
+            ],
+            0,
+        );
+        let mut vaos: [GLuint; 2] = [0; 2];
+        let mut vbos: [GLuint; 2] = [0; 2];
+        unsafe {
+            gl::GenVertexArrays(2, vaos.as_mut_ptr());
+            gl::GenBuffers(2, vbos.as_mut_ptr());
+            let buffers: [&[f32]; 2] = [&cube_vertices, &floor_vertices];
+            for ((vao, vbo), vertices) in vaos.iter().zip(vbos).zip(buffers) {
+                gl::BindVertexArray(*vao);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    mem::size_of_val(vertices) as GLsizeiptr,
+                    vertices.as_ptr() as *const _,
+                    gl::STATIC_DRAW,
+                );
+                layout.apply();
+            }
+            gl::BindVertexArray(0);
+        }
+
+        let scene = Scene {
+            cube_vao: vaos[0],
+            floor_vao: vaos[1],
+            floor: Transform::from_translation(vec3(0.0, -0.5, 0.0)),
+            cubes: [
+                Transform::from_translation(vec3(0.0, 1.5, 0.0)),
+                Transform::from_translation(vec3(2.0, 0.0, 1.0)),
+                Transform {
+                    translation: vec3(-1.0, 0.0, 2.0),
+                    scale: vec3(0.5, 0.5, 0.5),
+                    ..Transform::from_axis_angle(vec3(1.0, 0.0, 1.0), Deg(60.0))
+                },
+            ],
+        };
+        let (scene_min, scene_max) = scene.bounds();
+
+        let floor_material = MapMaterial {
+            diffuse: Texture2D::from_path(
+                "resources/textures/marble.jpg",
+                TextureOptions::default(),
+            )?,
+            specular: Texture2D::solid([64, 64, 64, 255])?,
+            emission: Texture2D::solid([0, 0, 0, 255])?,
+            shininess: 64.0,
+        };
+        let cube_material = MapMaterial {
+            diffuse: Texture2D::from_path(
+                "resources/textures/container2.png",
+                TextureOptions::default(),
+            )?,
+            specular: Texture2D::from_path(
+                "resources/textures/container2_specular.png",
+                TextureOptions::default(),
+            )?,
+            emission: Texture2D::solid([0, 0, 0, 255])?,
+            shininess: 32.0,
+        };
+        let mut light = DirectionalLight {
+            direction: vec3(2.0, -4.0, 1.0),
+            color: LightColor {
+                ambient: vec3(0.15, 0.15, 0.15),
+                diffuse: vec3(0.8, 0.8, 0.8),
+                specular: vec3(0.5, 0.5, 0.5),
+            },
+        };
+        // angle of the light around the vertical axis, and its distance from it
+        let mut light_angle = light.direction.z.atan2(light.direction.x);
+        let light_radius = light.direction.x.hypot(light.direction.z);
+
+        let mut input = InputState {
+            camera: Camera::with_angles(Point3::new(0.0, 3.0, 8.0), -90.0, -20.0),
+            cursor: CursorTracker::default(),
+            framebuffer_size: window.get_framebuffer_size(),
+            filter: ShadowFilter::default(),
+            depth_bias: true,
+            bias_scale: 1.0,
+            normal_offset: true,
+            polygon_offset: false,
+            cull_front_faces: true,
+            show_depth_map: false,
+            orbit_light: false,
+        };
+        println!(
+            "1/2/3 filter, B depth bias, Up/Down bias scale, N normal offset, O polygon offset, \
+             C front face culling, V depth map, R orbit the light"
+        );
+        input.print_settings();
+
+        // time between the current and the last frame
+        let mut last_frame = glfw.get_time() as f32;
+
+        while !window.should_close() {
+            let current_frame = glfw.get_time() as f32;
+            let delta_time = current_frame - last_frame;
+            last_frame = current_frame;
+
+            process_events(&mut window, &events, &mut input);
+            process_input(&window, &mut input.camera, delta_time);
+
+            if input.orbit_light {
+                light_angle += 0.5 * delta_time;
+                light.direction = vec3(
+                    light_radius * light_angle.cos(),
+                    light.direction.y,
+                    light_radius * light_angle.sin(),
+                );
+            }
+            shadow_map.filter = input.filter;
+            shadow_map.bias = input.bias();
+            shadow_map.light_space = fit_light_space(light.direction, scene_min, scene_max);
+
+            let (width, height) = input.framebuffer_size;
+
+            // NOTE: start rendering process here
+            unsafe {
+                // 1. the scene's depth as seen from the light
+                shadow_map.begin_depth_pass()?;
+                scene.draw(shadow_map.depth_shader(), None)?;
+                shadow_map.end_depth_pass(input.framebuffer_size);
+
+                // 2. the scene from the camera, comparing against the depth map
+                gl::ClearColor(0.1, 0.1, 0.1, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+                shader.use_program();
+                shader.set_mat4(
+                    &CString::new("projection")?,
+                    &input.camera.projection_matrix(aspect_ratio(width, height)),
+                );
+                shader.set_mat4(&CString::new("view")?, &input.camera.view_matrix());
+                shader.set_vector3(&CString::new("viewPos")?, &input.camera.position.to_vec());
+                light.upload(&shader, "dirLight")?;
+                shadow_map.upload(&shader, "shadow")?;
+                scene.draw(&shader, Some((&floor_material, &cube_material)))?;
+
+                if input.show_depth_map {
+                    let size = height / 3;
+                    gl::Viewport(0, 0, size, size);
+                    shadow_map.draw_debug();
+                    gl::Viewport(0, 0, width, height);
+                }
+            }
+            window.swap_buffers();
+            glfw.poll_events();
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(2, vaos.as_ptr());
+            gl::DeleteBuffers(2, vbos.as_ptr());
+        }
+    }
+    Ok(())
+}
+
+fn process_events(
+    window: &mut glfw::Window,
+    events: &GlfwReceiver<(f64, glfw::WindowEvent)>,
+    input: &mut InputState,
+) {
+    for (_, event) in glfw::flush_messages(events) {
+        match event {
+            glfw::WindowEvent::FramebufferSize(width, height) => {
+                // make sure the viewport matches the new window dimensions; note that width and
+                // height will be significantly larger than specified on retina displays.
+                unsafe { gl::Viewport(0, 0, width, height) }
+                input.framebuffer_size = (width, height);
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                window.set_should_close(true)
+            }
+            glfw::WindowEvent::Key(key, _, Action::Press, _) => {
+                match key {
+                    Key::Num1 => input.filter = ShadowFilter::Single,
+                    Key::Num2 => input.filter = ShadowFilter::Pcf3x3,
+                    Key::Num3 => input.filter = ShadowFilter::Poisson,
+                    Key::B => input.depth_bias = !input.depth_bias,
+                    Key::Up => input.bias_scale = (input.bias_scale * 2.0).min(16.0),
+                    Key::Down => input.bias_scale = (input.bias_scale / 2.0).max(0.125),
+                    Key::N => input.normal_offset = !input.normal_offset,
+                    Key::O => input.polygon_offset = !input.polygon_offset,
+                    Key::C => input.cull_front_faces = !input.cull_front_faces,
+                    Key::V => {
+                        input.show_depth_map = !input.show_depth_map;
+                        continue;
+                    }
+                    Key::R => {
+                        input.orbit_light = !input.orbit_light;
+                        continue;
+                    }
+                    _ => continue,
+                }
+                input.print_settings();
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x_offset, y_offset) = input.cursor.offset(x, y);
+                input
+                    .camera
+                    .process_mouse_movement(x_offset, y_offset, true);
+            }
+            glfw::WindowEvent::Scroll(_, y_offset) => {
+                input.camera.process_mouse_scroll(y_offset as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// movement keys are polled every frame so holding a key moves the camera smoothly
+fn process_input(window: &glfw::Window, camera: &mut Camera, delta_time: f32) {
+    let bindings = [
+        (Key::W, CameraMovement::Forward),
+        (Key::S, CameraMovement::Backward),
+        (Key::A, CameraMovement::Left),
+        (Key::D, CameraMovement::Right),
+        (Key::Space, CameraMovement::Up),
+        (Key::LeftControl, CameraMovement::Down),
+    ];
+    for (key, movement) in bindings {
+        if window.get_key(key) == Action::Press {
+            camera.process_keyboard(movement, delta_time);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+// advanced lighting chapter
+pub mod _3_1_shadow_mapping;
+
+pub use _3_1_shadow_mapping::*;
@@ -0,0 +1,113 @@
+#version 330 core
+out vec4 FragColor;
+
+struct Material {
+    sampler2D diffuse;
+    sampler2D specular;
+    float shininess;
+};
+
+struct DirLight {
+    vec3 direction;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct ShadowBias {
+    float constant;
+    float slope;
+    float max;
+    float normalOffset;
+};
+
+struct Shadow {
+    sampler2DShadow map;
+    mat4 lightSpaceMatrix;
+    // 0 single tap, 1 3x3 pcf, 2 poisson pcf
+    int filterMode;
+    float poissonRadius;
+    ShadowBias bias;
+};
+
+in vec3 FragPos;
+in vec3 Normal;
+in vec2 TexCoords;
+
+uniform vec3 viewPos;
+uniform Material material;
+uniform DirLight dirLight;
+uniform Shadow shadow;
+
+const vec2 POISSON_DISK[16] = vec2[](
+    vec2(-0.94201624, -0.39906216), vec2(0.94558609, -0.76890725),
+    vec2(-0.09418410, -0.92938870), vec2(0.34495938, 0.29387760),
+    vec2(-0.91588581, 0.45771432), vec2(-0.81544232, -0.87912464),
+    vec2(-0.38277543, 0.27676845), vec2(0.97484398, 0.75648379),
+    vec2(0.44323325, -0.97511554), vec2(0.53742981, -0.47373420),
+    vec2(-0.26496911, -0.41893023), vec2(0.79197514, 0.19090188),
+    vec2(-0.24188840, 0.99706507), vec2(-0.81409955, 0.91437590),
+    vec2(0.19984126, 0.78641367), vec2(0.14383161, -0.14100790)
+);
+
+float ShadowFactor(vec3 normal, vec3 lightDir);
+
+void main()
+{
+    vec3 color = texture(material.diffuse, TexCoords).rgb;
+    vec3 normal = normalize(Normal);
+    vec3 lightDir = normalize(-dirLight.direction);
+    vec3 viewDir = normalize(viewPos - FragPos);
+
+    // blinn-phong
+    vec3 ambient = dirLight.ambient * color;
+    vec3 diffuse = dirLight.diffuse * max(dot(normal, lightDir), 0.0) * color;
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), material.shininess);
+    vec3 specular = dirLight.specular * spec * texture(material.specular, TexCoords).rgb;
+
+    // the shadow only takes away the direct light
+    FragColor = vec4(ambient + ShadowFactor(normal, lightDir) * (diffuse + specular), 1.0);
+}
+
+// 1.0 where the light reaches the fragment, 0.0 in full shadow
+float ShadowFactor(vec3 normal, vec3 lightDir)
+{
+    float cosTheta = clamp(dot(normal, lightDir), 0.0, 1.0);
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+
+    // look up a point moved out of the surface, further at grazing angles
+    vec3 position = FragPos + normal * shadow.bias.normalOffset * sinTheta;
+    vec4 lightSpace = shadow.lightSpaceMatrix * vec4(position, 1.0);
+    vec3 coords = lightSpace.xyz / lightSpace.w * 0.5 + 0.5;
+    // beyond the light's far plane isn't covered by the map
+    if (coords.z > 1.0)
+        return 1.0;
+
+    // slope-scaled: one texel covers more depth the more the surface faces away
+    float tanTheta = sinTheta / max(cosTheta, 0.001);
+    float bias = min(shadow.bias.constant + shadow.bias.slope * tanTheta, shadow.bias.max);
+    float depth = coords.z - bias;
+
+    if (shadow.filterMode == 0)
+        return texture(shadow.map, vec3(coords.xy, depth));
+
+    vec2 texelSize = 1.0 / vec2(textureSize(shadow.map, 0));
+    float lit = 0.0;
+    if (shadow.filterMode == 1) {
+        for (int x = -1; x <= 1; ++x)
+            for (int y = -1; y <= 1; ++y)
+                lit += texture(shadow.map, vec3(coords.xy + vec2(x, y) * texelSize, depth));
+        return lit / 9.0;
+    }
+
+    // rotating the disk per pixel trades the banding of a fixed pattern for noise
+    float angle = 6.2831853 * fract(sin(dot(gl_FragCoord.xy, vec2(12.9898, 78.233))) * 43758.5453);
+    mat2 rotation = mat2(cos(angle), sin(angle), -sin(angle), cos(angle));
+    for (int i = 0; i < 16; ++i) {
+        vec2 offset = rotation * POISSON_DISK[i] * shadow.poissonRadius * texelSize;
+        lit += texture(shadow.map, vec3(coords.xy + offset, depth));
+    }
+    return lit / 16.0;
+}
@@ -0,0 +1,328 @@
+use cgmath::{vec3, Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Rad, Vector3};
+
+use crate::projection::{perspective_projection, DepthMode};
+
+/// possible directions of keyboard movement, decoupled from any windowing system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMovement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// default camera values
+pub const YAW: f32 = -90.0;
+pub const PITCH: f32 = 0.0;
+pub const SPEED: f32 = 2.5;
+pub const SENSITIVITY: f32 = 0.1;
+pub const ZOOM: f32 = 45.0;
+/// looking straight up or down flips the view because front and world up become parallel
+pub const MAX_PITCH: f32 = 89.0;
+pub const MIN_ZOOM: f32 = 1.0;
+pub const MAX_ZOOM: f32 = 45.0;
+
+/// fly camera driven by euler angles, angles are in degrees
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub position: Point3<f32>,
+    pub front: Vector3<f32>,
+    pub up: Vector3<f32>,
+    pub right: Vector3<f32>,
+    pub world_up: Vector3<f32>,
+    /// -90 looks down -z
+    pub yaw: f32,
+    pub pitch: f32,
+    /// units per second
+    pub movement_speed: f32,
+    /// degrees per pixel of cursor movement
+    pub mouse_sensitivity: f32,
+    /// vertical field of view in degrees
+    pub zoom: f32,
+    pub near: f32,
+    pub far: f32,
+    pub depth: DepthMode,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(Point3::new(0.0, 0.0, 0.0))
+    }
+}
+
+#[allow(dead_code)]
+impl Camera {
+    pub fn new(position: Point3<f32>) -> Camera {
+        Self::with_angles(position, YAW, PITCH)
+    }
+
+    pub fn with_angles(position: Point3<f32>, yaw: f32, pitch: f32) -> Camera {
+        let mut camera = Camera {
+            position,
+            front: vec3(0.0, 0.0, -1.0),
+            up: vec3(0.0, 1.0, 0.0),
+            right: vec3(1.0, 0.0, 0.0),
+            world_up: vec3(0.0, 1.0, 0.0),
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            movement_speed: SPEED,
+            mouse_sensitivity: SENSITIVITY,
+            zoom: ZOOM,
+            near: 0.1,
+            far: 100.0,
+            depth: DepthMode::Standard,
+        };
+        camera.update_camera_vectors();
+        camera
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_to_rh(self.position, self.front, self.up)
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Matrix4<f32> {
+        perspective_projection(Deg(self.zoom), aspect, self.near, self.far, self.depth)
+    }
+
+    /// move along the camera axes, scaled by the frame time so the speed does not depend on
+    /// the frame rate. up and down follow the world up axis
+    pub fn process_keyboard(&mut self, direction: CameraMovement, delta_time: f32) {
+        let velocity = self.movement_speed * delta_time;
+        self.position += match direction {
+            CameraMovement::Forward => self.front,
+            CameraMovement::Backward => -self.front,
+            CameraMovement::Left => -self.right,
+            CameraMovement::Right => self.right,
+            CameraMovement::Up => self.world_up,
+            CameraMovement::Down => -self.world_up,
+        } * velocity;
+    }
+
+    /// turn by a cursor offset in pixels, y goes up
+    pub fn process_mouse_movement(&mut self, x_offset: f32, y_offset: f32, constrain_pitch: bool) {
+        self.yaw += x_offset * self.mouse_sensitivity;
+        self.pitch += y_offset * self.mouse_sensitivity;
+        if constrain_pitch {
+            self.pitch = self.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        }
+        self.update_camera_vectors();
+    }
+
+    /// narrow or widen the field of view with the scroll wheel
+    pub fn process_mouse_scroll(&mut self, y_offset: f32) {
+        self.zoom = (self.zoom - y_offset).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    /// take over the current view of an orbit camera, e.g. when switching camera modes
+    pub fn set_from_orbit(&mut self, orbit: &OrbitCamera) {
+        self.position = orbit.position();
+        self.yaw = orbit.current.yaw;
+        self.pitch = orbit.current.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.zoom = orbit.zoom;
+        self.update_camera_vectors();
+    }
+
+    /// recalculate front, right and up from the euler angles
+    fn update_camera_vectors(&mut self) {
+        self.front = direction(self.yaw, self.pitch);
+        // normalize, their length gets closer to 0 the more you look up or down
+        self.right = self.front.cross(self.world_up).normalize();
+        self.up = self.right.cross(self.front).normalize();
+    }
+}
+
+/// unit vector for the given yaw and pitch in degrees
+fn direction(yaw: f32, pitch: f32) -> Vector3<f32> {
+    let (yaw, pitch) = (yaw.to_radians(), pitch.to_radians());
+    vec3(
+        yaw.cos() * pitch.cos(),
+        pitch.sin(),
+        yaw.sin() * pitch.cos(),
+    )
+    .normalize()
+}
+
+/// where an orbit camera looks from, yaw and pitch give the viewing direction like for `Camera`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitState {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl OrbitState {
+    /// move a fraction `t` of the way towards `goal`
+    fn lerp(&self, goal: &OrbitState, t: f32) -> OrbitState {
+        OrbitState {
+            target: self.target + (goal.target - self.target) * t,
+            distance: self.distance + (goal.distance - self.distance) * t,
+            yaw: self.yaw + (goal.yaw - self.yaw) * t,
+            pitch: self.pitch + (goal.pitch - self.pitch) * t,
+        }
+    }
+}
+
+/// camera circling a target point, for inspecting a single object. input changes `goal` and
+/// `update` eases `current` towards it
+#[derive(Debug, Clone, Copy)]
+pub struct OrbitCamera {
+    pub goal: OrbitState,
+    pub current: OrbitState,
+    /// how quickly the view catches up with the input per second, 0 disables smoothing
+    pub damping: f32,
+    /// degrees per pixel of dragging
+    pub rotate_sensitivity: f32,
+    /// fraction of the distance moved per pixel of dragging
+    pub pan_sensitivity: f32,
+    /// distance factor per scroll step
+    pub dolly_factor: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// vertical field of view in degrees
+    pub zoom: f32,
+    pub near: f32,
+    pub far: f32,
+    pub depth: DepthMode,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self::new(Point3::new(0.0, 0.0, 0.0), 3.0)
+    }
+}
+
+#[allow(dead_code)]
+impl OrbitCamera {
+    /// look at `target` along -z from `distance` away
+    pub fn new(target: Point3<f32>, distance: f32) -> OrbitCamera {
+        let state = OrbitState {
+            target,
+            distance,
+            yaw: YAW,
+            pitch: PITCH,
+        };
+        OrbitCamera {
+            goal: state,
+            current: state,
+            damping: 12.0,
+            rotate_sensitivity: 0.3,
+            pan_sensitivity: 0.002,
+            dolly_factor: 1.1,
+            min_distance: 0.1,
+            max_distance: 100.0,
+            zoom: ZOOM,
+            near: 0.1,
+            far: 100.0,
+            depth: DepthMode::Standard,
+        }
+    }
+
+    /// the current eye position
+    pub fn position(&self) -> Point3<f32> {
+        self.current.target - self.front() * self.current.distance
+    }
+
+    pub fn front(&self) -> Vector3<f32> {
+        direction(self.current.yaw, self.current.pitch)
+    }
+
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        Matrix4::look_at_rh(self.position(), self.current.target, vec3(0.0, 1.0, 0.0))
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Matrix4<f32> {
+        perspective_projection(Deg(self.zoom), aspect, self.near, self.far, self.depth)
+    }
+
+    /// circle around the target by a drag offset in pixels, y goes up
+    pub fn orbit(&mut self, x_offset: f32, y_offset: f32) {
+        self.goal.yaw += x_offset * self.rotate_sensitivity;
+        self.goal.pitch =
+            (self.goal.pitch + y_offset * self.rotate_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// move the target in the view plane so it follows the cursor, scaled by the distance
+    pub fn pan(&mut self, x_offset: f32, y_offset: f32) {
+        let front = direction(self.goal.yaw, self.goal.pitch);
+        let right = front.cross(vec3(0.0, 1.0, 0.0)).normalize();
+        let up = right.cross(front);
+        let scale = self.goal.distance * self.pan_sensitivity;
+        self.goal.target += (up * y_offset - right * x_offset) * scale;
+    }
+
+    /// move towards (positive steps) or away from the target
+    pub fn dolly(&mut self, steps: f32) {
+        self.goal.distance = (self.goal.distance * self.dolly_factor.powf(-steps))
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    /// ease the current view towards the goal, independent of the frame rate
+    pub fn update(&mut self, delta_time: f32) {
+        let t = if self.damping <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-self.damping * delta_time).exp()
+        };
+        self.current = self.current.lerp(&self.goal, t);
+    }
+
+    /// jump to the goal without easing
+    pub fn snap(&mut self) {
+        self.current = self.goal;
+    }
+
+    /// aim at the centre of an axis aligned box and back off until its bounding sphere fits
+    /// both the vertical and the horizontal field of view. the direction is kept
+    pub fn frame_bounds(&mut self, min: Point3<f32>, max: Point3<f32>, aspect: f32) {
+        let center = min.midpoint(max);
+        let radius = (max - min).magnitude() / 2.0;
+        let half_vertical = Rad::from(Deg(self.zoom)).0 / 2.0;
+        let half_horizontal = (half_vertical.tan() * aspect).atan();
+        let half_fov = half_vertical.min(half_horizontal);
+
+        self.goal.target = center;
+        self.goal.distance = (radius / half_fov.sin())
+            .max(self.near + radius)
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    /// take over the view of a fly camera, orbiting the point `distance` in front of it
+    pub fn set_from_camera(&mut self, camera: &Camera, distance: f32) {
+        self.goal = OrbitState {
+            target: camera.position + camera.front * distance,
+            distance,
+            yaw: camera.yaw,
+            pitch: camera.pitch,
+        };
+        self.current = self.goal;
+        self.zoom = camera.zoom;
+    }
+}
+
+/// turns absolute cursor positions into per-event offsets
+#[derive(Debug, Default)]
+pub struct CursorTracker {
+    last: Option<(f64, f64)>,
+}
+
+#[allow(dead_code)]
+impl CursorTracker {
+    /// offset since the previous position, with y going up. the first event only records the
+    /// position, otherwise the camera jumps to wherever the cursor entered the window
+    pub fn offset(&mut self, x: f64, y: f64) -> (f32, f32) {
+        let offset = match self.last {
+            Some((last_x, last_y)) => ((x - last_x) as f32, (last_y - y) as f32),
+            None => (0.0, 0.0),
+        };
+        self.last = Some((x, y));
+        offset
+    }
+
+    /// forget the last position, e.g. after the cursor was released and captured again
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
@@ -0,0 +1,327 @@
+// DirectDraw Surface container, including the DX10 header extension
+
+use anyhow::{bail, Context, Result};
+
+use super::{check_level_count, read_u32, slice, BlockFormat, DataFormat, TextureData};
+
+pub const MAGIC: [u8; 4] = *b"DDS ";
+
+const HEADER_SIZE: usize = 4 + 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+fn four_cc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// formats named by the DX10 header
+fn dxgi_format(format: u32) -> Result<DataFormat> {
+    let compressed = |block, srgb| Ok(DataFormat::Compressed { block, srgb });
+    match format {
+        2 => Ok(DataFormat::uncompressed(
+            gl::RGBA32F,
+            gl::RGBA,
+            gl::FLOAT,
+            16,
+        )),
+        10 => Ok(DataFormat::uncompressed(
+            gl::RGBA16F,
+            gl::RGBA,
+            gl::HALF_FLOAT,
+            8,
+        )),
+        28 => Ok(DataFormat::rgba8(false)),
+        29 => Ok(DataFormat::rgba8(true)),
+        49 => Ok(DataFormat::uncompressed(
+            gl::RG8,
+            gl::RG,
+            gl::UNSIGNED_BYTE,
+            2,
+        )),
+        61 => Ok(DataFormat::uncompressed(
+            gl::R8,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            1,
+        )),
+        71 => compressed(BlockFormat::Bc1Rgba, false),
+        72 => compressed(BlockFormat::Bc1Rgba, true),
+        74 => compressed(BlockFormat::Bc2, false),
+        75 => compressed(BlockFormat::Bc2, true),
+        77 => compressed(BlockFormat::Bc3, false),
+        78 => compressed(BlockFormat::Bc3, true),
+        80 => compressed(BlockFormat::Bc4, false),
+        83 => compressed(BlockFormat::Bc5, false),
+        87 => Ok(DataFormat::uncompressed(
+            gl::RGBA8,
+            gl::BGRA,
+            gl::UNSIGNED_BYTE,
+            4,
+        )),
+        91 => Ok(DataFormat::uncompressed(
+            gl::SRGB8_ALPHA8,
+            gl::BGRA,
+            gl::UNSIGNED_BYTE,
+            4,
+        )),
+        98 => compressed(BlockFormat::Bc7, false),
+        99 => compressed(BlockFormat::Bc7, true),
+        other => bail!("unsupported DXGI format {}", other),
+    }
+}
+
+/// formats described by the legacy pixel format block
+fn legacy_format(bytes: &[u8]) -> Result<DataFormat> {
+    let flags = read_u32(bytes, 80)?;
+    let code = read_u32(bytes, 84)?;
+    let bit_count = read_u32(bytes, 88)?;
+    let red_mask = read_u32(bytes, 92)?;
+
+    if flags & DDPF_FOURCC != 0 {
+        let compressed = |block| Ok(DataFormat::Compressed { block, srgb: false });
+        return match code {
+            c if c == four_cc(b"DXT1") => {
+                if flags & DDPF_ALPHAPIXELS != 0 {
+                    compressed(BlockFormat::Bc1Rgba)
+                } else {
+                    compressed(BlockFormat::Bc1Rgb)
+                }
+            }
+            c if c == four_cc(b"DXT2") || c == four_cc(b"DXT3") => compressed(BlockFormat::Bc2),
+            c if c == four_cc(b"DXT4") || c == four_cc(b"DXT5") => compressed(BlockFormat::Bc3),
+            c if c == four_cc(b"ATI1") || c == four_cc(b"BC4U") => compressed(BlockFormat::Bc4),
+            c if c == four_cc(b"ATI2") || c == four_cc(b"BC5U") => compressed(BlockFormat::Bc5),
+            // D3DFMT_A16B16G16R16F and D3DFMT_A32B32G32R32F
+            113 => Ok(DataFormat::uncompressed(
+                gl::RGBA16F,
+                gl::RGBA,
+                gl::HALF_FLOAT,
+                8,
+            )),
+            116 => Ok(DataFormat::uncompressed(
+                gl::RGBA32F,
+                gl::RGBA,
+                gl::FLOAT,
+                16,
+            )),
+            other => bail!(
+                "unsupported DDS fourCC {:?}",
+                String::from_utf8_lossy(&other.to_le_bytes())
+            ),
+        };
+    }
+
+    match (flags & (DDPF_RGB | DDPF_LUMINANCE), bit_count, red_mask) {
+        (DDPF_RGB, 32, 0x00ff_0000) => Ok(DataFormat::uncompressed(
+            gl::RGBA8,
+            gl::BGRA,
+            gl::UNSIGNED_BYTE,
+            4,
+        )),
+        (DDPF_RGB, 32, 0x0000_00ff) => Ok(DataFormat::rgba8(false)),
+        (DDPF_RGB, 24, 0x00ff_0000) => Ok(DataFormat::uncompressed(
+            gl::RGB8,
+            gl::BGR,
+            gl::UNSIGNED_BYTE,
+            3,
+        )),
+        (DDPF_RGB, 24, 0x0000_00ff) => Ok(DataFormat::uncompressed(
+            gl::RGB8,
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            3,
+        )),
+        (DDPF_LUMINANCE, 8, _) => Ok(DataFormat::uncompressed(
+            gl::R8,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            1,
+        )),
+        _ => bail!(
+            "unsupported uncompressed DDS layout ({} bits, red mask {:#x})",
+            bit_count,
+            red_mask
+        ),
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Result<TextureData> {
+    if !bytes.starts_with(&MAGIC) {
+        bail!("missing DDS magic");
+    }
+    if read_u32(bytes, 4)? != 124 {
+        bail!("DDS header has the wrong size");
+    }
+
+    let flags = read_u32(bytes, 8)?;
+    let height = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 16)?;
+    let mip_count = read_u32(bytes, 28)?;
+    let caps2 = read_u32(bytes, 112)?;
+    let code = read_u32(bytes, 84)?;
+
+    if caps2 & DDSCAPS2_VOLUME != 0 {
+        bail!("volume textures are not supported");
+    }
+
+    let (format, layers, cubemap, data_offset) = if code == four_cc(b"DX10") {
+        let format = dxgi_format(read_u32(bytes, HEADER_SIZE)?)?;
+        let misc = read_u32(bytes, HEADER_SIZE + 8)?;
+        let array_size = read_u32(bytes, HEADER_SIZE + 12)?.max(1);
+        let cubemap = misc & DDS_RESOURCE_MISC_TEXTURECUBE != 0;
+        (format, array_size, cubemap, HEADER_SIZE + DX10_HEADER_SIZE)
+    } else {
+        let cubemap = caps2 & DDSCAPS2_CUBEMAP != 0;
+        (legacy_format(bytes)?, 1, cubemap, HEADER_SIZE)
+    };
+
+    let level_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+        check_level_count(mip_count, width, height)?;
+        mip_count.max(1) as usize
+    } else {
+        1
+    };
+    let faces = if cubemap { 6 } else { 1 };
+    let images = layers
+        .checked_mul(faces)
+        .with_context(|| format!("{} layers with {} faces", layers, faces))?;
+
+    let mut texture = TextureData {
+        format,
+        width,
+        height,
+        layers,
+        faces,
+        levels: vec![Vec::new(); level_count],
+        generate_mipmaps: false,
+    };
+
+    // DDS stores every image with its whole mip chain (layer, face, level), regroup per level
+    let mut offset = data_offset;
+    for _ in 0..images {
+        for level in 0..level_count {
+            let size = texture.image_size(level);
+            let image = slice(bytes, offset, size)?;
+            texture.levels[level].extend_from_slice(image);
+            offset += size;
+        }
+    }
+
+    texture.validate()?;
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DDSD_REQUIRED: u32 = 0x1 | 0x2 | 0x4 | 0x1000;
+
+    /// a DDS file storing `data` under a legacy header with the given fourCC
+    fn dds(
+        width: u32,
+        height: u32,
+        mip_count: Option<u32>,
+        code: &[u8; 4],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        let mut write = |offset: usize, value: u32| {
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        write(4, 124);
+        write(8, DDSD_REQUIRED | mip_count.map_or(0, |_| DDSD_MIPMAPCOUNT));
+        write(12, height);
+        write(16, width);
+        write(28, mip_count.unwrap_or(0));
+        write(76, 32);
+        write(80, DDPF_FOURCC);
+        write(84, four_cc(code));
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        format!("{:#}", parse(bytes).unwrap_err())
+    }
+
+    #[test]
+    fn single_level() {
+        let block = [0xff, 0xff, 0, 0, 0, 0, 0, 0];
+        let texture = parse(&dds(4, 4, None, b"DXT1", &block)).unwrap();
+        assert_eq!((texture.width, texture.height), (4, 4));
+        assert_eq!(
+            texture.format,
+            DataFormat::Compressed {
+                block: BlockFormat::Bc1Rgb,
+                srgb: false
+            }
+        );
+        assert_eq!(texture.levels, vec![block.to_vec()]);
+    }
+
+    #[test]
+    fn mip_chain() {
+        // 8x8 is 2x2 blocks, every smaller level still takes a whole block
+        let data: Vec<u8> = (0..(4 + 1 + 1 + 1) * 16).map(|i| i as u8).collect();
+        let texture = parse(&dds(8, 8, Some(4), b"DXT5", &data)).unwrap();
+        assert_eq!(texture.levels.len(), 4);
+        assert_eq!(texture.levels[0], &data[..64]);
+        assert_eq!(texture.levels[3], &data[96..]);
+        assert_eq!(texture.level_size(3), (1, 1));
+    }
+
+    #[test]
+    fn dx10_cubemap_array() {
+        let mut bytes = dds(4, 4, None, b"DX10", &[]);
+        // DXGI_FORMAT_R8G8B8A8_UNORM, 2D, cubemap, 2 layers
+        for value in [28u32, 3, DDS_RESOURCE_MISC_TEXTURECUBE, 2, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend((0..2 * 6).flat_map(|image| [image as u8; 64]));
+        let texture = parse(&bytes).unwrap();
+        assert!(texture.is_cubemap());
+        assert_eq!(texture.layers, 2);
+        assert_eq!(texture.image(0, 1, 2), [8; 64]);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let bytes = dds(8, 8, None, b"DXT1", &[0; 32]);
+        assert!(error(&bytes[..bytes.len() - 1]).contains("past the end of the file"));
+        assert!(error(&bytes[..60]).contains("file truncated"));
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut bytes = dds(4, 4, None, b"DXT1", &[0; 8]);
+        bytes[0] = b'X';
+        assert!(error(&bytes).contains("missing DDS magic"));
+    }
+
+    #[test]
+    fn oversized_mip_count() {
+        for mip_count in [5, 32, 33, u32::MAX] {
+            let bytes = dds(8, 8, Some(mip_count), b"DXT1", &[0; 56]);
+            assert!(error(&bytes).contains("at most 4"), "{} levels", mip_count);
+        }
+    }
+
+    #[test]
+    fn overflowing_layer_count() {
+        let mut bytes = dds(4, 4, None, b"DX10", &[]);
+        for value in [28u32, 3, DDS_RESOURCE_MISC_TEXTURECUBE, u32::MAX, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        assert!(error(&bytes).contains("layers with 6 faces"));
+    }
+}
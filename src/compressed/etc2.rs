@@ -0,0 +1,279 @@
+// CPU decoders for the ETC2 colour and EAC alpha block formats, every function decodes one 4x4
+// block into RGBA8 texels in row-major order. ETC2 stores its texel indices column by column,
+// texel `x * 4 + y` in the index bits is texel `y * 4 + x` in the output.
+
+/// intensity modifiers of the individual/differential modes, negated for indices 2 and 3
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// distances of the T and H modes
+const ETC_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+#[rustfmt::skip]
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn extend4(value: u8) -> i32 {
+    (value as i32) * 17
+}
+
+fn extend5(value: u8) -> i32 {
+    ((value << 3) | (value >> 2)) as i32
+}
+
+fn extend6(value: u8) -> i32 {
+    ((value << 2) | (value >> 4)) as i32
+}
+
+fn extend7(value: u8) -> i32 {
+    ((value << 1) | (value >> 6)) as i32
+}
+
+fn clamp(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+fn offset_color(color: [i32; 3], offset: i32) -> [u8; 4] {
+    [
+        clamp(color[0] + offset),
+        clamp(color[1] + offset),
+        clamp(color[2] + offset),
+        255,
+    ]
+}
+
+/// 2-bit index of the texel at (x, y), msb in the upper half of the low 32 bits
+fn texel_index(bits: u64, x: usize, y: usize) -> usize {
+    let j = x * 4 + y;
+    let msb = (bits >> (16 + j)) & 1;
+    let lsb = (bits >> j) & 1;
+    ((msb << 1) | lsb) as usize
+}
+
+/// decode an ETC2 RGB block, `punchthrough` selects the RGB8A1 variant where the
+/// differential bit tells whether the block is opaque
+pub fn decode_etc2_rgb(block: &[u8], punchthrough: bool) -> [[u8; 4]; 16] {
+    let bits = u64::from_be_bytes(block[0..8].try_into().unwrap());
+    let b = &block[0..8];
+    let diff_bit = b[3] & 2 != 0;
+    // RGB8A1 has no individual mode, the bit means "opaque" there
+    let (differential, opaque) = if punchthrough {
+        (true, diff_bit)
+    } else {
+        (diff_bit, true)
+    };
+
+    if differential {
+        let r = (b[0] >> 3) as i32;
+        let g = (b[1] >> 3) as i32;
+        let bl = (b[2] >> 3) as i32;
+        // 3-bit two's complement deltas
+        let delta = |byte: u8| ((byte & 7) as i32) << 29 >> 29;
+        let (r2, g2, b2) = (r + delta(b[0]), g + delta(b[1]), bl + delta(b[2]));
+        if !(0..32).contains(&r2) {
+            return decode_t_mode(b, bits, opaque);
+        }
+        if !(0..32).contains(&g2) {
+            return decode_h_mode(b, bits, opaque);
+        }
+        if !(0..32).contains(&b2) {
+            return decode_planar_mode(b);
+        }
+        let base1 = [r, g, bl].map(|c| extend5(c as u8));
+        let base2 = [r2, g2, b2].map(|c| extend5(c as u8));
+        decode_subblocks(b, bits, base1, base2, opaque)
+    } else {
+        let base1 = [b[0] >> 4, b[1] >> 4, b[2] >> 4].map(extend4);
+        let base2 = [b[0] & 15, b[1] & 15, b[2] & 15].map(extend4);
+        decode_subblocks(b, bits, base1, base2, true)
+    }
+}
+
+/// individual and differential modes: two sub blocks with their own base colour and table
+fn decode_subblocks(
+    b: &[u8],
+    bits: u64,
+    base1: [i32; 3],
+    base2: [i32; 3],
+    opaque: bool,
+) -> [[u8; 4]; 16] {
+    let flip = b[3] & 1 != 0;
+    let tables = [(b[3] >> 5) as usize, ((b[3] >> 2) & 7) as usize];
+
+    let mut texels = [[0u8; 4]; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            // not flipped: 2x4 sub blocks side by side, flipped: 4x2 sub blocks stacked
+            let second = if flip { y >= 2 } else { x >= 2 };
+            let (base, table) = if second {
+                (base2, tables[1])
+            } else {
+                (base1, tables[0])
+            };
+            let [small, large] = ETC_MODIFIERS[table];
+            let index = texel_index(bits, x, y);
+            texels[y * 4 + x] = if !opaque {
+                // punchthrough: index 2 is transparent and index 0 is the base colour
+                match index {
+                    0 => offset_color(base, 0),
+                    1 => offset_color(base, large),
+                    2 => [0, 0, 0, 0],
+                    _ => offset_color(base, -large),
+                }
+            } else {
+                let modifier = match index {
+                    0 => small,
+                    1 => large,
+                    2 => -small,
+                    _ => -large,
+                };
+                offset_color(base, modifier)
+            };
+        }
+    }
+    texels
+}
+
+/// fill the block from four paint colours, index 2 is transparent in non-opaque punchthrough
+fn paint_block(bits: u64, paint: [[u8; 4]; 4], opaque: bool) -> [[u8; 4]; 16] {
+    let mut texels = [[0u8; 4]; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let index = texel_index(bits, x, y);
+            texels[y * 4 + x] = if !opaque && index == 2 {
+                [0, 0, 0, 0]
+            } else {
+                paint[index]
+            };
+        }
+    }
+    texels
+}
+
+fn decode_t_mode(b: &[u8], bits: u64, opaque: bool) -> [[u8; 4]; 16] {
+    let r1 = (((b[0] >> 3) & 3) << 2) | (b[0] & 3);
+    let c1 = [r1, b[1] >> 4, b[1] & 15].map(extend4);
+    let c2 = [b[2] >> 4, b[2] & 15, b[3] >> 4].map(extend4);
+    let distance = ETC_DISTANCES[((((b[3] >> 2) & 3) << 1) | (b[3] & 1)) as usize];
+
+    let paint = [
+        offset_color(c1, 0),
+        offset_color(c2, distance),
+        offset_color(c2, 0),
+        offset_color(c2, -distance),
+    ];
+    paint_block(bits, paint, opaque)
+}
+
+fn decode_h_mode(b: &[u8], bits: u64, opaque: bool) -> [[u8; 4]; 16] {
+    let r1 = (b[0] >> 3) & 15;
+    let g1 = ((b[0] & 7) << 1) | ((b[1] >> 4) & 1);
+    let b1 = (b[1] & 8) | ((b[1] & 3) << 1) | (b[2] >> 7);
+    let r2 = (b[2] >> 3) & 15;
+    let g2 = ((b[2] & 7) << 1) | (b[3] >> 7);
+    let b2 = (b[3] >> 3) & 15;
+
+    // the lowest distance bit is whether the first colour sorts before the second one
+    let value1 = ((r1 as u32) << 8) | ((g1 as u32) << 4) | b1 as u32;
+    let value2 = ((r2 as u32) << 8) | ((g2 as u32) << 4) | b2 as u32;
+    let distance_index = (b[3] & 4) | ((b[3] & 1) << 1) | (value1 >= value2) as u8;
+    let distance = ETC_DISTANCES[distance_index as usize];
+
+    let c1 = [r1, g1, b1].map(extend4);
+    let c2 = [r2, g2, b2].map(extend4);
+    let paint = [
+        offset_color(c1, distance),
+        offset_color(c1, -distance),
+        offset_color(c2, distance),
+        offset_color(c2, -distance),
+    ];
+    paint_block(bits, paint, opaque)
+}
+
+fn decode_planar_mode(b: &[u8]) -> [[u8; 4]; 16] {
+    let origin = [
+        extend6((b[0] >> 1) & 63),
+        extend7(((b[0] & 1) << 6) | ((b[1] >> 1) & 63)),
+        extend6(((b[1] & 1) << 5) | (((b[2] >> 3) & 3) << 3) | ((b[2] & 3) << 1) | (b[3] >> 7)),
+    ];
+    let horizontal = [
+        extend6((((b[3] >> 2) & 31) << 1) | (b[3] & 1)),
+        extend7(b[4] >> 1),
+        extend6(((b[4] & 1) << 5) | (b[5] >> 3)),
+    ];
+    let vertical = [
+        extend6(((b[5] & 7) << 3) | (b[6] >> 5)),
+        extend7(((b[6] & 31) << 2) | (b[7] >> 6)),
+        extend6(b[7] & 63),
+    ];
+
+    let mut texels = [[0u8; 4]; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let channel = |c: usize| {
+                clamp(
+                    (x as i32 * (horizontal[c] - origin[c])
+                        + y as i32 * (vertical[c] - origin[c])
+                        + 4 * origin[c]
+                        + 2)
+                        >> 2,
+                )
+            };
+            texels[y * 4 + x] = [channel(0), channel(1), channel(2), 255];
+        }
+    }
+    texels
+}
+
+/// decode an 8 byte EAC block holding one 8-bit channel
+pub fn decode_eac_channel(block: &[u8]) -> [u8; 16] {
+    let base = block[0] as i32;
+    let multiplier = (block[1] >> 4) as i32;
+    let table = &EAC_MODIFIERS[(block[1] & 15) as usize];
+    let bits = u64::from_be_bytes(block[0..8].try_into().unwrap());
+
+    let mut values = [0u8; 16];
+    for x in 0..4 {
+        for y in 0..4 {
+            // 3-bit indices from the most significant end, column by column
+            let j = x * 4 + y;
+            let index = (bits >> (45 - 3 * j)) & 7;
+            values[y * 4 + x] = clamp(base + table[index as usize] * multiplier);
+        }
+    }
+    values
+}
+
+/// decode an ETC2 RGBA8 block: EAC alpha followed by an opaque ETC2 colour block
+pub fn decode_etc2_rgba(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = decode_eac_channel(&block[0..8]);
+    let mut texels = decode_etc2_rgb(&block[8..16], false);
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+    texels
+}
@@ -0,0 +1,257 @@
+// KTX 2.0 container, see https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html
+
+use anyhow::{bail, Context, Result};
+
+use super::{check_level_count, read_u32, read_u64, slice, BlockFormat, DataFormat, TextureData};
+
+pub const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// size of the identifier, the fixed header and the section index
+const HEADER_SIZE: usize = 80;
+/// byteOffset, byteLength and uncompressedByteLength of every level
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+/// map the Vulkan format of the header to how the data is laid out
+fn data_format(vk_format: u32) -> Result<DataFormat> {
+    let compressed = |block, srgb| Ok(DataFormat::Compressed { block, srgb });
+    match vk_format {
+        // VK_FORMAT_R8_UNORM, R8G8_UNORM, R8G8B8_UNORM/SRGB
+        9 => Ok(DataFormat::uncompressed(
+            gl::R8,
+            gl::RED,
+            gl::UNSIGNED_BYTE,
+            1,
+        )),
+        16 => Ok(DataFormat::uncompressed(
+            gl::RG8,
+            gl::RG,
+            gl::UNSIGNED_BYTE,
+            2,
+        )),
+        23 => Ok(DataFormat::uncompressed(
+            gl::RGB8,
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            3,
+        )),
+        29 => Ok(DataFormat::uncompressed(
+            gl::SRGB8,
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            3,
+        )),
+        // R8G8B8A8_UNORM/SRGB, B8G8R8A8_UNORM/SRGB
+        37 => Ok(DataFormat::rgba8(false)),
+        43 => Ok(DataFormat::rgba8(true)),
+        44 => Ok(DataFormat::uncompressed(
+            gl::RGBA8,
+            gl::BGRA,
+            gl::UNSIGNED_BYTE,
+            4,
+        )),
+        50 => Ok(DataFormat::uncompressed(
+            gl::SRGB8_ALPHA8,
+            gl::BGRA,
+            gl::UNSIGNED_BYTE,
+            4,
+        )),
+        // R16G16B16_SFLOAT, R16G16B16A16_SFLOAT, R32G32B32_SFLOAT, R32G32B32A32_SFLOAT
+        90 => Ok(DataFormat::uncompressed(
+            gl::RGB16F,
+            gl::RGB,
+            gl::HALF_FLOAT,
+            6,
+        )),
+        97 => Ok(DataFormat::uncompressed(
+            gl::RGBA16F,
+            gl::RGBA,
+            gl::HALF_FLOAT,
+            8,
+        )),
+        106 => Ok(DataFormat::uncompressed(gl::RGB32F, gl::RGB, gl::FLOAT, 12)),
+        109 => Ok(DataFormat::uncompressed(
+            gl::RGBA32F,
+            gl::RGBA,
+            gl::FLOAT,
+            16,
+        )),
+        // VK_FORMAT_BC*_BLOCK
+        131 => compressed(BlockFormat::Bc1Rgb, false),
+        132 => compressed(BlockFormat::Bc1Rgb, true),
+        133 => compressed(BlockFormat::Bc1Rgba, false),
+        134 => compressed(BlockFormat::Bc1Rgba, true),
+        135 => compressed(BlockFormat::Bc2, false),
+        136 => compressed(BlockFormat::Bc2, true),
+        137 => compressed(BlockFormat::Bc3, false),
+        138 => compressed(BlockFormat::Bc3, true),
+        139 => compressed(BlockFormat::Bc4, false),
+        141 => compressed(BlockFormat::Bc5, false),
+        145 => compressed(BlockFormat::Bc7, false),
+        146 => compressed(BlockFormat::Bc7, true),
+        // VK_FORMAT_ETC2_*_BLOCK
+        147 => compressed(BlockFormat::Etc2Rgb8, false),
+        148 => compressed(BlockFormat::Etc2Rgb8, true),
+        149 => compressed(BlockFormat::Etc2Rgb8A1, false),
+        150 => compressed(BlockFormat::Etc2Rgb8A1, true),
+        151 => compressed(BlockFormat::Etc2Rgba8, false),
+        152 => compressed(BlockFormat::Etc2Rgba8, true),
+        0 => bail!("VK_FORMAT_UNDEFINED (Basis Universal) textures need transcoding"),
+        other => bail!("unsupported vkFormat {}", other),
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Result<TextureData> {
+    if !bytes.starts_with(&IDENTIFIER) {
+        bail!("missing KTX2 identifier");
+    }
+    if bytes.len() < HEADER_SIZE {
+        bail!("KTX2 header truncated");
+    }
+
+    let vk_format = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?.max(1);
+    let depth = read_u32(bytes, 28)?;
+    let layers = read_u32(bytes, 32)?;
+    let faces = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?;
+    let supercompression = read_u32(bytes, 44)?;
+
+    if depth > 1 {
+        bail!("3D textures are not supported");
+    }
+    if supercompression != 0 {
+        bail!(
+            "supercompression scheme {} is not supported",
+            supercompression
+        );
+    }
+    let format = data_format(vk_format)?;
+    check_level_count(level_count, width, height)?;
+
+    // a level count of 0 asks the loader to generate the mip chain from level 0
+    let stored_levels = level_count.max(1) as usize;
+    let mut levels = Vec::with_capacity(stored_levels);
+    for level in 0..stored_levels {
+        let entry = HEADER_SIZE + level * LEVEL_INDEX_ENTRY_SIZE;
+        let offset = read_u64(bytes, entry).context("level index truncated")? as usize;
+        let length = read_u64(bytes, entry + 8).context("level index truncated")? as usize;
+        let data = slice(bytes, offset, length).with_context(|| format!("mip level {}", level))?;
+        levels.push(data.to_vec());
+    }
+
+    let texture = TextureData {
+        format,
+        width,
+        height,
+        // a layer count of 0 means "not an array texture"
+        layers: layers.max(1),
+        faces,
+        levels,
+        generate_mipmaps: level_count == 0,
+    };
+    texture.validate()?;
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+
+    /// a KTX2 file with the given header fields, one level index entry per level and the
+    /// level data right after the index
+    fn ktx2(width: u32, height: u32, level_count: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = IDENTIFIER.to_vec();
+        for field in [
+            VK_FORMAT_R8G8B8A8_UNORM,
+            1,
+            width,
+            height,
+            0,
+            0,
+            1,
+            level_count,
+            0,
+        ] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        // empty data format descriptor, key/value and supercompression sections
+        bytes.resize(HEADER_SIZE, 0);
+        let mut offset = HEADER_SIZE + levels.len() * LEVEL_INDEX_ENTRY_SIZE;
+        for level in levels {
+            for field in [offset, level.len(), level.len()] {
+                bytes.extend_from_slice(&(field as u64).to_le_bytes());
+            }
+            offset += level.len();
+        }
+        for level in levels {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        format!("{:#}", parse(bytes).unwrap_err())
+    }
+
+    #[test]
+    fn single_level() {
+        let texels: Vec<u8> = (0..16).collect();
+        let texture = parse(&ktx2(2, 2, 1, &[&texels])).unwrap();
+        assert_eq!((texture.width, texture.height), (2, 2));
+        assert_eq!((texture.layers, texture.faces), (1, 1));
+        assert_eq!(texture.format, DataFormat::rgba8(false));
+        assert_eq!(texture.levels, vec![texels]);
+        assert!(!texture.generate_mipmaps);
+    }
+
+    #[test]
+    fn mip_chain() {
+        let levels = [[1u8; 64].as_slice(), &[2; 16], &[3; 4]];
+        let texture = parse(&ktx2(4, 4, 3, &levels)).unwrap();
+        assert_eq!(texture.levels.len(), 3);
+        for (level, size) in [(4, 4), (2, 2), (1, 1)].into_iter().enumerate() {
+            assert_eq!(texture.level_size(level), size);
+            assert_eq!(texture.image(level, 0, 0), levels[level]);
+        }
+    }
+
+    #[test]
+    fn zero_levels_generate_mipmaps() {
+        let texture = parse(&ktx2(1, 1, 0, &[&[0; 4]])).unwrap();
+        assert_eq!(texture.levels.len(), 1);
+        assert!(texture.generate_mipmaps);
+    }
+
+    #[test]
+    fn truncated_data() {
+        let bytes = ktx2(2, 2, 1, &[&[0; 16]]);
+        assert!(error(&bytes[..bytes.len() - 1]).contains("past the end of the file"));
+        assert!(error(&bytes[..40]).contains("header truncated"));
+    }
+
+    #[test]
+    fn bad_identifier() {
+        let mut bytes = ktx2(2, 2, 1, &[&[0; 16]]);
+        bytes[1] = b'X';
+        assert!(error(&bytes).contains("missing KTX2 identifier"));
+    }
+
+    #[test]
+    fn oversized_level_count() {
+        // the largest level count a 4x4 texture can have is 3
+        assert!(parse(&ktx2(4, 4, 3, &[&[0; 64], &[0; 16], &[0; 4]])).is_ok());
+        for level_count in [4, 32, 33, u32::MAX] {
+            let bytes = ktx2(4, 4, level_count, &[&[0; 64]]);
+            assert!(
+                error(&bytes).contains("at most 3"),
+                "{} levels",
+                level_count
+            );
+        }
+    }
+}
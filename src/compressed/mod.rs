@@ -0,0 +1,778 @@
+// loaders for GPU texture containers, the data is uploaded as-is when the driver supports the
+// block format and decoded to RGBA8 on the CPU otherwise
+pub mod bcn;
+pub mod dds;
+pub mod etc2;
+pub mod ktx2;
+
+use std::{ffi::CStr, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use gl::types::*;
+
+use crate::texture::PixelFormat;
+
+// EXT_texture_compression_s3tc and EXT_texture_sRGB, never core so the gl crate lacks them
+const COMPRESSED_RGB_S3TC_DXT1: GLenum = 0x83F0;
+const COMPRESSED_RGBA_S3TC_DXT1: GLenum = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3: GLenum = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5: GLenum = 0x83F3;
+const COMPRESSED_SRGB_S3TC_DXT1: GLenum = 0x8C4C;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: GLenum = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3: GLenum = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: GLenum = 0x8C4F;
+
+/// block compressed formats, every block covers 4x4 texels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    /// BC1 without alpha (DXT1)
+    Bc1Rgb,
+    /// BC1 with 1-bit alpha (DXT1)
+    Bc1Rgba,
+    /// BC2, explicit 4-bit alpha (DXT3)
+    Bc2,
+    /// BC3, interpolated alpha (DXT5)
+    Bc3,
+    /// BC4, single red channel (RGTC1)
+    Bc4,
+    /// BC5, red and green channels (RGTC2)
+    Bc5,
+    /// BC7, high quality RGBA (BPTC)
+    Bc7,
+    Etc2Rgb8,
+    /// ETC2 with 1-bit punchthrough alpha
+    Etc2Rgb8A1,
+    /// ETC2 colour with an EAC alpha block
+    Etc2Rgba8,
+}
+
+#[allow(dead_code)]
+impl BlockFormat {
+    pub fn block_bytes(&self) -> usize {
+        match self {
+            BlockFormat::Bc1Rgb
+            | BlockFormat::Bc1Rgba
+            | BlockFormat::Bc4
+            | BlockFormat::Etc2Rgb8
+            | BlockFormat::Etc2Rgb8A1 => 8,
+            BlockFormat::Bc2
+            | BlockFormat::Bc3
+            | BlockFormat::Bc5
+            | BlockFormat::Bc7
+            | BlockFormat::Etc2Rgba8 => 16,
+        }
+    }
+
+    /// internal format for glCompressedTexImage2D, `None` if there is no sRGB variant
+    pub fn gl_internal_format(&self, srgb: bool) -> Option<GLenum> {
+        let format = match (self, srgb) {
+            (BlockFormat::Bc1Rgb, false) => COMPRESSED_RGB_S3TC_DXT1,
+            (BlockFormat::Bc1Rgb, true) => COMPRESSED_SRGB_S3TC_DXT1,
+            (BlockFormat::Bc1Rgba, false) => COMPRESSED_RGBA_S3TC_DXT1,
+            (BlockFormat::Bc1Rgba, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT1,
+            (BlockFormat::Bc2, false) => COMPRESSED_RGBA_S3TC_DXT3,
+            (BlockFormat::Bc2, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT3,
+            (BlockFormat::Bc3, false) => COMPRESSED_RGBA_S3TC_DXT5,
+            (BlockFormat::Bc3, true) => COMPRESSED_SRGB_ALPHA_S3TC_DXT5,
+            (BlockFormat::Bc4, false) => gl::COMPRESSED_RED_RGTC1,
+            (BlockFormat::Bc5, false) => gl::COMPRESSED_RG_RGTC2,
+            (BlockFormat::Bc4 | BlockFormat::Bc5, true) => return None,
+            (BlockFormat::Bc7, false) => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            (BlockFormat::Bc7, true) => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+            (BlockFormat::Etc2Rgb8, false) => gl::COMPRESSED_RGB8_ETC2,
+            (BlockFormat::Etc2Rgb8, true) => gl::COMPRESSED_SRGB8_ETC2,
+            (BlockFormat::Etc2Rgb8A1, false) => gl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            (BlockFormat::Etc2Rgb8A1, true) => gl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            (BlockFormat::Etc2Rgba8, false) => gl::COMPRESSED_RGBA8_ETC2_EAC,
+            (BlockFormat::Etc2Rgba8, true) => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+        };
+        Some(format)
+    }
+
+    /// whether the current context can sample this format without decoding it first
+    pub unsafe fn driver_supports(&self, srgb: bool) -> bool {
+        if self.gl_internal_format(srgb).is_none() {
+            return false;
+        }
+        let s3tc = has_extension("GL_EXT_texture_compression_s3tc")
+            && (!srgb || has_extension("GL_EXT_texture_sRGB"));
+        match self {
+            BlockFormat::Bc1Rgb | BlockFormat::Bc1Rgba | BlockFormat::Bc2 | BlockFormat::Bc3 => {
+                s3tc
+            }
+            // RGTC is core since 3.0
+            BlockFormat::Bc4 | BlockFormat::Bc5 => true,
+            BlockFormat::Bc7 => {
+                gl_version() >= (4, 2) || has_extension("GL_ARB_texture_compression_bptc")
+            }
+            BlockFormat::Etc2Rgb8 | BlockFormat::Etc2Rgb8A1 | BlockFormat::Etc2Rgba8 => {
+                gl_version() >= (4, 3) || has_extension("GL_ARB_ES3_compatibility")
+            }
+        }
+    }
+
+    /// decode a whole image to tightly packed RGBA8, single channel formats fill the missing
+    /// channels like GL does (green/blue = 0, alpha = 255)
+    pub fn decode(&self, data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+        let (width, height) = (width as usize, height as usize);
+        let blocks_x = width.div_ceil(4);
+        let blocks_y = height.div_ceil(4);
+        let block_bytes = self.block_bytes();
+        if data.len() < blocks_x * blocks_y * block_bytes {
+            bail!(
+                "{:?} image of {}x{} needs {} bytes, got {}",
+                self,
+                width,
+                height,
+                blocks_x * blocks_y * block_bytes,
+                data.len()
+            );
+        }
+
+        let mut rgba = vec![0u8; width * height * 4];
+        for (index, block) in data.chunks_exact(block_bytes).enumerate() {
+            let (block_x, block_y) = (index % blocks_x, index / blocks_x);
+            if block_y >= blocks_y {
+                break;
+            }
+            let texels = match self {
+                BlockFormat::Bc1Rgb => bcn::decode_bc1(block, false),
+                BlockFormat::Bc1Rgba => bcn::decode_bc1(block, true),
+                BlockFormat::Bc2 => bcn::decode_bc2(block),
+                BlockFormat::Bc3 => bcn::decode_bc3(block),
+                BlockFormat::Bc4 => bcn::decode_bc4(block),
+                BlockFormat::Bc5 => bcn::decode_bc5(block),
+                BlockFormat::Bc7 => bcn::decode_bc7(block),
+                BlockFormat::Etc2Rgb8 => etc2::decode_etc2_rgb(block, false),
+                BlockFormat::Etc2Rgb8A1 => etc2::decode_etc2_rgb(block, true),
+                BlockFormat::Etc2Rgba8 => etc2::decode_etc2_rgba(block),
+            };
+            // copy the 4x4 texels, clipping blocks that hang over the image edge
+            for (texel, color) in texels.iter().enumerate() {
+                let x = block_x * 4 + texel % 4;
+                let y = block_y * 4 + texel / 4;
+                if x < width && y < height {
+                    let offset = (y * width + x) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(color);
+                }
+            }
+        }
+        Ok(rgba)
+    }
+}
+
+/// how the texel data of a container is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Uncompressed {
+        format: PixelFormat,
+        bytes_per_pixel: usize,
+    },
+    Compressed {
+        block: BlockFormat,
+        srgb: bool,
+    },
+}
+
+impl DataFormat {
+    pub fn image_size(&self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        // saturates for sizes no file can hold, reading them then fails as truncated
+        match self {
+            DataFormat::Uncompressed {
+                bytes_per_pixel, ..
+            } => width
+                .saturating_mul(height)
+                .saturating_mul(*bytes_per_pixel),
+            DataFormat::Compressed { block, .. } => width
+                .div_ceil(4)
+                .saturating_mul(height.div_ceil(4))
+                .saturating_mul(block.block_bytes()),
+        }
+    }
+
+    /// plain 8-bit formats shared by both containers
+    pub(crate) fn rgba8(srgb: bool) -> DataFormat {
+        let internal_format = if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 };
+        Self::uncompressed(internal_format, gl::RGBA, gl::UNSIGNED_BYTE, 4)
+    }
+
+    pub(crate) fn uncompressed(
+        internal_format: GLenum,
+        format: GLenum,
+        data_type: GLenum,
+        bytes_per_pixel: usize,
+    ) -> DataFormat {
+        DataFormat::Uncompressed {
+            format: PixelFormat {
+                internal_format,
+                format,
+                data_type,
+                swizzle: None,
+            },
+            bytes_per_pixel,
+        }
+    }
+}
+
+/// every image of a texture container, ready to be uploaded
+#[derive(Debug, Clone)]
+pub struct TextureData {
+    pub format: DataFormat,
+    pub width: u32,
+    pub height: u32,
+    /// 1 for plain textures, more for texture arrays
+    pub layers: u32,
+    /// 6 for cubemaps, 1 otherwise
+    pub faces: u32,
+    /// mip levels, largest first; every level holds `layers * faces` images, layer by layer
+    /// with the faces of a layer in +X, -X, +Y, -Y, +Z, -Z order
+    pub levels: Vec<Vec<u8>>,
+    /// the container asked for the mip chain to be generated at load time
+    pub generate_mipmaps: bool,
+}
+
+#[allow(dead_code)]
+impl TextureData {
+    /// load a KTX2 or DDS file, the container is detected from its magic bytes
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<TextureData> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&bytes).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<TextureData> {
+        if bytes.starts_with(&ktx2::IDENTIFIER) {
+            ktx2::parse(bytes)
+        } else if bytes.starts_with(&dds::MAGIC) {
+            dds::parse(bytes)
+        } else {
+            bail!("not a KTX2 or DDS file")
+        }
+    }
+
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        (
+            mip_dimension(self.width, level),
+            mip_dimension(self.height, level),
+        )
+    }
+
+    /// bytes of a single image of the given level
+    pub fn image_size(&self, level: usize) -> usize {
+        let (width, height) = self.level_size(level);
+        self.format.image_size(width, height)
+    }
+
+    pub fn image(&self, level: usize, layer: u32, face: u32) -> &[u8] {
+        let size = self.image_size(level);
+        let start = (layer * self.faces + face) as usize * size;
+        &self.levels[level][start..start + size]
+    }
+
+    pub fn is_cubemap(&self) -> bool {
+        self.faces == 6
+    }
+
+    /// check that every level holds exactly the bytes its images need
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            bail!("texture has no size");
+        }
+        if self.faces != 1 && self.faces != 6 {
+            bail!("{} faces, expected 1 or 6", self.faces);
+        }
+        if self.faces == 6 && self.width != self.height {
+            bail!(
+                "cubemap faces are {}x{}, not square",
+                self.width,
+                self.height
+            );
+        }
+        if self.levels.is_empty() {
+            bail!("texture has no mip levels");
+        }
+        let images = self
+            .layers
+            .checked_mul(self.faces)
+            .with_context(|| format!("{} layers with {} faces", self.layers, self.faces))?;
+        for (level, data) in self.levels.iter().enumerate() {
+            let expected = self.image_size(level).saturating_mul(images as usize);
+            if data.len() != expected {
+                bail!(
+                    "mip level {} holds {} bytes, expected {}",
+                    level,
+                    data.len(),
+                    expected
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `size` halved `level` times, never less than 1
+fn mip_dimension(size: u32, level: usize) -> u32 {
+    u32::try_from(level)
+        .ok()
+        .and_then(|level| size.checked_shr(level))
+        .unwrap_or(0)
+        .max(1)
+}
+
+/// fail on more mip levels than a `width`x`height` chain has, before the levels are allocated
+pub(crate) fn check_level_count(level_count: u32, width: u32, height: u32) -> Result<()> {
+    let max = 32 - width.max(height).max(1).leading_zeros();
+    if level_count > max {
+        bail!(
+            "{} mip levels, but a {}x{} texture has at most {}",
+            level_count,
+            width,
+            height,
+            max
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .with_context(|| format!("file truncated at offset {}", offset))
+}
+
+pub(crate) fn read_u64(bytes: &[u8], offset: usize) -> Result<u64> {
+    Ok(read_u32(bytes, offset)? as u64 | (read_u32(bytes, offset + 4)? as u64) << 32)
+}
+
+pub(crate) fn slice(bytes: &[u8], offset: usize, length: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .with_context(|| {
+            format!(
+                "{} bytes at offset {} are past the end of the file",
+                length, offset
+            )
+        })
+}
+
+/// (major, minor) version of the current context
+pub unsafe fn gl_version() -> (i32, i32) {
+    let (mut major, mut minor) = (0, 0);
+    gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+    gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    (major, minor)
+}
+
+pub unsafe fn has_extension(name: &str) -> bool {
+    let mut count = 0;
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    (0..count as GLuint).any(|index| {
+        let extension = gl::GetStringi(gl::EXTENSIONS, index);
+        !extension.is_null() && CStr::from_ptr(extension as *const _).to_bytes() == name.as_bytes()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// pack `(bit count, value)` fields least significant bit first, like BC7 stores them
+    fn pack_bits(fields: &[(u32, u128)]) -> [u8; 16] {
+        let mut bits = 0u128;
+        let mut position = 0;
+        for &(count, value) in fields {
+            bits |= value << position;
+            position += count;
+        }
+        assert_eq!(position, 128);
+        bits.to_le_bytes()
+    }
+
+    /// pack `(bit count, value)` fields most significant bit first, like ETC2 stores them
+    fn pack_etc(fields: &[(u32, u64)]) -> [u8; 8] {
+        let mut bits = 0u64;
+        let mut position = 0;
+        for &(count, value) in fields {
+            bits = bits << count | value;
+            position += count;
+        }
+        assert_eq!(position, 64);
+        bits.to_be_bytes()
+    }
+
+    /// the 32 index bits of an ETC2 colour block from 2-bit indices by row, stored column by
+    /// column with the high bits in the upper half
+    fn etc_indices(rows: [[u64; 4]; 4]) -> u64 {
+        let mut bits = 0;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, index) in row.iter().enumerate() {
+                let j = x * 4 + y;
+                bits |= (index >> 1) << (16 + j) | (index & 1) << j;
+            }
+        }
+        bits
+    }
+
+    /// a differential block with bases 16 and 19 for red, 10 and 6 for green and 0 for blue,
+    /// tables 1 and 2, side by side sub blocks and indices 0..4 along the first row
+    fn etc_differential(differential_bit: u64) -> [u8; 8] {
+        pack_etc(&[
+            (5, 16),
+            (3, 3),
+            (5, 10),
+            (3, 0b100),
+            (5, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (1, differential_bit),
+            (1, 0),
+            (32, etc_indices([[0, 1, 2, 3], [0; 4], [0; 4], [0; 4]])),
+        ])
+    }
+
+    fn decode_block(format: BlockFormat, block: &[u8]) -> Vec<[u8; 4]> {
+        let rgba = format.decode(block, 4, 4).unwrap();
+        rgba.chunks_exact(4)
+            .map(|t| [t[0], t[1], t[2], t[3]])
+            .collect()
+    }
+
+    #[test]
+    fn level_size_stops_at_one() {
+        let texture = TextureData {
+            format: DataFormat::rgba8(false),
+            width: 16,
+            height: 4,
+            layers: 1,
+            faces: 1,
+            levels: vec![],
+            generate_mipmaps: false,
+        };
+        assert_eq!(texture.level_size(0), (16, 4));
+        assert_eq!(texture.level_size(3), (2, 1));
+        assert_eq!(texture.level_size(31), (1, 1));
+        assert_eq!(texture.level_size(32), (1, 1));
+        assert_eq!(texture.level_size(usize::MAX), (1, 1));
+    }
+
+    #[test]
+    fn level_count_limit() {
+        assert!(check_level_count(1, 1, 1).is_ok());
+        assert!(check_level_count(2, 1, 1).is_err());
+        assert!(check_level_count(13, 4096, 16).is_ok());
+        assert!(check_level_count(14, 4096, 16).is_err());
+        assert!(check_level_count(32, u32::MAX, 1).is_ok());
+        assert!(check_level_count(33, u32::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn unknown_container() {
+        let error = TextureData::parse(b"\x89PNG\r\n\x1a\n").unwrap_err();
+        assert_eq!(error.to_string(), "not a KTX2 or DDS file");
+    }
+
+    #[test]
+    fn decode_bc1() {
+        // red and blue endpoints, texels 0..4 use indices 0..4
+        let four_colors = [0x00, 0xf8, 0x1f, 0x00, 0xe4, 0, 0, 0];
+        let texels = decode_block(BlockFormat::Bc1Rgb, &four_colors);
+        assert_eq!(texels[0], [255, 0, 0, 255]);
+        assert_eq!(texels[1], [0, 0, 255, 255]);
+        assert_eq!(texels[2], [170, 0, 85, 255]);
+        assert_eq!(texels[3], [85, 0, 170, 255]);
+        assert_eq!(texels[15], [255, 0, 0, 255]);
+
+        // swapped endpoints select the three colour mode with transparent black
+        let three_colors = [0x1f, 0x00, 0x00, 0xf8, 0xe4, 0, 0, 0];
+        let texels = decode_block(BlockFormat::Bc1Rgba, &three_colors);
+        assert_eq!(texels[2], [127, 0, 127, 255]);
+        assert_eq!(texels[3], [0, 0, 0, 0]);
+        // without alpha the black stays opaque, like COMPRESSED_RGB_S3TC_DXT1 samples it
+        let texels = decode_block(BlockFormat::Bc1Rgb, &three_colors);
+        assert_eq!(texels[2], [127, 0, 127, 255]);
+        assert_eq!(texels[3], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_bc2() {
+        // explicit 4-bit alpha 0, 5, 10, 15 on the first texels, the colour block always uses
+        // four colours even with swapped endpoints
+        let mut block = [0u8; 16];
+        block[0..2].copy_from_slice(&[0x50, 0xfa]);
+        block[8..16].copy_from_slice(&[0x1f, 0x00, 0x00, 0xf8, 0xe4, 0, 0, 0]);
+        let texels = decode_block(BlockFormat::Bc2, &block);
+        assert_eq!(texels[0], [0, 0, 255, 0]);
+        assert_eq!(texels[1], [255, 0, 0, 85]);
+        assert_eq!(texels[2], [85, 0, 170, 170]);
+        assert_eq!(texels[3], [170, 0, 85, 255]);
+        assert_eq!(texels[4], [0, 0, 255, 0]);
+    }
+
+    #[test]
+    fn decode_bc3() {
+        // BC4 style alpha in six value mode with indices 0, 1, 2, 6 and 7, four colours with
+        // swapped endpoints
+        let mut block = [0u8; 16];
+        block[0..8].copy_from_slice(&[50, 250, 0x88, 0x7c, 0, 0, 0, 0]);
+        block[8..16].copy_from_slice(&[0x1f, 0x00, 0x00, 0xf8, 0xe4, 0, 0, 0]);
+        let texels = decode_block(BlockFormat::Bc3, &block);
+        assert_eq!(texels[0], [0, 0, 255, 50]);
+        assert_eq!(texels[1], [255, 0, 0, 250]);
+        assert_eq!(texels[2], [85, 0, 170, 90]);
+        assert_eq!(texels[3], [170, 0, 85, 0]);
+        assert_eq!(texels[4], [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn decode_bc4() {
+        // eight value mode, indices 0, 1, 2 and 7 on the first texels
+        let block = [200, 100, 0x88, 0x0e, 0, 0, 0, 0];
+        let texels = decode_block(BlockFormat::Bc4, &block);
+        assert_eq!(texels[0], [200, 0, 0, 255]);
+        assert_eq!(texels[1], [100, 0, 0, 255]);
+        assert_eq!(texels[2], [185, 0, 0, 255]);
+        assert_eq!(texels[3], [114, 0, 0, 255]);
+        assert_eq!(texels[4], [200, 0, 0, 255]);
+    }
+
+    #[test]
+    fn decode_bc5() {
+        // constant red, green in six value mode with the explicit 0 and 255 at indices 6 and 7
+        let block = [128, 128, 0, 0, 0, 0, 0, 0, 50, 250, 0x3e, 0, 0, 0, 0, 0];
+        let texels = decode_block(BlockFormat::Bc5, &block);
+        assert_eq!(texels[0], [128, 0, 0, 255]);
+        assert_eq!(texels[1], [128, 255, 0, 255]);
+        assert_eq!(texels[2], [128, 50, 0, 255]);
+    }
+
+    #[test]
+    fn decode_bc7_mode_6() {
+        let mut fields = vec![
+            // mode 6
+            (7, 1 << 6),
+            // red, green, blue and alpha of both endpoints
+            (7, 127),
+            (7, 0),
+            (7, 0),
+            (7, 0),
+            (7, 0),
+            (7, 127),
+            (7, 127),
+            (7, 127),
+            // p-bits
+            (1, 1),
+            (1, 1),
+            // the anchor texel's index has one bit less
+            (3, 0),
+            (4, 15),
+            (4, 8),
+        ];
+        fields.extend([(4, 0); 13]);
+        let texels = decode_block(BlockFormat::Bc7, &pack_bits(&fields));
+        assert_eq!(texels[0], [255, 1, 1, 255]);
+        assert_eq!(texels[1], [1, 1, 255, 255]);
+        assert_eq!(texels[2], [120, 1, 136, 255]);
+        assert_eq!(texels[3], [255, 1, 1, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_individual() {
+        let block = |flip| {
+            pack_etc(&[
+                // red, green and blue of both sub blocks
+                (4, 8),
+                (4, 4),
+                (4, 4),
+                (4, 2),
+                (4, 2),
+                (4, 1),
+                // tables, differential bit and flip bit
+                (3, 0),
+                (3, 7),
+                (1, 0),
+                (1, flip),
+                (32, etc_indices([[0, 1, 2, 3]; 4])),
+            ])
+        };
+        // 2x4 sub blocks side by side, bases (136, 68, 34) and (68, 34, 17)
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &block(0));
+        assert_eq!(texels[0], [138, 70, 36, 255]);
+        assert_eq!(texels[1], [144, 76, 42, 255]);
+        assert_eq!(texels[2], [21, 0, 0, 255]);
+        assert_eq!(texels[15], [0, 0, 0, 255]);
+
+        // 4x2 sub blocks stacked
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &block(1));
+        assert_eq!(texels[6], [134, 66, 32, 255]);
+        assert_eq!(texels[9], [251, 217, 200, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_differential() {
+        // bases (132, 82, 0) and (156, 49, 0)
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &etc_differential(1));
+        assert_eq!(texels[0], [137, 87, 5, 255]);
+        assert_eq!(texels[1], [149, 99, 17, 255]);
+        assert_eq!(texels[2], [147, 40, 0, 255]);
+        assert_eq!(texels[3], [127, 20, 0, 255]);
+        assert_eq!(texels[4], [137, 87, 5, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_punchthrough() {
+        // the differential bit is the opaque bit, set it decodes like RGB8
+        let opaque = etc_differential(1);
+        assert_eq!(
+            decode_block(BlockFormat::Etc2Rgb8A1, &opaque),
+            decode_block(BlockFormat::Etc2Rgb8, &opaque)
+        );
+        // cleared index 0 is the base colour without modifier and index 2 is transparent
+        let texels = decode_block(BlockFormat::Etc2Rgb8A1, &etc_differential(0));
+        assert_eq!(texels[0], [132, 82, 0, 255]);
+        assert_eq!(texels[1], [149, 99, 17, 255]);
+        assert_eq!(texels[2], [0, 0, 0, 0]);
+        assert_eq!(texels[3], [127, 20, 0, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_t_mode() {
+        let block = |differential_bit| {
+            pack_etc(&[
+                // red overflows, 2 - 4, around the split first red
+                (3, 0),
+                (2, 0b10),
+                (1, 1),
+                (2, 0b00),
+                (4, 4),
+                (4, 12),
+                // second colour
+                (4, 2),
+                (4, 8),
+                (4, 6),
+                // distance 5 split around the differential bit
+                (2, 0b10),
+                (1, differential_bit),
+                (1, 1),
+                (
+                    32,
+                    etc_indices([[0, 1, 2, 3], [3, 2, 1, 0], [0; 4], [0; 4]]),
+                ),
+            ])
+        };
+        // colours (136, 68, 204) and (34, 136, 102), distance 32
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &block(1));
+        assert_eq!(texels[0], [136, 68, 204, 255]);
+        assert_eq!(texels[1], [66, 168, 134, 255]);
+        assert_eq!(texels[2], [34, 136, 102, 255]);
+        assert_eq!(texels[3], [2, 104, 70, 255]);
+        assert_eq!(texels[4], [2, 104, 70, 255]);
+        assert_eq!(texels[6], [66, 168, 134, 255]);
+
+        let texels = decode_block(BlockFormat::Etc2Rgb8A1, &block(0));
+        assert_eq!(texels[1], [66, 168, 134, 255]);
+        assert_eq!(texels[2], [0, 0, 0, 0]);
+        assert_eq!(texels[5], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn decode_etc2_h_mode() {
+        let block = pack_etc(&[
+            // red 10 + 3 fits, green overflows, 1 - 3
+            (1, 0),
+            (4, 10),
+            (3, 0b011),
+            (3, 0),
+            (1, 0),
+            (1, 1),
+            (1, 1),
+            (3, 0b011),
+            // second colour
+            (4, 3),
+            (4, 12),
+            (4, 5),
+            (1, 1),
+            (1, 1),
+            (1, 0),
+            (
+                32,
+                etc_indices([[0, 1, 2, 3], [3, 2, 1, 0], [0; 4], [0; 4]]),
+            ),
+        ]);
+        // colours (170, 102, 187) and (51, 204, 85), the first sorts after the second which
+        // adds 1 to the distance index, 5 for a distance of 32
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &block);
+        assert_eq!(texels[0], [202, 134, 219, 255]);
+        assert_eq!(texels[1], [138, 70, 155, 255]);
+        assert_eq!(texels[2], [83, 236, 117, 255]);
+        assert_eq!(texels[3], [19, 172, 53, 255]);
+        assert_eq!(texels[4], [19, 172, 53, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_planar() {
+        let block = pack_etc(&[
+            // origin red 32, green 64 split by an unused bit
+            (1, 0),
+            (6, 32),
+            (1, 1),
+            (1, 0),
+            (6, 0),
+            // origin blue 26 in three parts, the unused bits make blue overflow, 31 + 1
+            (1, 0),
+            (3, 0b111),
+            (2, 0b11),
+            (1, 0),
+            (3, 0b010),
+            // horizontal red 0 split around the differential bit
+            (5, 0),
+            (1, 1),
+            (1, 0),
+            (7, 127),
+            (6, 26),
+            // vertical
+            (6, 32),
+            (7, 0),
+            (6, 63),
+        ]);
+        // origin (130, 129, 105), horizontal (0, 255, 105), vertical (130, 0, 255)
+        let texels = decode_block(BlockFormat::Etc2Rgb8, &block);
+        assert_eq!(texels[0], [130, 129, 105, 255]);
+        assert_eq!(texels[3], [33, 224, 105, 255]);
+        assert_eq!(texels[12], [130, 32, 218, 255]);
+        assert_eq!(texels[15], [33, 127, 218, 255]);
+    }
+
+    #[test]
+    fn decode_etc2_rgba() {
+        // EAC alpha: base 250, multiplier 3, table 0, 3-bit indices column by column. index 7
+        // clamps to 255
+        let alpha_indices: [[u64; 4]; 4] = [[0, 1, 3, 7], [2, 0, 0, 0], [0; 4], [0; 4]];
+        let mut fields = vec![(8, 250), (4, 3), (4, 0)];
+        for j in 0..16 {
+            fields.push((3, alpha_indices[j % 4][j / 4]));
+        }
+        let mut block = pack_etc(&fields).to_vec();
+        block.extend(etc_differential(1));
+
+        let colors = decode_block(BlockFormat::Etc2Rgb8, &block[8..16]);
+        let texels = decode_block(BlockFormat::Etc2Rgba8, &block);
+        for (texel, color) in texels.iter().zip(&colors) {
+            assert_eq!(texel[..3], color[..3]);
+        }
+        let alpha: Vec<u8> = texels.iter().map(|texel| texel[3]).collect();
+        assert_eq!(alpha[..5], [241, 232, 205, 255, 223]);
+        assert_eq!(alpha[15], 241);
+    }
+
+    #[test]
+    fn decode_clips_to_the_image() {
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0xe4, 0, 0, 0];
+        let rgba = BlockFormat::Bc1Rgb.decode(&block, 2, 1).unwrap();
+        assert_eq!(rgba, [255, 0, 0, 255, 0, 0, 255, 255]);
+        assert!(BlockFormat::Bc1Rgb.decode(&block, 8, 4).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use cgmath::{Matrix4, Point3, Transform};
+
+/// one queued draw, `item` is whatever the caller needs to issue it
+#[derive(Debug, Clone)]
+pub struct QueuedDraw<T> {
+    /// world space point the draw is sorted by, usually the centre of the object
+    pub center: Point3<f32>,
+    pub item: T,
+    /// distance in front of the camera, filled in by `sort`
+    depth: f32,
+}
+
+/// draws collected for a frame, split into opaque and transparent ones. opaque draws go first
+/// and front to back so the depth test rejects hidden fragments early, transparent draws go
+/// last and back to front so every one blends over what is behind it
+#[derive(Debug, Clone)]
+pub struct DrawQueue<T> {
+    opaque: Vec<QueuedDraw<T>>,
+    transparent: Vec<QueuedDraw<T>>,
+}
+
+impl<T> Default for DrawQueue<T> {
+    fn default() -> Self {
+        Self {
+            opaque: Vec::new(),
+            transparent: Vec::new(),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl<T> DrawQueue<T> {
+    pub fn new() -> DrawQueue<T> {
+        Self::default()
+    }
+
+    pub fn push_opaque(&mut self, center: Point3<f32>, item: T) {
+        self.opaque.push(QueuedDraw {
+            center,
+            item,
+            depth: 0.0,
+        });
+    }
+
+    pub fn push_transparent(&mut self, center: Point3<f32>, item: T) {
+        self.transparent.push(QueuedDraw {
+            center,
+            item,
+            depth: 0.0,
+        });
+    }
+
+    /// remove all draws, keeping the allocations for the next frame
+    pub fn clear(&mut self) {
+        self.opaque.clear();
+        self.transparent.clear();
+    }
+
+    /// order both lists by view space depth. the sort is stable, draws at the same depth keep
+    /// the order they were pushed in
+    pub fn sort(&mut self, view: &Matrix4<f32>) {
+        for draw in self.opaque.iter_mut().chain(self.transparent.iter_mut()) {
+            // the camera looks down -z in view space
+            draw.depth = -view.transform_point(draw.center).z;
+        }
+        self.opaque.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+        self.transparent.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+    }
+
+    pub fn opaque(&self) -> impl Iterator<Item = &T> {
+        self.opaque.iter().map(|draw| &draw.item)
+    }
+
+    pub fn transparent(&self) -> impl Iterator<Item = &T> {
+        self.transparent.iter().map(|draw| &draw.item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,135 @@
+use std::{ffi::CString, mem, ptr};
+
+use anyhow::Result;
+use cgmath::{perspective, vec3, Deg, Matrix4, Point3};
+use gl::types::*;
+
+use crate::{
+    framebuffer::check_status,
+    sampler::SamplerDesc,
+    shaders::Shader,
+    skybox::SKYBOX_VERTICES,
+    texture::{Texture2D, TextureCube, CUBE_FACE_TARGETS},
+};
+
+/// views from the origin through every cube face, in `CUBE_FACE_TARGETS` order. cubemap faces
+/// have their t axis pointing down, hence the flipped up vectors
+pub fn capture_views() -> [Matrix4<f32>; 6] {
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    let view = |x, y, z, up| Matrix4::look_at_rh(origin, Point3::new(x, y, z), up);
+    [
+        view(1.0, 0.0, 0.0, vec3(0.0, -1.0, 0.0)),
+        view(-1.0, 0.0, 0.0, vec3(0.0, -1.0, 0.0)),
+        view(0.0, 1.0, 0.0, vec3(0.0, 0.0, 1.0)),
+        view(0.0, -1.0, 0.0, vec3(0.0, 0.0, -1.0)),
+        view(0.0, 0.0, 1.0, vec3(0.0, -1.0, 0.0)),
+        view(0.0, 0.0, -1.0, vec3(0.0, -1.0, 0.0)),
+    ]
+}
+
+/// 90 degree square frustum that exactly covers one cube face
+pub fn capture_projection() -> Matrix4<f32> {
+    perspective(Deg(90.0), 1.0, 0.1, 10.0)
+}
+
+/// render an equirectangular (longitude/latitude) map into a new RGB16F cubemap with faces of
+/// `size` pixels. load the map with `TextureOptions::hdr()` to keep the full range
+#[allow(dead_code)]
+pub fn equirectangular_to_cubemap(map: &Texture2D, size: u32) -> Result<TextureCube> {
+    let shader = Shader::new(
+        "src/shaders/cubemap_capture.vs",
+        "src/shaders/equirectangular_to_cubemap.fs",
+    )?;
+
+    let mut cubemap = TextureCube { id: 0, size };
+    let (mut fbo, mut vao, mut vbo) = (0, 0, 0);
+    unsafe {
+        gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        gl::GenTextures(1, &mut cubemap.id);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap.id);
+        for target in CUBE_FACE_TARGETS {
+            gl::TexImage2D(
+                target,
+                0,
+                gl::RGB16F as GLint,
+                size as GLsizei,
+                size as GLsizei,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                ptr::null(),
+            );
+        }
+        SamplerDesc::linear_clamp().apply(gl::TEXTURE_CUBE_MAP);
+
+        // the caller's framebuffer and viewport are restored afterwards
+        let mut previous_fbo = 0;
+        let mut previous_viewport = [0; 4];
+        gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_fbo);
+        gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+
+        // no depth attachment needed, from the inside every pixel is covered by exactly one face
+        gl::GenFramebuffers(1, &mut fbo);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        gl::FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            CUBE_FACE_TARGETS[0],
+            cubemap.id,
+            0,
+        );
+        if let Err(error) = check_status(gl::FRAMEBUFFER) {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::DeleteFramebuffers(1, &fbo);
+            return Err(error.context("cubemap capture"));
+        }
+
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            mem::size_of_val(&SKYBOX_VERTICES) as GLsizeiptr,
+            SKYBOX_VERTICES.as_ptr() as *const _,
+            gl::STATIC_DRAW,
+        );
+        gl::VertexAttribPointer(
+            0,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            3 * mem::size_of::<GLfloat>() as GLsizei,
+            ptr::null(),
+        );
+        gl::EnableVertexAttribArray(0);
+
+        shader.use_program();
+        shader.set_int(&CString::new("equirectangularMap")?, 0);
+        shader.set_mat4(&CString::new("projection")?, &capture_projection());
+        map.bind(0);
+
+        gl::Viewport(0, 0, size as GLsizei, size as GLsizei);
+        for (target, view) in CUBE_FACE_TARGETS.into_iter().zip(capture_views()) {
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                target,
+                cubemap.id,
+                0,
+            );
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            shader.set_mat4(&CString::new("view")?, &view);
+            gl::DrawArrays(gl::TRIANGLES, 0, 36);
+        }
+
+        gl::BindVertexArray(0);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+        let [x, y, width, height] = previous_viewport;
+        gl::Viewport(x, y, width, height);
+        gl::DeleteVertexArrays(1, &vao);
+        gl::DeleteBuffers(1, &vbo);
+        gl::DeleteFramebuffers(1, &fbo);
+    }
+    Ok(cubemap)
+}
@@ -0,0 +1,494 @@
+use std::ptr;
+
+use anyhow::{bail, Result};
+use gl::types::*;
+
+use crate::sampler::SamplerDesc;
+
+/// where a depth or stencil attachment is stored
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentStorage {
+    /// can only be rendered to and blitted, usually faster
+    Renderbuffer,
+    /// can also be sampled afterwards, e.g. as a shadow map
+    Texture,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorAttachment {
+    /// any color-renderable format, e.g. RGBA8, SRGB8_ALPHA8, RGBA16F or R32UI
+    pub internal_format: GLenum,
+    /// ignored for multisampled attachments, which can't be filtered
+    pub sampler: SamplerDesc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthStencilAttachment {
+    /// DEPTH_COMPONENT16/24/32F, DEPTH24_STENCIL8, DEPTH32F_STENCIL8 or STENCIL_INDEX8
+    pub internal_format: GLenum,
+    pub storage: AttachmentStorage,
+}
+
+/// what a `Framebuffer` is made of, the sizes are given when it is built or resized
+#[derive(Debug, Clone, Default)]
+pub struct FramebufferDesc {
+    /// bound to COLOR_ATTACHMENT0.. in order, all of them are draw buffers
+    pub color: Vec<ColorAttachment>,
+    pub depth_stencil: Option<DepthStencilAttachment>,
+    /// 0 for a regular framebuffer, otherwise every attachment is multisampled
+    pub samples: u32,
+}
+
+#[allow(dead_code)]
+impl FramebufferDesc {
+    pub fn new() -> FramebufferDesc {
+        Self::default()
+    }
+
+    /// add a colour attachment with linear filtering and clamped edges
+    pub fn with_color(mut self, internal_format: GLenum) -> Self {
+        self.color.push(ColorAttachment {
+            internal_format,
+            sampler: SamplerDesc::linear_clamp(),
+        });
+        self
+    }
+
+    pub fn with_depth_stencil(
+        mut self,
+        internal_format: GLenum,
+        storage: AttachmentStorage,
+    ) -> Self {
+        self.depth_stencil = Some(DepthStencilAttachment {
+            internal_format,
+            storage,
+        });
+        self
+    }
+
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn build(self, width: i32, height: i32) -> Result<Framebuffer> {
+        Framebuffer::new(self, width, height)
+    }
+}
+
+/// an off-screen render target owning its attachments
+pub struct Framebuffer {
+    id: GLuint,
+    desc: FramebufferDesc,
+    size: (i32, i32),
+    color_textures: Vec<GLuint>,
+    /// a texture or a renderbuffer, depending on the attachment's storage
+    depth_stencil: Option<GLuint>,
+}
+
+#[allow(dead_code)]
+impl Framebuffer {
+    pub fn new(desc: FramebufferDesc, width: i32, height: i32) -> Result<Framebuffer> {
+        let mut framebuffer = Framebuffer {
+            id: 0,
+            desc,
+            size: (0, 0),
+            color_textures: Vec::new(),
+            depth_stencil: None,
+        };
+        unsafe {
+            if framebuffer.desc.samples > 0 {
+                validate_samples(framebuffer.desc.samples, max_samples())?;
+            }
+            gl::GenFramebuffers(1, &mut framebuffer.id);
+            framebuffer.resize(width, height)?;
+        }
+        Ok(framebuffer)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        self.size
+    }
+
+    pub fn desc(&self) -> &FramebufferDesc {
+        &self.desc
+    }
+
+    /// the texture behind COLOR_ATTACHMENT`index`
+    pub fn color_texture(&self, index: usize) -> GLuint {
+        self.color_textures[index]
+    }
+
+    /// the depth/stencil texture, `None` without one or when it is a renderbuffer
+    pub fn depth_texture(&self) -> Option<GLuint> {
+        match self.desc.depth_stencil {
+            Some(DepthStencilAttachment {
+                storage: AttachmentStorage::Texture,
+                ..
+            }) => self.depth_stencil,
+            _ => None,
+        }
+    }
+
+    /// the target the attachment textures are bound to
+    pub fn texture_target(&self) -> GLenum {
+        texture_target(self.desc.samples)
+    }
+
+    /// render into this framebuffer, the viewport is set to cover it
+    pub unsafe fn bind(&self) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        gl::Viewport(0, 0, self.size.0, self.size.1);
+    }
+
+    /// render into the window again, with a viewport of `size`
+    pub unsafe fn bind_default(size: (i32, i32)) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, size.0, size.1);
+    }
+
+    /// bind colour attachment `index` to texture unit `unit`
+    pub unsafe fn bind_color_texture(&self, index: usize, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(self.texture_target(), self.color_textures[index]);
+    }
+
+    /// bind the depth/stencil texture to texture unit `unit`, unbinds the unit without one
+    pub unsafe fn bind_depth_texture(&self, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(self.texture_target(), self.depth_texture().unwrap_or(0));
+    }
+
+    /// recreate the attachments at a new size, e.g. after the window was resized. does nothing
+    /// when the size is unchanged, so it can be called every frame
+    pub unsafe fn resize(&mut self, width: i32, height: i32) -> Result<()> {
+        // a minimised window reports a zero size, which no attachment can have
+        let (width, height) = (width.max(1), height.max(1));
+        if self.size == (width, height) {
+            return Ok(());
+        }
+        self.rebuild((width, height), self.desc.samples)
+    }
+
+    /// recreate the attachments with another sample count, 0 for none. the framebuffer is left
+    /// unchanged when the driver doesn't support that many samples
+    pub unsafe fn set_samples(&mut self, samples: u32) -> Result<()> {
+        if samples == self.desc.samples {
+            return Ok(());
+        }
+        if samples > 0 {
+            validate_samples(samples, max_samples())?;
+        }
+        if self.size == (0, 0) {
+            // a failed rebuild, there is nothing to recreate until the next resize
+            self.desc.samples = samples;
+            return Ok(());
+        }
+        self.rebuild(self.size, samples)
+    }
+
+    /// replace the attachments, `size` and `samples` are only stored once that succeeded. on
+    /// failure the framebuffer is left without attachments and a size of (0, 0), so the next
+    /// `resize` tries again whatever its size
+    unsafe fn rebuild(&mut self, size: (i32, i32), samples: u32) -> Result<()> {
+        self.delete_attachments();
+        let mut previous_fbo = 0;
+        gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_fbo);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+        let result = self.create_attachments(size, samples);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+        match result {
+            Ok(()) => {
+                self.size = size;
+                self.desc.samples = samples;
+            }
+            Err(_) => {
+                self.delete_attachments();
+                self.size = (0, 0);
+            }
+        }
+        result
+    }
+
+    /// expects the framebuffer to be bound
+    unsafe fn create_attachments(
+        &mut self,
+        (width, height): (i32, i32),
+        samples: u32,
+    ) -> Result<()> {
+        let target = texture_target(samples);
+        let samples = samples as GLsizei;
+
+        let mut draw_buffers = Vec::new();
+        for (index, attachment) in self.desc.color.iter().enumerate() {
+            let texture =
+                create_texture(target, attachment.internal_format, samples, width, height)?;
+            if samples == 0 {
+                attachment.sampler.apply(target);
+            }
+            self.color_textures.push(texture);
+            let attachment_point = gl::COLOR_ATTACHMENT0 + index as GLenum;
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment_point, target, texture, 0);
+            draw_buffers.push(attachment_point);
+        }
+        if draw_buffers.is_empty() {
+            // depth only, e.g. shadow maps
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+        } else {
+            gl::DrawBuffers(draw_buffers.len() as GLsizei, draw_buffers.as_ptr());
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+        }
+
+        if let Some(attachment) = self.desc.depth_stencil {
+            let attachment_point = depth_stencil_attachment_point(attachment.internal_format)?;
+            match attachment.storage {
+                AttachmentStorage::Renderbuffer => {
+                    let mut renderbuffer = 0;
+                    gl::GenRenderbuffers(1, &mut renderbuffer);
+                    self.depth_stencil = Some(renderbuffer);
+                    gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                    gl::RenderbufferStorageMultisample(
+                        gl::RENDERBUFFER,
+                        samples,
+                        attachment.internal_format,
+                        width,
+                        height,
+                    );
+                    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+                    gl::FramebufferRenderbuffer(
+                        gl::FRAMEBUFFER,
+                        attachment_point,
+                        gl::RENDERBUFFER,
+                        renderbuffer,
+                    );
+                }
+                AttachmentStorage::Texture => {
+                    let texture =
+                        create_texture(target, attachment.internal_format, samples, width, height)?;
+                    self.depth_stencil = Some(texture);
+                    if samples == 0 {
+                        gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+                        gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+                    }
+                    gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment_point, target, texture, 0);
+                }
+            }
+        }
+        gl::BindTexture(target, 0);
+
+        check_status(gl::FRAMEBUFFER)
+    }
+
+    unsafe fn delete_attachments(&mut self) {
+        if !self.color_textures.is_empty() {
+            gl::DeleteTextures(
+                self.color_textures.len() as GLsizei,
+                self.color_textures.as_ptr(),
+            );
+            self.color_textures.clear();
+        }
+        if let (Some(id), Some(attachment)) = (self.depth_stencil.take(), self.desc.depth_stencil) {
+            match attachment.storage {
+                AttachmentStorage::Renderbuffer => gl::DeleteRenderbuffers(1, &id),
+                AttachmentStorage::Texture => gl::DeleteTextures(1, &id),
+            }
+        }
+    }
+
+    /// copy the `mask` buffers (COLOR_BUFFER_BIT, DEPTH_BUFFER_BIT, STENCIL_BUFFER_BIT) into
+    /// `target`, scaling to its size. this also resolves multisampled framebuffers. depth and
+    /// stencil need NEAREST filtering and matching formats
+    pub unsafe fn blit_to(&self, target: &Framebuffer, mask: GLbitfield, filter: GLenum) {
+        blit(self.id, self.size, target.id, target.size, mask, filter);
+    }
+
+    /// like `blit_to`, into the window's framebuffer of `size`
+    pub unsafe fn blit_to_default(&self, size: (i32, i32), mask: GLbitfield, filter: GLenum) {
+        blit(self.id, self.size, 0, size, mask, filter);
+    }
+
+    /// copy from the window's framebuffer of `size` into this one
+    pub unsafe fn blit_from_default(&self, size: (i32, i32), mask: GLbitfield, filter: GLenum) {
+        blit(0, size, self.id, self.size, mask, filter);
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.delete_attachments();
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
+
+/// copy between two framebuffers by id, 0 being the window. the previous framebuffer binding
+/// is not restored, both targets are left bound
+pub unsafe fn blit(
+    source: GLuint,
+    source_size: (i32, i32),
+    target: GLuint,
+    target_size: (i32, i32),
+    mask: GLbitfield,
+    filter: GLenum,
+) {
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, source);
+    gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target);
+    gl::BlitFramebuffer(
+        0,
+        0,
+        source_size.0,
+        source_size.1,
+        0,
+        0,
+        target_size.0,
+        target_size.1,
+        mask,
+        filter,
+    );
+}
+
+/// GL_MAX_SAMPLES, the most samples any multisampled attachment can have
+pub unsafe fn max_samples() -> u32 {
+    let mut max = 0;
+    gl::GetIntegerv(gl::MAX_SAMPLES, &mut max);
+    max.max(0) as u32
+}
+
+/// fail when `samples` is more than `max` allows, see `max_samples`
+pub fn validate_samples(samples: u32, max: u32) -> Result<()> {
+    if samples > max {
+        bail!(
+            "{} samples requested, but the driver supports at most {}",
+            samples,
+            max
+        );
+    }
+    Ok(())
+}
+
+/// fail with a readable reason when the framebuffer bound to `target` is not complete
+pub unsafe fn check_status(target: GLenum) -> Result<()> {
+    let status = gl::CheckFramebufferStatus(target);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        bail!(
+            "framebuffer incomplete: {} ({:#x})",
+            status_message(status),
+            status
+        );
+    }
+    Ok(())
+}
+
+/// what a glCheckFramebufferStatus result means
+pub fn status_message(status: GLenum) -> &'static str {
+    match status {
+        gl::FRAMEBUFFER_COMPLETE => "complete",
+        gl::FRAMEBUFFER_UNDEFINED => "the default framebuffer doesn't exist",
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+            "an attachment is incomplete or its format can't be rendered to"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "no image is attached",
+        gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => "a draw buffer names an empty attachment",
+        gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => "the read buffer names an empty attachment",
+        gl::FRAMEBUFFER_UNSUPPORTED => "the driver doesn't support this combination of formats",
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+            "the attachments differ in sample count or fixed sample locations"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => "layered and non-layered attachments are mixed",
+        0 => "the status query itself failed",
+        _ => "unknown status",
+    }
+}
+
+/// the target attachment textures with `samples` samples are bound to
+fn texture_target(samples: u32) -> GLenum {
+    if samples > 0 {
+        gl::TEXTURE_2D_MULTISAMPLE
+    } else {
+        gl::TEXTURE_2D
+    }
+}
+
+/// allocate an uninitialised texture of `internal_format`, left bound to `target`
+unsafe fn create_texture(
+    target: GLenum,
+    internal_format: GLenum,
+    samples: GLsizei,
+    width: i32,
+    height: i32,
+) -> Result<GLuint> {
+    // the format and type only have to be compatible, no data is uploaded
+    let (format, data_type) = transfer_format(internal_format)?;
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(target, texture);
+    if samples > 0 {
+        gl::TexImage2DMultisample(target, samples, internal_format, width, height, gl::TRUE);
+    } else {
+        gl::TexImage2D(
+            target,
+            0,
+            internal_format as GLint,
+            width,
+            height,
+            0,
+            format,
+            data_type,
+            ptr::null(),
+        );
+    }
+    Ok(texture)
+}
+
+/// a pixel format and type glTexImage2D accepts together with `internal_format`
+fn transfer_format(internal_format: GLenum) -> Result<(GLenum, GLenum)> {
+    Ok(match internal_format {
+        gl::R8 | gl::R16 | gl::R16F | gl::R32F | gl::R8_SNORM => (gl::RED, gl::FLOAT),
+        gl::RG8 | gl::RG16 | gl::RG16F | gl::RG32F | gl::RG8_SNORM => (gl::RG, gl::FLOAT),
+        gl::RGB8 | gl::SRGB8 | gl::RGB16F | gl::RGB32F | gl::R11F_G11F_B10F | gl::RGB565 => {
+            (gl::RGB, gl::FLOAT)
+        }
+        gl::RGBA8 | gl::SRGB8_ALPHA8 | gl::RGBA16 | gl::RGBA16F | gl::RGBA32F | gl::RGB10_A2 => {
+            (gl::RGBA, gl::FLOAT)
+        }
+        gl::R8I | gl::R16I | gl::R32I => (gl::RED_INTEGER, gl::INT),
+        gl::R8UI | gl::R16UI | gl::R32UI => (gl::RED_INTEGER, gl::UNSIGNED_INT),
+        gl::RG8I | gl::RG16I | gl::RG32I => (gl::RG_INTEGER, gl::INT),
+        gl::RG8UI | gl::RG16UI | gl::RG32UI => (gl::RG_INTEGER, gl::UNSIGNED_INT),
+        gl::RGBA8I | gl::RGBA16I | gl::RGBA32I => (gl::RGBA_INTEGER, gl::INT),
+        gl::RGBA8UI | gl::RGBA16UI | gl::RGBA32UI | gl::RGB10_A2UI => {
+            (gl::RGBA_INTEGER, gl::UNSIGNED_INT)
+        }
+        gl::DEPTH_COMPONENT16 | gl::DEPTH_COMPONENT24 | gl::DEPTH_COMPONENT32F => {
+            (gl::DEPTH_COMPONENT, gl::FLOAT)
+        }
+        gl::DEPTH24_STENCIL8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+        gl::DEPTH32F_STENCIL8 => (gl::DEPTH_STENCIL, gl::FLOAT_32_UNSIGNED_INT_24_8_REV),
+        // stencil-only textures need GL 4.4, use a renderbuffer instead
+        _ => bail!(
+            "internal format {:#x} can't be used for a framebuffer texture",
+            internal_format
+        ),
+    })
+}
+
+fn depth_stencil_attachment_point(internal_format: GLenum) -> Result<GLenum> {
+    Ok(match internal_format {
+        gl::DEPTH_COMPONENT16 | gl::DEPTH_COMPONENT24 | gl::DEPTH_COMPONENT32F => {
+            gl::DEPTH_ATTACHMENT
+        }
+        gl::DEPTH24_STENCIL8 | gl::DEPTH32F_STENCIL8 => gl::DEPTH_STENCIL_ATTACHMENT,
+        gl::STENCIL_INDEX8 => gl::STENCIL_ATTACHMENT,
+        _ => bail!(
+            "internal format {:#x} is not a depth or stencil format",
+            internal_format
+        ),
+    })
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use gl::types::*;
+
+use crate::{
+    render_state::{BlendState, CullState, DepthState, StencilState},
+    shaders::Shader,
+};
+
+/// how many state changes went to the driver and how many were dropped as redundant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateCounters {
+    pub issued: u32,
+    pub skipped: u32,
+}
+
+/// shadows the GL state set through it and skips calls that wouldn't change anything.
+///
+/// GL calls made around the cache, e.g. by a texture upload binding its texture, leave the
+/// shadow stale. call `invalidate` afterwards so the next change of that state is issued again.
+/// unknown state (`None`) is always set
+#[derive(Debug, Default)]
+pub struct GlState {
+    program: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    /// buffer bound to each target, e.g. ARRAY_BUFFER or UNIFORM_BUFFER. ELEMENT_ARRAY_BUFFER
+    /// belongs to the vertex array and is not tracked
+    buffers: HashMap<GLenum, GLuint>,
+    active_texture_unit: Option<u32>,
+    /// texture bound to each (unit, target)
+    textures: HashMap<(u32, GLenum), GLuint>,
+    depth: Option<DepthState>,
+    stencil: Option<StencilState>,
+    blend: Option<BlendState>,
+    cull: Option<CullState>,
+    viewport: Option<[GLint; 4]>,
+    counters: StateCounters,
+}
+
+#[allow(dead_code)]
+impl GlState {
+    pub fn new() -> GlState {
+        Self::default()
+    }
+
+    /// forget everything, the next change of every state is issued
+    pub fn invalidate(&mut self) {
+        *self = GlState {
+            counters: self.counters,
+            ..GlState::default()
+        };
+    }
+
+    /// the counters since the last call, which starts counting a new frame
+    pub fn end_frame(&mut self) -> StateCounters {
+        std::mem::take(&mut self.counters)
+    }
+
+    pub fn counters(&self) -> StateCounters {
+        self.counters
+    }
+
+    /// count the change and report whether it has to be issued
+    fn changed<T: PartialEq>(
+        counters: &mut StateCounters,
+        current: &mut Option<T>,
+        new: T,
+    ) -> bool {
+        if current.as_ref() == Some(&new) {
+            counters.skipped += 1;
+            false
+        } else {
+            *current = Some(new);
+            counters.issued += 1;
+            true
+        }
+    }
+
+    pub unsafe fn use_program(&mut self, shader: &Shader) {
+        self.use_program_id(shader.id);
+    }
+
+    pub unsafe fn use_program_id(&mut self, program: GLuint) {
+        if Self::changed(&mut self.counters, &mut self.program, program) {
+            gl::UseProgram(program);
+        }
+    }
+
+    pub unsafe fn bind_vertex_array(&mut self, vertex_array: GLuint) {
+        if Self::changed(&mut self.counters, &mut self.vertex_array, vertex_array) {
+            gl::BindVertexArray(vertex_array);
+        }
+    }
+
+    pub unsafe fn bind_buffer(&mut self, target: GLenum, buffer: GLuint) {
+        let mut current = self.buffers.get(&target).copied();
+        if Self::changed(&mut self.counters, &mut current, buffer) {
+            self.buffers.insert(target, buffer);
+            gl::BindBuffer(target, buffer);
+        }
+    }
+
+    /// bind `texture` to `target` of texture unit `unit`, only switching the active unit when
+    /// the binding actually changes
+    pub unsafe fn bind_texture(&mut self, unit: u32, target: GLenum, texture: GLuint) {
+        let mut current = self.textures.get(&(unit, target)).copied();
+        if Self::changed(&mut self.counters, &mut current, texture) {
+            self.textures.insert((unit, target), texture);
+            self.set_active_texture_unit(unit);
+            gl::BindTexture(target, texture);
+        }
+    }
+
+    pub unsafe fn set_active_texture_unit(&mut self, unit: u32) {
+        if Self::changed(&mut self.counters, &mut self.active_texture_unit, unit) {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+        }
+    }
+
+    /// a deleted texture's id can be reused, drop it from the shadow
+    pub fn forget_texture(&mut self, texture: GLuint) {
+        self.textures.retain(|_, bound| *bound != texture);
+    }
+
+    pub unsafe fn set_depth(&mut self, depth: DepthState) {
+        if Self::changed(&mut self.counters, &mut self.depth, depth) {
+            depth.apply();
+        }
+    }
+
+    pub unsafe fn set_stencil(&mut self, stencil: StencilState) {
+        if Self::changed(&mut self.counters, &mut self.stencil, stencil) {
+            stencil.apply();
+        }
+    }
+
+    pub unsafe fn set_blend(&mut self, blend: BlendState) {
+        if Self::changed(&mut self.counters, &mut self.blend, blend) {
+            blend.apply();
+        }
+    }
+
+    pub unsafe fn set_cull(&mut self, cull: CullState) {
+        if Self::changed(&mut self.counters, &mut self.cull, cull) {
+            cull.apply();
+        }
+    }
+
+    pub unsafe fn set_viewport(&mut self, x: GLint, y: GLint, width: GLsizei, height: GLsizei) {
+        if Self::changed(
+            &mut self.counters,
+            &mut self.viewport,
+            [x, y, width, height],
+        ) {
+            gl::Viewport(x, y, width, height);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::ffi::CString;
+
+use anyhow::{bail, Result};
+use cgmath::{vec3, Angle, Deg, InnerSpace, Vector3};
+
+use crate::{shaders::Shader, texture::Texture2D};
+
+/// name of a struct field uniform, e.g. `material.diffuse`
+pub fn uniform_name(prefix: &str, field: &str) -> Result<CString> {
+    Ok(CString::new(format!("{}.{}", prefix, field))?)
+}
+
+/// a rust value mirroring a glsl struct uniform
+pub trait UniformStruct {
+    /// set every field as `<name>.field`, the shader has to be in use
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()>;
+}
+
+/// upload `items` to the array uniform `<name>[i]` and their count to `int <name>Count`.
+/// `max` is the array size the shader was compiled with
+pub unsafe fn upload_array<T: UniformStruct>(
+    shader: &Shader,
+    name: &str,
+    items: &[T],
+    max: usize,
+) -> Result<()> {
+    if items.len() > max {
+        bail!(
+            "{} elements for {}, but the shader only has room for {}",
+            items.len(),
+            name,
+            max
+        );
+    }
+    for (index, item) in items.iter().enumerate() {
+        item.upload(shader, &format!("{}[{}]", name, index))?;
+    }
+    shader.set_int(&CString::new(format!("{}Count", name))?, items.len() as i32);
+    Ok(())
+}
+
+/// surface colours of the phong model, uploaded to a glsl `struct Material`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    /// specular exponent, higher is a smaller and sharper highlight
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        // the coral cube from the colors chapter
+        Self {
+            ambient: vec3(1.0, 0.5, 0.31),
+            diffuse: vec3(1.0, 0.5, 0.31),
+            specular: vec3(0.5, 0.5, 0.5),
+            shininess: 32.0,
+        }
+    }
+}
+
+impl UniformStruct for Material {
+    /// sets `<name>.ambient`, `<name>.diffuse`, `<name>.specular` and `<name>.shininess`
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(&uniform_name(name, "ambient")?, &self.ambient);
+        shader.set_vector3(&uniform_name(name, "diffuse")?, &self.diffuse);
+        shader.set_vector3(&uniform_name(name, "specular")?, &self.specular);
+        shader.set_float(&uniform_name(name, "shininess")?, self.shininess);
+        Ok(())
+    }
+}
+
+/// a light at a position without attenuation, uploaded to a glsl `struct Light`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub position: Vector3<f32>,
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            position: vec3(1.2, 1.0, 2.0),
+            ambient: vec3(0.2, 0.2, 0.2),
+            diffuse: vec3(0.5, 0.5, 0.5),
+            specular: vec3(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Light {
+    /// a light of the given colour, see `LightColor::from_color`
+    pub fn with_color(position: Vector3<f32>, color: Vector3<f32>) -> Light {
+        let LightColor {
+            ambient,
+            diffuse,
+            specular,
+        } = LightColor::from_color(color);
+        Light {
+            position,
+            ambient,
+            diffuse,
+            specular,
+        }
+    }
+
+    fn color(&self) -> LightColor {
+        LightColor {
+            ambient: self.ambient,
+            diffuse: self.diffuse,
+            specular: self.specular,
+        }
+    }
+}
+
+impl UniformStruct for Light {
+    /// sets `<name>.position`, `<name>.ambient`, `<name>.diffuse` and `<name>.specular`
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(&uniform_name(name, "position")?, &self.position);
+        self.color().upload(shader, name)
+    }
+}
+
+/// texture units used by `MapMaterial`, shaders never have to assign these themselves
+pub const DIFFUSE_UNIT: u32 = 0;
+pub const SPECULAR_UNIT: u32 = 1;
+pub const EMISSION_UNIT: u32 = 2;
+
+/// a material whose colours come from textures, uploaded to a glsl `struct Material` with
+/// `sampler2D diffuse`, `sampler2D specular`, `sampler2D emission` and `float shininess`.
+/// use `Texture2D::solid` for maps a surface doesn't have
+pub struct MapMaterial {
+    /// also used as the ambient colour
+    pub diffuse: Texture2D,
+    pub specular: Texture2D,
+    /// light the surface gives off by itself, added on top of the lighting
+    pub emission: Texture2D,
+    pub shininess: f32,
+}
+
+impl UniformStruct for MapMaterial {
+    /// binds the maps to their fixed units and points the sampler uniforms `<name>.diffuse`,
+    /// `<name>.specular` and `<name>.emission` at them
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        let maps = [
+            ("diffuse", DIFFUSE_UNIT, &self.diffuse),
+            ("specular", SPECULAR_UNIT, &self.specular),
+            ("emission", EMISSION_UNIT, &self.emission),
+        ];
+        for (field, unit, texture) in maps {
+            shader.set_int(&uniform_name(name, field)?, unit as i32);
+            texture.bind(unit);
+        }
+        shader.set_float(&uniform_name(name, "shininess")?, self.shininess);
+        Ok(())
+    }
+}
+
+/// the three phong intensities every light caster has
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightColor {
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+}
+
+impl Default for LightColor {
+    fn default() -> Self {
+        Self::from_color(vec3(1.0, 1.0, 1.0))
+    }
+}
+
+impl LightColor {
+    /// dimmed diffuse, a faint ambient and full specular
+    pub fn from_color(color: Vector3<f32>) -> LightColor {
+        let diffuse = color * 0.5;
+        LightColor {
+            ambient: diffuse * 0.2,
+            diffuse,
+            specular: vec3(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl UniformStruct for LightColor {
+    /// sets `<name>.ambient`, `<name>.diffuse` and `<name>.specular`
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(&uniform_name(name, "ambient")?, &self.ambient);
+        shader.set_vector3(&uniform_name(name, "diffuse")?, &self.diffuse);
+        shader.set_vector3(&uniform_name(name, "specular")?, &self.specular);
+        Ok(())
+    }
+}
+
+/// `1 / (constant + linear * d + quadratic * d^2)` falloff of point and spot lights
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+/// (range, linear, quadratic) from the Ogre3D wiki, the constant term is always 1
+const ATTENUATION_TABLE: [(f32, f32, f32); 12] = [
+    (7.0, 0.7, 1.8),
+    (13.0, 0.35, 0.44),
+    (20.0, 0.22, 0.20),
+    (32.0, 0.14, 0.07),
+    (50.0, 0.09, 0.032),
+    (65.0, 0.07, 0.017),
+    (100.0, 0.045, 0.0075),
+    (160.0, 0.027, 0.0028),
+    (200.0, 0.022, 0.0019),
+    (325.0, 0.014, 0.0007),
+    (600.0, 0.007, 0.0002),
+    (3250.0, 0.0014, 0.000007),
+];
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::for_range(50.0)
+    }
+}
+
+impl Attenuation {
+    /// the smallest table entry that still reaches `range`, the last one for larger ranges
+    pub fn for_range(range: f32) -> Attenuation {
+        let (_, linear, quadratic) = ATTENUATION_TABLE
+            .iter()
+            .copied()
+            .find(|(entry, _, _)| *entry >= range)
+            .unwrap_or(ATTENUATION_TABLE[ATTENUATION_TABLE.len() - 1]);
+        Attenuation {
+            constant: 1.0,
+            linear,
+            quadratic,
+        }
+    }
+}
+
+impl UniformStruct for Attenuation {
+    /// sets `<name>.constant`, `<name>.linear` and `<name>.quadratic`
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_float(&uniform_name(name, "constant")?, self.constant);
+        shader.set_float(&uniform_name(name, "linear")?, self.linear);
+        shader.set_float(&uniform_name(name, "quadratic")?, self.quadratic);
+        Ok(())
+    }
+}
+
+/// light from infinitely far away, like the sun
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// direction the light travels in
+    pub direction: Vector3<f32>,
+    pub color: LightColor,
+}
+
+impl UniformStruct for DirectionalLight {
+    /// sets `<name>.direction` and the colours
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(
+            &uniform_name(name, "direction")?,
+            &self.direction.normalize(),
+        );
+        self.color.upload(shader, name)
+    }
+}
+
+/// light shining in every direction from a position, fading with distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Vector3<f32>,
+    pub attenuation: Attenuation,
+    pub color: LightColor,
+}
+
+impl UniformStruct for PointLight {
+    /// sets `<name>.position`, the attenuation terms and the colours
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(&uniform_name(name, "position")?, &self.position);
+        self.attenuation.upload(shader, name)?;
+        self.color.upload(shader, name)
+    }
+}
+
+/// a point light limited to a cone, with a soft edge between the inner and outer angle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: Vector3<f32>,
+    pub direction: Vector3<f32>,
+    /// full intensity inside this angle from the direction
+    pub inner_cut_off: Deg<f32>,
+    /// no light outside this angle
+    pub outer_cut_off: Deg<f32>,
+    pub attenuation: Attenuation,
+    pub color: LightColor,
+}
+
+impl UniformStruct for SpotLight {
+    /// sets `<name>.position`, `<name>.direction`, the cosines of the angles as `<name>.cutOff`
+    /// and `<name>.outerCutOff`, the attenuation terms and the colours
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_vector3(&uniform_name(name, "position")?, &self.position);
+        shader.set_vector3(
+            &uniform_name(name, "direction")?,
+            &self.direction.normalize(),
+        );
+        // the shader compares against dot products, so it gets cosines instead of angles
+        shader.set_float(&uniform_name(name, "cutOff")?, self.inner_cut_off.cos());
+        shader.set_float(
+            &uniform_name(name, "outerCutOff")?,
+            self.outer_cut_off.cos(),
+        );
+        self.attenuation.upload(shader, name)?;
+        self.color.upload(shader, name)
+    }
+}
+
+/// array sizes of the light casters shader, injected as `#define`s so rust and glsl agree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LightLimits {
+    pub max_point_lights: usize,
+    pub max_spot_lights: usize,
+}
+
+impl Default for LightLimits {
+    fn default() -> Self {
+        Self {
+            max_point_lights: 4,
+            max_spot_lights: 1,
+        }
+    }
+}
+
+impl LightLimits {
+    /// `MAX_POINT_LIGHTS` and `MAX_SPOT_LIGHTS` for `Shader::with_defines`
+    pub fn defines(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("MAX_POINT_LIGHTS", self.max_point_lights.to_string()),
+            ("MAX_SPOT_LIGHTS", self.max_spot_lights.to_string()),
+        ]
+    }
+}
@@ -1,4 +1,16 @@
-use _1_getting_started::{main_1_4_1, main_1_4_2};
+use _1_getting_started::{
+    main_1_4_1, main_1_4_2, main_1_5_1, main_1_5_2, main_1_6_1, main_1_6_2, main_1_6_3, main_1_7_1,
+    main_1_7_2, main_1_7_3,
+};
+use _2_lighting::{
+    main_2_1_1, main_2_2_1, main_2_2_2, main_2_2_3, main_2_3_1, main_2_4_1, main_2_4_2, main_2_6_1,
+};
+use _3_model_loading::{main_3_1_1, main_3_1_2};
+use _4_advanced_opengl::{
+    main_4_10_1, main_4_10_3, main_4_11_1, main_4_2_1, main_4_3_2, main_4_3_3, main_4_5_1,
+    main_4_6_1, main_4_8_1, main_4_9_1, main_4_9_2, main_4_9_3,
+};
+use _5_advanced_lighting::main_5_3_1;
 
 use crate::_1_getting_started::{
     main_1_1_1, main_1_2_1, main_1_2_2, main_1_2_3, main_1_2_4, main_1_2_5, main_1_3_1, main_1_3_2,
@@ -7,11 +19,36 @@ use crate::_1_getting_started::{
 
 // this is based on the web version
 mod _1_getting_started;
+mod _2_lighting;
+mod _3_model_loading;
+mod _4_advanced_opengl;
+mod _5_advanced_lighting;
+mod camera;
+mod compressed;
+mod draw_queue;
+mod environment;
+mod framebuffer;
+mod gl_state;
+mod lighting;
+mod model;
+mod normal_overlay;
+mod oit;
+mod post_process;
+mod projection;
+mod render_state;
+mod sampler;
 mod shaders;
+mod shadow;
+mod skybox;
+mod texture;
+mod transform;
+mod uniform_buffer;
+mod vertex_layout;
 
 fn main() -> anyhow::Result<()> {
     let args = std::env::args().collect::<Vec<_>>();
-    if args.len() != 2 {
+    // anything after the tutorial id is read by the tutorial itself
+    if args.len() < 2 {
         println!("Call with the number of the tutorial, eg:. 1_1_2 for _1_2_hello_window_clear.rs");
         std::process::exit(1);
     }
@@ -29,6 +66,37 @@ fn main() -> anyhow::Result<()> {
         "1_3_3" => main_1_3_3(),
         "1_4_1" => main_1_4_1(),
         "1_4_2" => main_1_4_2(),
+        "1_5_1" => main_1_5_1(),
+        "1_5_2" => main_1_5_2(),
+        "1_6_1" => main_1_6_1(),
+        "1_6_2" => main_1_6_2(),
+        "1_6_3" => main_1_6_3(),
+        "1_7_1" => main_1_7_1(),
+        "1_7_2" => main_1_7_2(),
+        "1_7_3" => main_1_7_3(),
+        "2_1_1" => main_2_1_1(),
+        "2_2_1" => main_2_2_1(),
+        "2_2_2" => main_2_2_2(),
+        "2_2_3" => main_2_2_3(),
+        "2_3_1" => main_2_3_1(),
+        "2_4_1" => main_2_4_1(),
+        "2_4_2" => main_2_4_2(),
+        "2_6_1" => main_2_6_1(),
+        "3_1_1" => main_3_1_1(),
+        "3_1_2" => main_3_1_2(),
+        "4_2_1" => main_4_2_1(),
+        "4_3_2" => main_4_3_2(),
+        "4_3_3" => main_4_3_3(),
+        "4_5_1" => main_4_5_1(),
+        "4_6_1" => main_4_6_1(),
+        "4_8_1" => main_4_8_1(),
+        "4_9_1" => main_4_9_1(),
+        "4_9_2" => main_4_9_2(),
+        "4_9_3" => main_4_9_3(),
+        "4_10_1" => main_4_10_1(),
+        "4_10_3" => main_4_10_3(),
+        "4_11_1" => main_4_11_1(),
+        "5_3_1" => main_5_3_1(),
         _ => {
             println!("Unknown chapter id");
             Ok(())
@@ -0,0 +1,848 @@
+use std::{fs, ops::Range, path::Path};
+
+use anyhow::{bail, Context, Result};
+use cgmath::{vec2, vec3, Matrix4, Quaternion, Vector3, Zero};
+
+use super::{
+    face_normal, json::Json, normalize_or_up, ImageSource, MaterialData, MeshData, ModelData,
+    NodeData, TextureSource, Vertex,
+};
+use crate::{
+    compressed::{read_u32, slice},
+    sampler::SamplerDesc,
+    transform::Transform,
+};
+
+/// "glTF" at the start of a binary file
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+// accessor component types
+const BYTE: u32 = 5120;
+const UNSIGNED_BYTE: u32 = 5121;
+const SHORT: u32 = 5122;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+// primitive modes, points and lines are not supported
+const TRIANGLES: usize = 4;
+const TRIANGLE_STRIP: usize = 5;
+const TRIANGLE_FAN: usize = 6;
+
+/// load a `.gltf` file with its external buffers and images or a self-contained `.glb`
+pub fn load<P: AsRef<Path>>(path: P) -> Result<ModelData> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let directory = path.parent().unwrap_or(Path::new(""));
+    parse(&bytes, directory).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// parse glTF JSON or a GLB container from memory, external files are resolved relative to
+/// `base_dir`. texture coordinates are flipped to put the origin at the bottom left like every
+/// other texture of the project
+pub fn parse(bytes: &[u8], base_dir: &Path) -> Result<ModelData> {
+    let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let json = std::str::from_utf8(json).context("the JSON is not valid utf-8")?;
+    let json = Json::parse(json).context("invalid JSON")?;
+
+    let version = json
+        .get("asset")
+        .map(|asset| asset.str("version"))
+        .transpose()?
+        .flatten();
+    if !version.is_some_and(|version| version.starts_with("2.")) {
+        bail!("unsupported glTF version {:?}", version);
+    }
+
+    let document = Document {
+        buffers: load_buffers(&json, bin, base_dir)?,
+        json: &json,
+        base_dir,
+    };
+    document.model()
+}
+
+/// the JSON and BIN chunks of a GLB file
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>)> {
+    let version = read_u32(bytes, 4)?;
+    if version != 2 {
+        bail!("GLB version {}, expected 2", version);
+    }
+    let length = read_u32(bytes, 8)? as usize;
+    let bytes = slice(bytes, 0, length).context("GLB shorter than its header says")?;
+
+    let (mut json, mut bin) = (None, None);
+    let mut offset = 12;
+    while offset < bytes.len() {
+        let chunk_length = read_u32(bytes, offset)? as usize;
+        let chunk_type = read_u32(bytes, offset + 4)?;
+        let data = slice(bytes, offset + 8, chunk_length)?;
+        match chunk_type {
+            CHUNK_JSON if json.is_none() => json = Some(data),
+            CHUNK_BIN if bin.is_none() => bin = Some(data),
+            // extension chunks
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+    Ok((json.context("GLB without a JSON chunk")?, bin))
+}
+
+fn load_buffers(json: &Json, bin: Option<&[u8]>, base_dir: &Path) -> Result<Vec<Vec<u8>>> {
+    json.array("buffers")?
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| {
+            let data = match buffer.str("uri")? {
+                Some(uri) => read_uri(uri, base_dir)?,
+                // only the first buffer of a GLB may refer to the BIN chunk
+                None if index == 0 => bin.context("no uri and no GLB BIN chunk")?.to_vec(),
+                None => bail!("no uri"),
+            };
+            let length = buffer.usize("byteLength")?.context("no byteLength")?;
+            if data.len() < length {
+                bail!("{} bytes, byteLength says {}", data.len(), length);
+            }
+            Ok(data)
+        })
+        .enumerate()
+        .map(|(index, result)| result.with_context(|| format!("buffer {}", index)))
+        .collect()
+}
+
+/// contents of a base64 data uri or of a file relative to `base_dir`
+fn read_uri(uri: &str, base_dir: &Path) -> Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (header, payload) = data.split_once(',').context("data uri without data")?;
+        if !header.ends_with(";base64") {
+            bail!("only base64 data uris are supported");
+        }
+        return decode_base64(payload);
+    }
+    let path = base_dir.join(percent_decode(uri));
+    fs::read(&path).with_context(|| format!("reading {}", path.display()))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut accumulator, mut bits) = (0u32, 0);
+    for character in text.bytes() {
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => bail!("invalid base64 character {:?}", character as char),
+        };
+        accumulator = accumulator << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((accumulator >> bits) as u8);
+            accumulator &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+/// uris are uri encoded, e.g. spaces in file names are `%20`
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// where the elements of an accessor are
+struct AccessorLayout<'a> {
+    /// `None` for accessors without a buffer view, which are all zeros
+    data: Option<&'a [u8]>,
+    component_type: u32,
+    component_size: usize,
+    components: usize,
+    count: usize,
+    stride: usize,
+    normalized: bool,
+}
+
+struct Document<'a> {
+    json: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    base_dir: &'a Path,
+}
+
+impl Document<'_> {
+    /// the element `index` of the top level array `key`
+    fn item(&self, key: &str, index: usize) -> Result<&Json> {
+        self.json
+            .array(key)?
+            .get(index)
+            .with_context(|| format!("{} {} does not exist", key, index))
+    }
+
+    /// the bytes of a buffer view and its stride
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>)> {
+        let view = self.item("bufferViews", index)?;
+        (|| -> Result<_> {
+            let buffer = view.usize("buffer")?.context("no buffer")?;
+            let data = self
+                .buffers
+                .get(buffer)
+                .with_context(|| format!("buffer {} does not exist", buffer))?;
+            let offset = view.usize("byteOffset")?.unwrap_or(0);
+            let length = view.usize("byteLength")?.context("no byteLength")?;
+            let data = slice(data, offset, length)
+                .with_context(|| format!("range outside of buffer {}", buffer))?;
+            Ok((data, view.usize("byteStride")?))
+        })()
+        .with_context(|| format!("buffer view {}", index))
+    }
+
+    fn accessor_layout(&self, index: usize) -> Result<AccessorLayout<'_>> {
+        let accessor = self.item("accessors", index)?;
+        if accessor.get("sparse").is_some() {
+            bail!("sparse accessors are not supported");
+        }
+        let component_type = accessor
+            .usize("componentType")?
+            .context("no componentType")? as u32;
+        let component_size = match component_type {
+            BYTE | UNSIGNED_BYTE => 1,
+            SHORT | UNSIGNED_SHORT => 2,
+            UNSIGNED_INT | FLOAT => 4,
+            _ => bail!("unknown component type {}", component_type),
+        };
+        let components = match accessor.str("type")?.context("no type")? {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            // columns of small matrices are padded to 4 bytes
+            "MAT2" if component_size == 4 => 4,
+            "MAT3" if component_size == 4 => 9,
+            "MAT4" if component_size == 4 => 16,
+            other => bail!("type {} with {} byte components", other, component_size),
+        };
+        let count = accessor.usize("count")?.context("no count")?;
+        let normalized = accessor
+            .get("normalized")
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let element_size = component_size * components;
+
+        let Some(view) = accessor.usize("bufferView")? else {
+            return Ok(AccessorLayout {
+                data: None,
+                component_type,
+                component_size,
+                components,
+                count,
+                stride: element_size,
+                normalized,
+            });
+        };
+        let (data, stride) = self.buffer_view(view)?;
+        let stride = stride.unwrap_or(element_size);
+        if stride < element_size {
+            bail!(
+                "byteStride {} is smaller than the {} byte elements",
+                stride,
+                element_size
+            );
+        }
+        let offset = accessor.usize("byteOffset")?.unwrap_or(0);
+        let needed = match count {
+            0 => 0,
+            _ => stride
+                .checked_mul(count - 1)
+                .and_then(|size| size.checked_add(element_size))
+                .with_context(|| {
+                    format!("{} elements with a stride of {} overflow", count, stride)
+                })?,
+        };
+        let data = slice(data, offset, needed).with_context(|| {
+            format!(
+                "{} elements at offset {} don't fit buffer view {} of {} bytes",
+                count,
+                offset,
+                view,
+                data.len()
+            )
+        })?;
+        Ok(AccessorLayout {
+            data: Some(data),
+            component_type,
+            component_size,
+            components,
+            count,
+            stride,
+            normalized,
+        })
+    }
+
+    /// the elements of a `VECN` or `SCALAR` accessor as floats. normalized integers are mapped
+    /// to 0..1 (unsigned) or -1..1 (signed), other integers are converted as-is
+    fn read_vectors<const N: usize>(&self, index: usize) -> Result<Vec<[f32; N]>> {
+        (|| -> Result<_> {
+            let layout = self.accessor_layout(index)?;
+            if layout.components != N {
+                bail!("expected {} components, got {}", N, layout.components);
+            }
+            let Some(data) = layout.data else {
+                return Ok(vec![[0.0; N]; layout.count]);
+            };
+            let read = |bytes: &[u8]| match (layout.component_type, layout.normalized) {
+                (BYTE, false) => bytes[0] as i8 as f32,
+                (BYTE, true) => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+                (UNSIGNED_BYTE, false) => bytes[0] as f32,
+                (UNSIGNED_BYTE, true) => bytes[0] as f32 / 255.0,
+                (SHORT, false) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                (SHORT, true) => {
+                    (i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32767.0).max(-1.0)
+                }
+                (UNSIGNED_SHORT, false) => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                (UNSIGNED_SHORT, true) => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+                (UNSIGNED_INT, _) => {
+                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32
+                }
+                _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            };
+            Ok((0..layout.count)
+                .map(|element| {
+                    let start = element * layout.stride;
+                    std::array::from_fn(|component| {
+                        read(&data[start + component * layout.component_size..])
+                    })
+                })
+                .collect())
+        })()
+        .with_context(|| format!("accessor {}", index))
+    }
+
+    /// an index accessor, which has to be unsigned and can't be normalized
+    fn read_indices(&self, index: usize) -> Result<Vec<u32>> {
+        (|| -> Result<_> {
+            let layout = self.accessor_layout(index)?;
+            if layout.components != 1 || layout.normalized {
+                bail!("indices have to be unnormalized scalars");
+            }
+            if !matches!(
+                layout.component_type,
+                UNSIGNED_BYTE | UNSIGNED_SHORT | UNSIGNED_INT
+            ) {
+                bail!("index component type {}", layout.component_type);
+            }
+            let Some(data) = layout.data else {
+                return Ok(vec![0; layout.count]);
+            };
+            Ok((0..layout.count)
+                .map(|element| {
+                    let bytes = &data[element * layout.stride..];
+                    match layout.component_type {
+                        UNSIGNED_BYTE => bytes[0] as u32,
+                        UNSIGNED_SHORT => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+                        _ => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    }
+                })
+                .collect())
+        })()
+        .with_context(|| format!("accessor {}", index))
+    }
+
+    fn model(&self) -> Result<ModelData> {
+        let materials = self
+            .json
+            .array("materials")?
+            .iter()
+            .enumerate()
+            .map(|(index, material)| {
+                self.material(material)
+                    .with_context(|| format!("material {}", index))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // every primitive becomes a mesh, remember which ones belong to each glTF mesh
+        let mut meshes = Vec::new();
+        let mut mesh_ranges: Vec<Range<usize>> = Vec::new();
+        for (index, mesh) in self.json.array("meshes")?.iter().enumerate() {
+            let start = meshes.len();
+            let name = mesh.str("name")?.unwrap_or_default();
+            for (primitive_index, primitive) in mesh.array("primitives")?.iter().enumerate() {
+                let data = self
+                    .primitive(name, primitive, materials.len())
+                    .with_context(|| {
+                        format!("mesh {} ({:?}) primitive {}", index, name, primitive_index)
+                    })?;
+                meshes.push(data);
+            }
+            mesh_ranges.push(start..meshes.len());
+        }
+
+        let nodes = self
+            .json
+            .array("nodes")?
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                self.node(node, &mesh_ranges)
+                    .with_context(|| format!("node {}", index))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let roots = self.roots(&nodes)?;
+        Ok(ModelData {
+            meshes,
+            materials,
+            nodes,
+            roots,
+        })
+    }
+
+    fn primitive(&self, name: &str, primitive: &Json, material_count: usize) -> Result<MeshData> {
+        let attributes = primitive.get("attributes").context("no attributes")?;
+        let positions = self.read_vectors::<3>(
+            attributes
+                .usize("POSITION")?
+                .context("no POSITION attribute")?,
+        )?;
+        let normals = attributes
+            .usize("NORMAL")?
+            .map(|accessor| self.read_vectors::<3>(accessor))
+            .transpose()?;
+        let tex_coords = attributes
+            .usize("TEXCOORD_0")?
+            .map(|accessor| self.read_vectors::<2>(accessor))
+            .transpose()?;
+        for (attribute, count) in [
+            ("NORMAL", normals.as_ref().map(Vec::len)),
+            ("TEXCOORD_0", tex_coords.as_ref().map(Vec::len)),
+        ] {
+            if count.is_some_and(|count| count != positions.len()) {
+                bail!(
+                    "{} has {} elements for {} positions",
+                    attribute,
+                    count.unwrap_or_default(),
+                    positions.len()
+                );
+            }
+        }
+
+        let indices = match primitive.usize("indices")? {
+            Some(accessor) => {
+                let indices = self.read_indices(accessor)?;
+                if let Some(index) = indices
+                    .iter()
+                    .find(|&&index| index as usize >= positions.len())
+                {
+                    bail!(
+                        "accessor {}: index {} out of range for {} vertices",
+                        accessor,
+                        index,
+                        positions.len()
+                    );
+                }
+                indices
+            }
+            None => (0..positions.len() as u32).collect(),
+        };
+        let indices = match primitive.usize("mode")?.unwrap_or(TRIANGLES) {
+            TRIANGLES => indices,
+            TRIANGLE_STRIP => strip_to_list(&indices),
+            TRIANGLE_FAN => fan_to_list(&indices),
+            mode => bail!("primitive mode {} is not supported", mode),
+        };
+        if indices.len() % 3 != 0 {
+            bail!("{} indices don't form whole triangles", indices.len());
+        }
+
+        let material = primitive.usize("material")?;
+        if let Some(material) = material.filter(|&material| material >= material_count) {
+            bail!("material {} does not exist", material);
+        }
+
+        let positions: Vec<Vector3<f32>> = positions.into_iter().map(Vector3::from).collect();
+        let normals = match normals {
+            Some(normals) => normals.into_iter().map(Vector3::from).collect(),
+            None => smooth_normals(&positions, &indices),
+        };
+        let vertices = positions
+            .iter()
+            .zip(normals)
+            .enumerate()
+            .map(|(index, (&position, normal))| Vertex {
+                position,
+                normal,
+                tex_coords: tex_coords.as_ref().map_or(vec2(0.0, 0.0), |tex_coords| {
+                    let [u, v] = tex_coords[index];
+                    vec2(u, 1.0 - v)
+                }),
+            })
+            .collect();
+        Ok(MeshData {
+            name: name.to_string(),
+            vertices,
+            indices,
+            material,
+        })
+    }
+
+    /// a metallic-roughness material with an approximation of the phong values
+    fn material(&self, material: &Json) -> Result<MaterialData> {
+        let pbr = material.get("pbrMetallicRoughness");
+        let pbr_value = |key: &str| pbr.map(|pbr| pbr.f32(key)).transpose();
+        let base_color = pbr
+            .map(|pbr| pbr.floats::<4>("baseColorFactor"))
+            .transpose()?
+            .flatten()
+            .unwrap_or([1.0; 4]);
+        let metallic = pbr_value("metallicFactor")?.flatten().unwrap_or(1.0);
+        let roughness = pbr_value("roughnessFactor")?.flatten().unwrap_or(1.0);
+        let emission = Vector3::from(material.floats::<3>("emissiveFactor")?.unwrap_or([0.0; 3]));
+
+        let diffuse = vec3(base_color[0], base_color[1], base_color[2]);
+        // dielectrics reflect about 4% of the light, metals tint the reflection
+        let specular = vec3(0.04, 0.04, 0.04) * (1.0 - metallic) + diffuse * metallic;
+        // the blinn-phong exponent with about the same highlight size
+        let alpha = roughness.max(0.05).powi(2);
+        let shininess = (2.0 / (alpha * alpha) - 2.0).clamp(1.0, 256.0);
+
+        let texture = |json: Option<&Json>, key: &str| {
+            json.and_then(|json| json.get(key))
+                .map(|info| self.texture(info).with_context(|| key.to_string()))
+                .transpose()
+        };
+        Ok(MaterialData {
+            name: material.str("name")?.unwrap_or_default().to_string(),
+            ambient: diffuse,
+            diffuse,
+            specular,
+            emission,
+            shininess,
+            opacity: base_color[3],
+            metallic,
+            roughness,
+            double_sided: material
+                .get("doubleSided")
+                .and_then(Json::as_bool)
+                .unwrap_or(false),
+            diffuse_map: texture(pbr, "baseColorTexture")?,
+            specular_map: None,
+            // the emissive texture is multiplied with the factor, which defaults to black
+            emission_map: match emission == Vector3::zero() {
+                true => None,
+                false => texture(Some(material), "emissiveTexture")?,
+            },
+            normal_map: texture(Some(material), "normalTexture")?,
+            metallic_roughness_map: texture(pbr, "metallicRoughnessTexture")?,
+        })
+    }
+
+    /// a texture info object, only the first texture coordinate set is loaded so `texCoord`
+    /// is ignored
+    fn texture(&self, info: &Json) -> Result<TextureSource> {
+        let index = info.usize("index")?.context("no texture index")?;
+        let texture = self.item("textures", index)?;
+        (|| -> Result<_> {
+            let source = texture.usize("source")?.context("no image source")?;
+            let image = self.image(source)?;
+            let sampler = match texture.usize("sampler")? {
+                Some(sampler) => self.sampler(sampler)?,
+                None => SamplerDesc::default(),
+            };
+            Ok(TextureSource { image, sampler })
+        })()
+        .with_context(|| format!("texture {}", index))
+    }
+
+    fn image(&self, index: usize) -> Result<ImageSource> {
+        let image = self.item("images", index)?;
+        (|| -> Result<_> {
+            if let Some(uri) = image.str("uri")? {
+                return Ok(match uri.starts_with("data:") {
+                    true => ImageSource::Embedded(read_uri(uri, self.base_dir)?),
+                    false => ImageSource::File(self.base_dir.join(percent_decode(uri))),
+                });
+            }
+            let view = image.usize("bufferView")?.context("no uri or bufferView")?;
+            Ok(ImageSource::Embedded(self.buffer_view(view)?.0.to_vec()))
+        })()
+        .with_context(|| format!("image {}", index))
+    }
+
+    /// glTF samplers use the GL enums directly
+    fn sampler(&self, index: usize) -> Result<SamplerDesc> {
+        let sampler = self.item("samplers", index)?;
+        (|| -> Result<_> {
+            let mut desc = SamplerDesc::default();
+            let filters = [
+                (
+                    "magFilter",
+                    &mut desc.mag_filter,
+                    &[gl::NEAREST, gl::LINEAR][..],
+                ),
+                (
+                    "minFilter",
+                    &mut desc.min_filter,
+                    &[
+                        gl::NEAREST,
+                        gl::LINEAR,
+                        gl::NEAREST_MIPMAP_NEAREST,
+                        gl::LINEAR_MIPMAP_NEAREST,
+                        gl::NEAREST_MIPMAP_LINEAR,
+                        gl::LINEAR_MIPMAP_LINEAR,
+                    ][..],
+                ),
+                (
+                    "wrapS",
+                    &mut desc.wrap_s,
+                    &[gl::CLAMP_TO_EDGE, gl::MIRRORED_REPEAT, gl::REPEAT][..],
+                ),
+                (
+                    "wrapT",
+                    &mut desc.wrap_t,
+                    &[gl::CLAMP_TO_EDGE, gl::MIRRORED_REPEAT, gl::REPEAT][..],
+                ),
+            ];
+            for (key, target, allowed) in filters {
+                if let Some(value) = sampler.usize(key)? {
+                    if !allowed.contains(&(value as u32)) {
+                        bail!("invalid {} {}", key, value);
+                    }
+                    *target = value as u32;
+                }
+            }
+            Ok(desc)
+        })()
+        .with_context(|| format!("sampler {}", index))
+    }
+
+    fn node(&self, node: &Json, mesh_ranges: &[Range<usize>]) -> Result<NodeData> {
+        let meshes = match node.usize("mesh")? {
+            Some(mesh) => mesh_ranges
+                .get(mesh)
+                .with_context(|| format!("mesh {} does not exist", mesh))?
+                .clone()
+                .collect(),
+            None => Vec::new(),
+        };
+        let children = node
+            .array("children")?
+            .iter()
+            .map(|child| child.as_usize().context("invalid child index"))
+            .collect::<Result<Vec<_>>>()?;
+
+        let transform = match node.floats::<16>("matrix")? {
+            // column major, like cgmath
+            Some(values) => {
+                let column = |index: usize| {
+                    [
+                        values[index * 4],
+                        values[index * 4 + 1],
+                        values[index * 4 + 2],
+                        values[index * 4 + 3],
+                    ]
+                };
+                Matrix4::from([column(0), column(1), column(2), column(3)])
+            }
+            None => {
+                let [x, y, z, w] = node
+                    .floats::<4>("rotation")?
+                    .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+                Transform {
+                    translation: Vector3::from(
+                        node.floats::<3>("translation")?.unwrap_or([0.0; 3]),
+                    ),
+                    rotation: Quaternion::new(w, x, y, z),
+                    scale: Vector3::from(node.floats::<3>("scale")?.unwrap_or([1.0; 3])),
+                }
+                .to_matrix()
+            }
+        };
+        Ok(NodeData {
+            name: node.str("name")?.unwrap_or_default().to_string(),
+            transform,
+            meshes,
+            children,
+        })
+    }
+
+    /// root nodes of the default scene, all parentless nodes for files without scenes. also
+    /// makes sure the hierarchy is a forest so drawing it terminates
+    fn roots(&self, nodes: &[NodeData]) -> Result<Vec<usize>> {
+        let mut parents: Vec<Option<usize>> = vec![None; nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            for &child in &node.children {
+                let parent = parents
+                    .get_mut(child)
+                    .with_context(|| format!("node {}: child {} does not exist", index, child))?;
+                if parent.replace(index).is_some() {
+                    bail!("node {} has more than one parent", child);
+                }
+            }
+        }
+
+        // with a single parent per node, a cycle means walking up never reaches a root
+        for start in 0..nodes.len() {
+            let mut current = start;
+            for _ in 0..=nodes.len() {
+                match parents[current] {
+                    Some(parent) => current = parent,
+                    None => break,
+                }
+            }
+            if parents[current].is_some() {
+                bail!("node {} is part of a cycle", start);
+            }
+        }
+
+        let scenes = self.json.array("scenes")?;
+        if scenes.is_empty() {
+            return Ok((0..nodes.len())
+                .filter(|&index| parents[index].is_none())
+                .collect());
+        }
+        let scene = self.json.usize("scene")?.unwrap_or(0);
+        let roots = self
+            .item("scenes", scene)?
+            .array("nodes")?
+            .iter()
+            .map(|node| node.as_usize().context("invalid node index"))
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("scene {}", scene))?;
+        for &root in &roots {
+            match parents.get(root) {
+                None => bail!("scene {}: node {} does not exist", scene, root),
+                Some(Some(_)) => bail!("scene {}: node {} is not a root", scene, root),
+                Some(None) => {}
+            }
+        }
+        Ok(roots)
+    }
+}
+
+fn strip_to_list(indices: &[u32]) -> Vec<u32> {
+    indices
+        .windows(3)
+        .enumerate()
+        .flat_map(|(index, triangle)| match index % 2 {
+            // every other triangle is wound the other way round
+            0 => [triangle[0], triangle[1], triangle[2]],
+            _ => [triangle[1], triangle[0], triangle[2]],
+        })
+        .collect()
+}
+
+fn fan_to_list(indices: &[u32]) -> Vec<u32> {
+    match indices.split_first() {
+        Some((&center, rest)) => rest
+            .windows(2)
+            .flat_map(|edge| [center, edge[0], edge[1]])
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// normals averaged over the triangles sharing each vertex
+fn smooth_normals(positions: &[Vector3<f32>], indices: &[u32]) -> Vec<Vector3<f32>> {
+    let mut normals = vec![Vector3::zero(); positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|index| index as usize);
+        let normal = face_normal(positions[a], positions[b], positions[c]);
+        for index in [a, b, c] {
+            normals[index] += normal;
+        }
+    }
+    normals.into_iter().map(normalize_or_up).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// read accessor 0 of `accessor` over a buffer view of all of `buffer`
+    fn read_vectors<const N: usize>(accessor: &str, buffer: &[u8]) -> Result<Vec<[f32; N]>> {
+        let json = Json::parse(&format!(
+            r#"{{
+                "accessors": [{}],
+                "bufferViews": [{{ "buffer": 0, "byteLength": {} }}]
+            }}"#,
+            accessor,
+            buffer.len()
+        ))?;
+        let document = Document {
+            json: &json,
+            buffers: vec![buffer.to_vec()],
+            base_dir: Path::new(""),
+        };
+        document.read_vectors(0)
+    }
+
+    #[test]
+    fn small_vec4_components() {
+        let colors = read_vectors::<4>(
+            r#"{ "bufferView": 0, "componentType": 5121, "normalized": true,
+                 "type": "VEC4", "count": 2 }"#,
+            &[255, 0, 0, 255, 0, 51, 255, 0],
+        )
+        .unwrap();
+        assert_eq!(colors, [[1.0, 0.0, 0.0, 1.0], [0.0, 0.2, 1.0, 0.0]]);
+
+        let joints = read_vectors::<4>(
+            r#"{ "bufferView": 0, "componentType": 5123, "type": "VEC4", "count": 1 }"#,
+            &[1, 0, 2, 0, 3, 0, 0, 1],
+        )
+        .unwrap();
+        assert_eq!(joints, [[1.0, 2.0, 3.0, 256.0]]);
+    }
+
+    #[test]
+    fn padded_matrices_are_rejected() {
+        let error = read_vectors::<4>(
+            r#"{ "bufferView": 0, "componentType": 5121, "type": "MAT2", "count": 1 }"#,
+            &[0; 8],
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "accessor 0: type MAT2 with 1 byte components"
+        );
+    }
+
+    #[test]
+    fn overflowing_accessor_size() {
+        let error = read_vectors::<4>(
+            r#"{ "bufferView": 0, "componentType": 5126, "type": "VEC4",
+                 "count": 4611686018427387904 }"#,
+            &[0; 16],
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "accessor 0: 4611686018427387904 elements with a stride of 16 overflow"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::{bail, Context, Result};
+
+/// a parsed JSON value, objects keep their key order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// nesting deeper than this is rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+#[allow(dead_code)]
+impl Json {
+    pub fn parse(source: &str) -> Result<Json> {
+        let mut parser = Parser {
+            bytes: source.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.position != parser.bytes.len() {
+            bail!("trailing characters at offset {}", parser.position);
+        }
+        Ok(value)
+    }
+
+    /// the member `key` of an object, `None` for other values and missing keys
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// non-negative integral numbers only
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|value| *value >= 0.0 && value.fract() == 0.0 && *value <= usize::MAX as f64)
+            .map(|value| value as usize)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// the elements of the array member `key`, empty when it is missing
+    pub fn array(&self, key: &str) -> Result<&[Json]> {
+        match self.get(key) {
+            None => Ok(&[]),
+            Some(value) => value
+                .as_array()
+                .with_context(|| format!("{} is not an array", key)),
+        }
+    }
+
+    /// the index or count member `key`
+    pub fn usize(&self, key: &str) -> Result<Option<usize>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_usize()
+                    .with_context(|| format!("{} is not a non-negative integer", key))
+            })
+            .transpose()
+    }
+
+    /// the number member `key`
+    pub fn f32(&self, key: &str) -> Result<Option<f32>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_f64()
+                    .map(|value| value as f32)
+                    .with_context(|| format!("{} is not a number", key))
+            })
+            .transpose()
+    }
+
+    /// the string member `key`
+    pub fn str(&self, key: &str) -> Result<Option<&str>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_str()
+                    .with_context(|| format!("{} is not a string", key))
+            })
+            .transpose()
+    }
+
+    /// the array member `key` of exactly `N` numbers
+    pub fn floats<const N: usize>(&self, key: &str) -> Result<Option<[f32; N]>> {
+        let Some(value) = self.get(key) else {
+            return Ok(None);
+        };
+        let mut result = [0.0; N];
+        match value.as_array() {
+            Some(values) if values.len() == N => {
+                for (target, value) in result.iter_mut().zip(values) {
+                    *target = value
+                        .as_f64()
+                        .with_context(|| format!("{} has a non-number element", key))?
+                        as f32;
+                }
+                Ok(Some(result))
+            }
+            _ => bail!("{} is not an array of {} numbers", key, N),
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            bail!("expected '{}' at offset {}", byte as char, self.position);
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, text: &str, value: Json) -> Result<Json> {
+        if !self.bytes[self.position..].starts_with(text.as_bytes()) {
+            bail!("invalid literal at offset {}", self.position);
+        }
+        self.position += text.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json> {
+        if depth > MAX_DEPTH {
+            bail!(
+                "nesting deeper than {} at offset {}",
+                MAX_DEPTH,
+                self.position
+            );
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(byte) => bail!("unexpected '{}' at offset {}", byte as char, self.position),
+            None => bail!("unexpected end of input"),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                bail!("expected a key at offset {}", self.position);
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => bail!("expected ',' or '}}' at offset {}", self.position),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => bail!("expected ',' or ']' at offset {}", self.position),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        // the source is a str and the scanned bytes are ascii, so this can't fail
+        let text = std::str::from_utf8(&self.bytes[start..self.position])?;
+        let value = text
+            .parse::<f64>()
+            .with_context(|| format!("invalid number {:?} at offset {}", text, start))?;
+        Ok(Json::Number(value))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let start = self.position;
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                bail!("unterminated string starting at offset {}", start);
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.peek().context("unterminated escape")?;
+                    self.position += 1;
+                    let character = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => bail!("invalid escape at offset {}", self.position - 1),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+                }
+                0x00..=0x1f => bail!("control character in string at offset {}", self.position),
+                _ => bytes.push(byte),
+            }
+        }
+        // only whole utf-8 sequences of the source and encoded chars were copied
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    /// the part after `\u`, joining surrogate pairs
+    fn unicode_escape(&mut self) -> Result<char> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.bytes[self.position..].starts_with(b"\\u") {
+                bail!("unpaired surrogate at offset {}", self.position);
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                bail!("invalid low surrogate at offset {}", self.position);
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code)
+            .with_context(|| format!("invalid code point at offset {}", self.position))
+    }
+
+    fn hex4(&mut self) -> Result<u32> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .with_context(|| format!("invalid \\u escape at offset {}", self.position))?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
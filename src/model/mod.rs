@@ -0,0 +1,568 @@
+// model loading: files are parsed into cpu side `ModelData`, which `Model` uploads to the gpu
+pub mod gltf;
+pub mod json;
+pub mod mtl;
+pub mod obj;
+
+use std::{
+    ffi::CString,
+    mem,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use cgmath::{vec3, InnerSpace, Matrix4, SquareMatrix, Vector2, Vector3, Zero};
+use gl::types::*;
+
+use crate::{
+    lighting::{MapMaterial, UniformStruct},
+    sampler::SamplerDesc,
+    shaders::Shader,
+    texture::{Texture2D, TextureOptions},
+    transform::normal_matrix,
+    vertex_layout::{AttributeType, InstanceBuffer, VertexLayout},
+};
+
+/// interleaved vertex of every loaded mesh: position at location 0, normal at 1 and texture
+/// coordinates at 2, the same layout the lighting tutorials use
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex {
+    pub position: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub tex_coords: Vector2<f32>,
+}
+
+impl Vertex {
+    pub fn layout() -> VertexLayout {
+        VertexLayout::per_vertex(mem::size_of::<Vertex>())
+            .with(0, AttributeType::Vec3, mem::offset_of!(Vertex, position))
+            .with(1, AttributeType::Vec3, mem::offset_of!(Vertex, normal))
+            .with(2, AttributeType::Vec2, mem::offset_of!(Vertex, tex_coords))
+    }
+}
+
+/// an indexed triangle list with a single material
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub name: String,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// index into `ModelData::materials`
+    pub material: Option<usize>,
+}
+
+/// where the pixels of a texture come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// already resolved, can be opened as-is
+    File(PathBuf),
+    /// an encoded image (png, jpeg, ...) stored inside the model file
+    Embedded(Vec<u8>),
+}
+
+/// a texture map of a material
+#[derive(Debug, Clone)]
+pub struct TextureSource {
+    pub image: ImageSource,
+    pub sampler: SamplerDesc,
+}
+
+#[allow(dead_code)]
+impl TextureSource {
+    /// a file sampled with the default sampler
+    pub fn file<P: Into<PathBuf>>(path: P) -> TextureSource {
+        TextureSource {
+            image: ImageSource::File(path.into()),
+            sampler: SamplerDesc::default(),
+        }
+    }
+
+    pub fn load(&self) -> Result<Texture2D> {
+        let options = TextureOptions {
+            sampler: self.sampler,
+            generate_mipmaps: self.sampler.uses_mipmaps(),
+            ..Default::default()
+        };
+        match &self.image {
+            ImageSource::File(path) => Texture2D::from_path(path, options)
+                .with_context(|| format!("loading {}", path.display())),
+            ImageSource::Embedded(bytes) => {
+                let image = image::load_from_memory(bytes).context("decoding embedded image")?;
+                Texture2D::from_image(image, options)
+            }
+        }
+    }
+}
+
+/// surface description of a model file. models without lighting information of their own
+/// (glTF is physically based) get an approximation of the phong values
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct MaterialData {
+    pub name: String,
+    pub ambient: Vector3<f32>,
+    pub diffuse: Vector3<f32>,
+    pub specular: Vector3<f32>,
+    pub emission: Vector3<f32>,
+    pub shininess: f32,
+    /// 1 is fully opaque
+    pub opacity: f32,
+    /// physically based parameters, 0 is a dielectric and 1 a metal
+    pub metallic: f32,
+    pub roughness: f32,
+    /// back faces have to be drawn too
+    pub double_sided: bool,
+    pub diffuse_map: Option<TextureSource>,
+    pub specular_map: Option<TextureSource>,
+    pub emission_map: Option<TextureSource>,
+    pub normal_map: Option<TextureSource>,
+    /// roughness in green and metalness in blue, as in glTF
+    pub metallic_roughness_map: Option<TextureSource>,
+}
+
+impl Default for MaterialData {
+    fn default() -> Self {
+        // what a material gets when the file leaves the values out
+        Self {
+            name: String::new(),
+            ambient: vec3(0.2, 0.2, 0.2),
+            diffuse: vec3(0.8, 0.8, 0.8),
+            specular: vec3(0.5, 0.5, 0.5),
+            emission: Vector3::zero(),
+            shininess: 32.0,
+            opacity: 1.0,
+            metallic: 0.0,
+            roughness: 1.0,
+            double_sided: false,
+            diffuse_map: None,
+            specular_map: None,
+            emission_map: None,
+            normal_map: None,
+            metallic_roughness_map: None,
+        }
+    }
+}
+
+/// a node of the scene hierarchy
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeData {
+    pub name: String,
+    /// relative to the parent node
+    pub transform: Matrix4<f32>,
+    /// indices into `ModelData::meshes`, a glTF mesh with several primitives has several
+    pub meshes: Vec<usize>,
+    /// indices into `ModelData::nodes`
+    pub children: Vec<usize>,
+}
+
+/// everything read from a model file
+#[derive(Debug, Clone, Default)]
+pub struct ModelData {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<MaterialData>,
+    /// every node of the file, each one is reachable from at most one parent
+    pub nodes: Vec<NodeData>,
+    /// the nodes drawn directly, with the model matrix of the whole model
+    pub roots: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl ModelData {
+    /// load a model, the format is picked from the extension
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ModelData> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("obj") => obj::load(path),
+            Some("gltf" | "glb") => gltf::load(path),
+            _ => bail!("unsupported model format: {}", path.display()),
+        }
+    }
+
+    /// a single root node drawing every mesh, for formats without a hierarchy
+    pub fn flat(meshes: Vec<MeshData>, materials: Vec<MaterialData>) -> ModelData {
+        let root = NodeData {
+            name: String::new(),
+            transform: Matrix4::identity(),
+            meshes: (0..meshes.len()).collect(),
+            children: Vec::new(),
+        };
+        ModelData {
+            meshes,
+            materials,
+            nodes: vec![root],
+            roots: vec![0],
+        }
+    }
+
+    /// every mesh instance with the model matrix of its node
+    pub fn world_meshes(&self) -> Vec<(usize, Matrix4<f32>)> {
+        let mut meshes = Vec::new();
+        let mut stack: Vec<(usize, Matrix4<f32>)> = self
+            .roots
+            .iter()
+            .map(|&root| (root, Matrix4::identity()))
+            .collect();
+        while let Some((index, parent)) = stack.pop() {
+            let node = &self.nodes[index];
+            let world = parent * node.transform;
+            meshes.extend(node.meshes.iter().map(|&mesh| (mesh, world)));
+            stack.extend(node.children.iter().map(|&child| (child, world)));
+        }
+        meshes
+    }
+
+    /// corners of the axis aligned box around all meshes as placed by the node hierarchy,
+    /// `None` for an empty model
+    pub fn bounds(&self) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        let instances = self.world_meshes();
+        let mut positions = instances.iter().flat_map(|(mesh, world)| {
+            self.meshes[*mesh]
+                .vertices
+                .iter()
+                .map(move |vertex| (world * vertex.position.extend(1.0)).truncate())
+        });
+        let first = positions.next()?;
+        Some(positions.fold((first, first), |(min, max), p| {
+            (
+                vec3(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                vec3(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        }))
+    }
+}
+
+/// a mesh uploaded to a vertex array with an element buffer
+pub struct Mesh {
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+    index_count: GLsizei,
+    pub material: Option<usize>,
+}
+
+#[allow(dead_code)]
+impl Mesh {
+    pub fn new(data: &MeshData) -> Mesh {
+        let mut mesh = Mesh {
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            index_count: data.indices.len() as GLsizei,
+            material: data.material,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut mesh.vao);
+            gl::GenBuffers(1, &mut mesh.vbo);
+            gl::GenBuffers(1, &mut mesh.ebo);
+
+            gl::BindVertexArray(mesh.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(data.vertices.as_slice()) as GLsizeiptr,
+                data.vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                mem::size_of_val(data.indices.as_slice()) as GLsizeiptr,
+                data.indices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            Vertex::layout().apply();
+            gl::BindVertexArray(0);
+        }
+        mesh
+    }
+
+    pub unsafe fn draw(&self) {
+        gl::BindVertexArray(self.vao);
+        gl::DrawElements(
+            gl::TRIANGLES,
+            self.index_count,
+            gl::UNSIGNED_INT,
+            std::ptr::null(),
+        );
+        gl::BindVertexArray(0);
+    }
+
+    /// read the attributes of `instances` next to the vertex attributes, their locations must
+    /// not overlap 0 to 2
+    pub unsafe fn add_instance_buffer(&self, instances: &InstanceBuffer) {
+        instances.attach(self.vao);
+    }
+
+    pub unsafe fn draw_instanced(&self, instance_count: usize) {
+        gl::BindVertexArray(self.vao);
+        gl::DrawElementsInstanced(
+            gl::TRIANGLES,
+            self.index_count,
+            gl::UNSIGNED_INT,
+            std::ptr::null(),
+            instance_count as GLsizei,
+        );
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ebo);
+        }
+    }
+}
+
+/// meshes and their texture mapped materials, ready to draw
+pub struct Model {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<MapMaterial>,
+    /// used for meshes without a material
+    pub default_material: MapMaterial,
+    pub nodes: Vec<NodeData>,
+    pub roots: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl Model {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Model> {
+        Self::from_data(&ModelData::open(path)?)
+    }
+
+    /// upload the meshes and load the texture maps, colours stand in for missing maps
+    pub fn from_data(data: &ModelData) -> Result<Model> {
+        let materials = data
+            .materials
+            .iter()
+            .map(|material| {
+                map_material(material).with_context(|| format!("material {}", material.name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Model {
+            meshes: data.meshes.iter().map(Mesh::new).collect(),
+            materials,
+            default_material: map_material(&MaterialData::default())?,
+            nodes: data.nodes.clone(),
+            roots: data.roots.clone(),
+        })
+    }
+
+    /// draw the node hierarchy under `model`. every node sets the `model` and `normalMatrix`
+    /// uniforms and its meshes upload their material to the `MapMaterial` uniform
+    /// `material_name`. the shader has to be in use
+    pub unsafe fn draw(
+        &self,
+        shader: &Shader,
+        material_name: &str,
+        model: &Matrix4<f32>,
+    ) -> Result<()> {
+        self.draw_nodes(shader, material_name, model, |mesh| mesh.draw())
+    }
+
+    /// attach `instances` to every mesh, see `Mesh::add_instance_buffer`
+    pub unsafe fn add_instance_buffer(&self, instances: &InstanceBuffer) {
+        for mesh in &self.meshes {
+            mesh.add_instance_buffer(instances);
+        }
+    }
+
+    /// like `draw`, but every mesh is drawn `instance_count` times in one call. the instance
+    /// attributes are applied on top of the node transforms by the shader
+    pub unsafe fn draw_instanced(
+        &self,
+        shader: &Shader,
+        material_name: &str,
+        model: &Matrix4<f32>,
+        instance_count: usize,
+    ) -> Result<()> {
+        self.draw_nodes(shader, material_name, model, |mesh| {
+            mesh.draw_instanced(instance_count)
+        })
+    }
+
+    unsafe fn draw_nodes(
+        &self,
+        shader: &Shader,
+        material_name: &str,
+        model: &Matrix4<f32>,
+        draw: impl Fn(&Mesh),
+    ) -> Result<()> {
+        let model_name = CString::new("model")?;
+        let normal_matrix_name = CString::new("normalMatrix")?;
+        let mut stack: Vec<(usize, Matrix4<f32>)> =
+            self.roots.iter().map(|&root| (root, *model)).collect();
+        while let Some((index, parent)) = stack.pop() {
+            let node = &self.nodes[index];
+            let world = parent * node.transform;
+            shader.set_mat4(&model_name, &world);
+            shader.set_mat3(&normal_matrix_name, &normal_matrix(&world));
+            for mesh in node.meshes.iter().map(|&mesh| &self.meshes[mesh]) {
+                let material = mesh
+                    .material
+                    .and_then(|index| self.materials.get(index))
+                    .unwrap_or(&self.default_material);
+                material.upload(shader, material_name)?;
+                draw(mesh);
+            }
+            stack.extend(node.children.iter().map(|&child| (child, world)));
+        }
+        Ok(())
+    }
+}
+
+fn map_material(material: &MaterialData) -> Result<MapMaterial> {
+    let map = |source: &Option<TextureSource>, color: Vector3<f32>| match source {
+        Some(source) => source.load(),
+        None => Texture2D::solid(color_bytes(color)),
+    };
+    Ok(MapMaterial {
+        diffuse: map(&material.diffuse_map, material.diffuse)?,
+        specular: map(&material.specular_map, material.specular)?,
+        emission: map(&material.emission_map, material.emission)?,
+        shininess: material.shininess.max(1.0),
+    })
+}
+
+fn color_bytes(color: Vector3<f32>) -> [u8; 4] {
+    let byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [byte(color.x), byte(color.y), byte(color.z), 255]
+}
+
+/// source lines with comments stripped and `\` continuations joined, paired with the 1-based
+/// line number they start on. empty lines are skipped
+pub(crate) fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let (number, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        match line.trim_end().strip_suffix('\\') {
+            Some(continued) => {
+                text.push_str(continued);
+                text.push(' ');
+                pending = Some((number, text));
+            }
+            None => {
+                text.push_str(line);
+                if !text.trim().is_empty() {
+                    lines.push((number, text.trim().to_string()));
+                }
+            }
+        }
+    }
+    if let Some((number, text)) = pending {
+        if !text.trim().is_empty() {
+            lines.push((number, text.trim().to_string()));
+        }
+    }
+    lines
+}
+
+/// parse every field as a float
+pub(crate) fn parse_floats(fields: &[&str]) -> Result<Vec<f32>> {
+    fields
+        .iter()
+        .map(|field| {
+            field
+                .parse::<f32>()
+                .with_context(|| format!("invalid number {:?}", field))
+        })
+        .collect()
+}
+
+/// an rgb colour, a single value is used for all three channels
+pub(crate) fn parse_color(fields: &[&str]) -> Result<Vector3<f32>> {
+    match parse_floats(fields)?.as_slice() {
+        [value] => Ok(vec3(*value, *value, *value)),
+        [r, g, b, ..] => Ok(vec3(*r, *g, *b)),
+        _ => bail!("expected a colour, got {:?}", fields),
+    }
+}
+
+/// split a polygon into triangles by ear clipping, returns indices into `points` in the winding
+/// of the polygon. concave polygons work as long as they are roughly planar, degenerate ones
+/// fall back to a fan
+pub(crate) fn triangulate(points: &[Vector3<f32>]) -> Vec<[usize; 3]> {
+    let count = points.len();
+    if count < 3 {
+        return Vec::new();
+    }
+
+    // newell's method, robust for concave and slightly non-planar polygons
+    let mut normal = Vector3::zero();
+    for (index, a) in points.iter().enumerate() {
+        let b = points[(index + 1) % count];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+
+    let mut remaining: Vec<usize> = (0..count).collect();
+    let mut triangles = Vec::with_capacity(count - 2);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let corner = |index: usize| {
+            (
+                remaining[(index + len - 1) % len],
+                remaining[index],
+                remaining[(index + 1) % len],
+            )
+        };
+        let ear = (0..len).find(|&index| {
+            let (prev, current, next) = corner(index);
+            let (a, b, c) = (points[prev], points[current], points[next]);
+            // convex corner, with no other corner of the polygon inside the triangle
+            (b - a).cross(c - b).dot(normal) > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&other| other != prev && other != current && other != next)
+                    .all(|&other| !inside_triangle(points[other], a, b, c, normal))
+        });
+        match ear {
+            Some(index) => {
+                let (prev, current, next) = corner(index);
+                triangles.push([prev, current, next]);
+                remaining.remove(index);
+            }
+            None => break,
+        }
+    }
+    for index in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[index], remaining[index + 1]]);
+    }
+    triangles
+}
+
+fn inside_triangle(
+    point: Vector3<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+    normal: Vector3<f32>,
+) -> bool {
+    let side = |from: Vector3<f32>, to: Vector3<f32>| (to - from).cross(point - from).dot(normal);
+    side(a, b) >= 0.0 && side(b, c) >= 0.0 && side(c, a) >= 0.0
+}
+
+/// normal of a triangle with a length of twice its area, so bigger faces weigh more when summed
+pub(crate) fn face_normal(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Vector3<f32> {
+    (b - a).cross(c - a)
+}
+
+/// normalize a summed normal, falling back to +y for degenerate geometry
+pub(crate) fn normalize_or_up(normal: Vector3<f32>) -> Vector3<f32> {
+    if normal.magnitude2() > 0.0 {
+        normal.normalize()
+    } else {
+        vec3(0.0, 1.0, 0.0)
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use super::{logical_lines, parse_color, parse_floats, MaterialData, TextureSource};
+
+/// texture options that come before the file name, with the most values they take. options
+/// with several values may leave trailing ones out
+const MAP_OPTIONS: [(&str, usize); 12] = [
+    ("-blendu", 1),
+    ("-blendv", 1),
+    ("-bm", 1),
+    ("-boost", 1),
+    ("-cc", 1),
+    ("-clamp", 1),
+    ("-imfchan", 1),
+    ("-mm", 2),
+    ("-o", 3),
+    ("-s", 3),
+    ("-t", 3),
+    ("-texres", 1),
+];
+
+/// parse a material library, texture paths are resolved relative to `base_dir`, the directory
+/// of the `.mtl` file
+pub fn parse(source: &str, base_dir: &Path) -> Result<Vec<MaterialData>> {
+    let mut materials: Vec<MaterialData> = Vec::new();
+    for (number, line) in logical_lines(source) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (keyword, args) = (fields[0], &fields[1..]);
+        let result = (|| {
+            if keyword == "newmtl" {
+                materials.push(MaterialData {
+                    name: args.join(" "),
+                    ..Default::default()
+                });
+                return Ok(());
+            }
+            let Some(material) = materials.last_mut() else {
+                bail!("{} before the first newmtl", keyword);
+            };
+            match keyword {
+                "Ka" => material.ambient = parse_color(args)?,
+                "Kd" => material.diffuse = parse_color(args)?,
+                "Ks" => material.specular = parse_color(args)?,
+                "Ke" => material.emission = parse_color(args)?,
+                "Ns" => material.shininess = single(args)?,
+                "d" => material.opacity = single(args)?,
+                // transparency, the inverse of d
+                "Tr" => material.opacity = 1.0 - single(args)?,
+                "map_Kd" => material.diffuse_map = Some(map_texture(args, base_dir)?),
+                "map_Ks" => material.specular_map = Some(map_texture(args, base_dir)?),
+                "map_Ke" => material.emission_map = Some(map_texture(args, base_dir)?),
+                "map_Bump" | "map_bump" | "bump" | "norm" => {
+                    material.normal_map = Some(map_texture(args, base_dir)?)
+                }
+                // illumination models, reflection maps and the like are not supported
+                _ => {}
+            }
+            Ok(())
+        })();
+        result.with_context(|| format!("line {}", number))?;
+    }
+    Ok(materials)
+}
+
+fn single(args: &[&str]) -> Result<f32> {
+    match parse_floats(args)?.as_slice() {
+        [value] => Ok(*value),
+        _ => bail!("expected a single number, got {:?}", args),
+    }
+}
+
+/// skip the options of a texture statement and resolve the file name. exporters on windows
+/// write backslashes, which are separators there as well
+fn map_texture(args: &[&str], base_dir: &Path) -> Result<TextureSource> {
+    let mut rest = args;
+    while let Some((option, values)) = rest.split_first() {
+        match MAP_OPTIONS.iter().find(|(name, _)| name == option) {
+            Some((_, 1)) => rest = values.get(1..).unwrap_or_default(),
+            Some((_, count)) => {
+                let numbers = values
+                    .iter()
+                    .take(*count)
+                    .take_while(|value| value.parse::<f32>().is_ok())
+                    .count();
+                rest = &values[numbers..];
+            }
+            None => break,
+        }
+    }
+    if rest.is_empty() {
+        bail!("texture statement without a file name");
+    }
+    Ok(TextureSource::file(
+        base_dir.join(rest.join(" ").replace('\\', "/")),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::vec3;
+
+    use super::*;
+    use crate::model::ImageSource;
+
+    #[test]
+    fn colors_and_maps() {
+        let source = "
+            # a comment
+            newmtl crate
+            Kd 0.8 0.6 0.4
+            Ns 32
+            Tr 0.25
+            map_Kd -s 2 2 -bm 0.5 textures\\crate diffuse.png
+            newmtl glass
+            d 0.5
+        ";
+        let materials = parse(source, Path::new("models/crate")).unwrap();
+        assert_eq!(materials.len(), 2);
+        let crate_material = &materials[0];
+        assert_eq!(crate_material.name, "crate");
+        assert_eq!(crate_material.diffuse, vec3(0.8, 0.6, 0.4));
+        assert_eq!(crate_material.shininess, 32.0);
+        assert_eq!(crate_material.opacity, 0.75);
+        assert_eq!(
+            crate_material.diffuse_map.as_ref().unwrap().image,
+            ImageSource::File("models/crate/textures/crate diffuse.png".into())
+        );
+        assert_eq!(materials[1].opacity, 0.5);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| format!("{:#}", parse(source, Path::new("")).unwrap_err());
+        assert!(error("Kd 1 1 1").contains("Kd before the first newmtl"));
+        assert!(error("newmtl a\nNs 1 2").contains("line 2"));
+        assert!(error("newmtl a\nmap_Kd -bm 0.5").contains("without a file name"));
+    }
+}
@@ -0,0 +1,424 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use cgmath::{vec2, vec3, Vector2, Vector3, Zero};
+
+use super::{
+    face_normal, logical_lines, mtl, normalize_or_up, parse_floats, triangulate, MaterialData,
+    MeshData, ModelData, Vertex,
+};
+
+/// a parsed `.obj` file, materials are still referenced by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjFile {
+    pub meshes: Vec<ObjMesh>,
+    /// `mtllib` file names, relative to the `.obj` file
+    pub material_libs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjMesh {
+    pub data: MeshData,
+    /// the `usemtl` name
+    pub material: Option<String>,
+}
+
+/// one face corner, 0-based indices into the position, texture coordinate and normal lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+    position: usize,
+    tex_coords: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// faces collected for the mesh currently being read
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: Option<String>,
+    triangles: Vec<[Corner; 3]>,
+}
+
+impl MeshBuilder {
+    /// turn the faces into an indexed mesh, corners sharing all indices become one vertex.
+    /// corners without a normal get a smooth one, averaged over the faces around the position
+    fn build(
+        self,
+        positions: &[Vector3<f32>],
+        tex_coords: &[Vector2<f32>],
+        normals: &[Vector3<f32>],
+    ) -> ObjMesh {
+        let mut smooth_normals: HashMap<usize, Vector3<f32>> = HashMap::new();
+        for triangle in &self.triangles {
+            if triangle.iter().all(|corner| corner.normal.is_some()) {
+                continue;
+            }
+            let [a, b, c] = triangle.map(|corner| positions[corner.position]);
+            let normal = face_normal(a, b, c);
+            for corner in triangle {
+                *smooth_normals
+                    .entry(corner.position)
+                    .or_insert_with(Vector3::zero) += normal;
+            }
+        }
+
+        let mut data = MeshData {
+            name: self.name,
+            ..Default::default()
+        };
+        let mut indices: HashMap<Corner, u32> = HashMap::new();
+        for corner in self.triangles.iter().flatten() {
+            let index = *indices.entry(*corner).or_insert_with(|| {
+                data.vertices.push(Vertex {
+                    position: positions[corner.position],
+                    normal: match corner.normal {
+                        Some(normal) => normals[normal],
+                        None => normalize_or_up(smooth_normals[&corner.position]),
+                    },
+                    tex_coords: corner
+                        .tex_coords
+                        .map_or(vec2(0.0, 0.0), |index| tex_coords[index]),
+                });
+                data.vertices.len() as u32 - 1
+            });
+            data.indices.push(index);
+        }
+        ObjMesh {
+            data,
+            material: self.material,
+        }
+    }
+}
+
+/// parse the contents of an `.obj` file. `o`, `g` and `usemtl` start a new mesh, polygons are
+/// triangulated and lines and points are skipped
+pub fn parse(source: &str) -> Result<ObjFile> {
+    let mut positions: Vec<Vector3<f32>> = Vec::new();
+    let mut tex_coords: Vec<Vector2<f32>> = Vec::new();
+    let mut normals: Vec<Vector3<f32>> = Vec::new();
+    let mut file = ObjFile::default();
+    let mut current = MeshBuilder::default();
+    let mut finished: Vec<MeshBuilder> = Vec::new();
+
+    for (number, line) in logical_lines(source) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (keyword, args) = (fields[0], &fields[1..]);
+        let result = (|| {
+            match keyword {
+                "v" => positions.push(vector3(args)?),
+                "vn" => normals.push(vector3(args)?),
+                "vt" => {
+                    let values = parse_floats(args)?;
+                    match values.as_slice() {
+                        [u] => tex_coords.push(vec2(*u, 0.0)),
+                        [u, v, ..] => tex_coords.push(vec2(*u, *v)),
+                        _ => bail!("texture coordinate without values"),
+                    }
+                }
+                "f" => {
+                    if args.len() < 3 {
+                        bail!("face with {} corners", args.len());
+                    }
+                    let counts = (positions.len(), tex_coords.len(), normals.len());
+                    let corners = args
+                        .iter()
+                        .map(|field| corner(field, counts))
+                        .collect::<Result<Vec<_>>>()?;
+                    let points: Vec<Vector3<f32>> = corners
+                        .iter()
+                        .map(|corner| positions[corner.position])
+                        .collect();
+                    for [a, b, c] in triangulate(&points) {
+                        current.triangles.push([corners[a], corners[b], corners[c]]);
+                    }
+                }
+                "o" | "g" => {
+                    let material = current.material.clone();
+                    finished.push(std::mem::take(&mut current));
+                    current.name = args.join(" ");
+                    current.material = material;
+                }
+                "usemtl" => {
+                    let name = current.name.clone();
+                    finished.push(std::mem::take(&mut current));
+                    current.name = name;
+                    current.material = Some(args.join(" "));
+                }
+                "mtllib" => file
+                    .material_libs
+                    .extend(args.iter().map(|name| name.to_string())),
+                // smoothing groups, lines, points and free-form geometry are not supported
+                _ => {}
+            }
+            Ok(())
+        })();
+        result.with_context(|| format!("line {}", number))?;
+    }
+    finished.push(current);
+
+    file.meshes = finished
+        .into_iter()
+        .filter(|builder| !builder.triangles.is_empty())
+        .map(|builder| builder.build(&positions, &tex_coords, &normals))
+        .collect();
+    Ok(file)
+}
+
+/// load an `.obj` file with its material libraries. `mtllib` paths are relative to the `.obj`
+/// file and texture paths relative to the `.mtl` file that names them
+pub fn load<P: AsRef<Path>>(path: P) -> Result<ModelData> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let file = parse(&source).with_context(|| format!("parsing {}", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut materials = Vec::new();
+    for library in &file.material_libs {
+        let library_path = directory.join(library.replace('\\', "/"));
+        let source = fs::read_to_string(&library_path)
+            .with_context(|| format!("reading {}", library_path.display()))?;
+        let library_directory = library_path.parent().unwrap_or(Path::new(""));
+        materials.extend(
+            mtl::parse(&source, library_directory)
+                .with_context(|| format!("parsing {}", library_path.display()))?,
+        );
+    }
+    file.into_model(materials)
+}
+
+impl ObjFile {
+    /// resolve the `usemtl` names against the materials of the libraries
+    pub fn into_model(self, materials: Vec<MaterialData>) -> Result<ModelData> {
+        let meshes = self
+            .meshes
+            .into_iter()
+            .map(|mesh| {
+                let mut data = mesh.data;
+                if let Some(name) = mesh.material {
+                    let index = materials.iter().position(|material| material.name == name);
+                    match index {
+                        Some(index) => data.material = Some(index),
+                        None => bail!("mesh {:?} uses unknown material {:?}", data.name, name),
+                    }
+                }
+                Ok(data)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ModelData::flat(meshes, materials))
+    }
+}
+
+fn vector3(args: &[&str]) -> Result<Vector3<f32>> {
+    // positions may carry a w or a vertex colour after xyz
+    match parse_floats(args)?.as_slice() {
+        [x, y, z, ..] => Ok(vec3(*x, *y, *z)),
+        _ => bail!("expected three numbers, got {:?}", args),
+    }
+}
+
+/// parse `v`, `v/vt`, `v//vn` or `v/vt/vn`. `counts` are the lengths of the position, texture
+/// coordinate and normal lists so far, which negative indices count back from
+fn corner(field: &str, counts: (usize, usize, usize)) -> Result<Corner> {
+    let mut parts = field.split('/');
+    let position = parts.next().unwrap_or_default();
+    let tex_coords = parts.next().filter(|part| !part.is_empty());
+    let normal = parts.next().filter(|part| !part.is_empty());
+    if parts.next().is_some() {
+        bail!("invalid face corner {:?}", field);
+    }
+    Ok(Corner {
+        position: resolve_index(position, counts.0)?,
+        tex_coords: tex_coords
+            .map(|index| resolve_index(index, counts.1))
+            .transpose()?,
+        normal: normal
+            .map(|index| resolve_index(index, counts.2))
+            .transpose()?,
+    })
+}
+
+/// turn a 1-based or negative (relative to the end) index into a 0-based one
+fn resolve_index(field: &str, count: usize) -> Result<usize> {
+    let index: i64 = field
+        .parse()
+        .with_context(|| format!("invalid index {:?}", field))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => count as i64 + index,
+        0 => bail!("indices start at 1"),
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        bail!("index {} out of range, {} elements so far", index, count);
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{assert_abs_diff_eq, InnerSpace};
+
+    use super::*;
+    use crate::model::ImageSource;
+
+    /// the single mesh of `source`
+    fn mesh(source: &str) -> MeshData {
+        let mut file = parse(source).unwrap();
+        assert_eq!(file.meshes.len(), 1);
+        file.meshes.remove(0).data
+    }
+
+    fn error(source: &str) -> String {
+        format!("{:#}", parse(source).unwrap_err())
+    }
+
+    /// the triangles of `mesh` as positions
+    fn triangles(mesh: &MeshData) -> Vec<[Vector3<f32>; 3]> {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].position))
+            .collect()
+    }
+
+    const SQUARE: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 0
+        vt 1 1
+        vt 0 1
+        vn 0 0 1
+    ";
+
+    #[test]
+    fn negative_indices() {
+        let absolute = mesh(&format!("{}f 1/1/1 2/2/1 3/3/1", SQUARE));
+        let relative = mesh(&format!("{}f -4/-4/-1 -3/-3/-1 -2/-2/-1", SQUARE));
+        assert_eq!(absolute, relative);
+        // relative to what has been read so far, not to the whole file
+        let early = mesh("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5");
+        assert_eq!(early.vertices[2].position, vec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn quad_is_split_into_two_triangles() {
+        let quad = mesh(&format!("{}f 1/1/1 2/2/1 3/3/1 4/4/1", SQUARE));
+        assert_eq!(quad.indices.len(), 6);
+        for [a, b, c] in triangles(&quad) {
+            assert!((b - a).cross(c - a).z > 0.0);
+        }
+    }
+
+    #[test]
+    fn concave_polygon_is_ear_clipped() {
+        // an arrow head, a fan from the first corner would cover the notch
+        let arrow = mesh("v 0 0 0\nv 4 0 0\nv 4 4 0\nv 2 1 0\nv 0 4 0\nf 1 2 3 4 5");
+        assert_eq!(arrow.indices.len(), 9);
+        let mut area = 0.0;
+        for [a, b, c] in triangles(&arrow) {
+            let doubled = (b - a).cross(c - a).z;
+            assert!(doubled > 0.0, "{:?} is wound the wrong way", [a, b, c]);
+            area += doubled / 2.0;
+        }
+        assert_abs_diff_eq!(area, 10.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn missing_normals_are_smoothed() {
+        // two faces folded along the x axis, one facing +z and one facing +y
+        let fold = mesh("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 2 3\nf 1 4 2");
+        let normal_at = |position: Vector3<f32>| {
+            fold.vertices
+                .iter()
+                .find(|vertex| vertex.position == position)
+                .unwrap()
+                .normal
+        };
+        let shared = vec3(0.0, 1.0, 1.0).normalize();
+        assert_abs_diff_eq!(normal_at(vec3(0.0, 0.0, 0.0)), shared, epsilon = 1e-6);
+        assert_abs_diff_eq!(normal_at(vec3(1.0, 0.0, 0.0)), shared, epsilon = 1e-6);
+        assert_eq!(normal_at(vec3(0.0, 1.0, 0.0)), vec3(0.0, 0.0, 1.0));
+        assert_eq!(normal_at(vec3(0.0, 0.0, 1.0)), vec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn shared_corners_are_deduplicated() {
+        let square = mesh(&format!(
+            "{}f 1/1/1 2/2/1 3/3/1\nf 1/1/1 3/3/1 4/4/1",
+            SQUARE
+        ));
+        assert_eq!(square.vertices.len(), 4);
+        assert_eq!(square.indices, [0, 1, 2, 0, 2, 3]);
+
+        // the same position with another texture coordinate is another vertex
+        let seam = mesh(&format!(
+            "{}f 1/1/1 2/2/1 3/3/1\nf 1/2/1 3/3/1 4/4/1",
+            SQUARE
+        ));
+        assert_eq!(seam.vertices.len(), 5);
+    }
+
+    #[test]
+    fn malformed_indices() {
+        let cases = [
+            ("f 1 2 9", "index 9 out of range, 4 elements so far"),
+            ("f 1 2 -5", "index -5 out of range"),
+            ("f 0 1 2", "indices start at 1"),
+            ("f 1/x 2 3", "invalid index \"x\""),
+            ("f 1/1/1/1 2 3", "invalid face corner"),
+            ("f 1//2 2 3", "index 2 out of range, 1 elements so far"),
+            ("f 1 2", "face with 2 corners"),
+        ];
+        for (face, message) in cases {
+            let error = error(&format!("{}{}", SQUARE, face));
+            assert!(error.contains("line 11"), "{}", error);
+            assert!(error.contains(message), "{:?} gave {}", face, error);
+        }
+    }
+
+    #[test]
+    fn materials_split_meshes() {
+        let file = parse(&format!(
+            "mtllib a.mtl b.mtl\n{}o box\nusemtl wood\nf 1 2 3\nusemtl metal\nf 1 3 4",
+            SQUARE
+        ))
+        .unwrap();
+        assert_eq!(file.material_libs, ["a.mtl", "b.mtl"]);
+        let meshes: Vec<_> = file
+            .meshes
+            .iter()
+            .map(|mesh| (mesh.data.name.as_str(), mesh.material.as_deref()))
+            .collect();
+        assert_eq!(meshes, [("box", Some("wood")), ("box", Some("metal"))]);
+    }
+
+    #[test]
+    fn texture_paths_are_relative_to_the_library() {
+        let directory = std::env::temp_dir().join(format!("learn-gl-obj-{}", std::process::id()));
+        fs::create_dir_all(directory.join("materials")).unwrap();
+        fs::write(
+            directory.join("model.obj"),
+            format!(
+                "mtllib materials/model.mtl\n{}usemtl crate\nf 1 2 3",
+                SQUARE
+            ),
+        )
+        .unwrap();
+        fs::write(
+            directory.join("materials/model.mtl"),
+            "newmtl crate\nmap_Kd textures\\crate.png\n",
+        )
+        .unwrap();
+        let model = load(directory.join("model.obj"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let model = model.unwrap();
+        assert_eq!(model.meshes[0].material, Some(0));
+        let diffuse = model.materials[0].diffuse_map.as_ref().unwrap();
+        assert_eq!(
+            diffuse.image,
+            ImageSource::File(directory.join("materials/textures/crate.png"))
+        );
+    }
+}
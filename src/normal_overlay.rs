@@ -0,0 +1,93 @@
+use std::{ffi::CString, fmt};
+
+use anyhow::Result;
+use cgmath::{vec3, Matrix4, Vector3};
+
+use crate::{model::Model, shaders::Shader};
+
+/// which normals `NormalOverlay` draws
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalSource {
+    /// the interpolated normals stored in the vertices
+    #[default]
+    Vertex,
+    /// the geometric normal of every triangle, from its winding
+    Face,
+}
+
+#[allow(dead_code)]
+impl NormalSource {
+    pub fn toggled(self) -> NormalSource {
+        match self {
+            NormalSource::Vertex => NormalSource::Face,
+            NormalSource::Face => NormalSource::Vertex,
+        }
+    }
+}
+
+impl fmt::Display for NormalSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalSource::Vertex => write!(f, "vertex normals"),
+            NormalSource::Face => write!(f, "face normals"),
+        }
+    }
+}
+
+/// draws the normals of triangle meshes as lines, on top of the regular draw of the same
+/// geometry. a geometry shader turns every triangle into lines, so any vertex array with
+/// positions at location 0 and normals at location 1 works
+pub struct NormalOverlay {
+    pub source: NormalSource,
+    /// line length in world units
+    pub length: f32,
+    pub color: Vector3<f32>,
+    shader: Shader,
+}
+
+#[allow(dead_code)]
+impl NormalOverlay {
+    pub fn new() -> Result<NormalOverlay> {
+        let shader = Shader::with_geometry_shader(
+            "src/shaders/normal_overlay.vs",
+            "src/shaders/normal_overlay.fs",
+            "src/shaders/normal_overlay.gs",
+        )?;
+        Ok(NormalOverlay {
+            source: NormalSource::default(),
+            length: 0.2,
+            color: vec3(1.0, 1.0, 0.0),
+            shader,
+        })
+    }
+
+    /// the overlay's program, for drawing other vertex arrays between `begin` and the next
+    /// program change. they have to set the `model` and `normalMatrix` uniforms
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// use the overlay's program with the camera and the settings
+    pub unsafe fn begin(&self, view: &Matrix4<f32>, projection: &Matrix4<f32>) -> Result<()> {
+        self.shader.use_program();
+        self.shader.set_mat4(&CString::new("view")?, view);
+        self.shader
+            .set_mat4(&CString::new("projection")?, projection);
+        self.shader
+            .set_float(&CString::new("magnitude")?, self.length);
+        self.shader.set_bool(
+            &CString::new("faceNormals")?,
+            self.source == NormalSource::Face,
+        );
+        self.shader
+            .set_vector3(&CString::new("color")?, &self.color);
+        Ok(())
+    }
+
+    /// the normals of every mesh of `model`, placed like `Model::draw` places them. call
+    /// `begin` first
+    pub unsafe fn draw_model(&self, model: &Model, transform: &Matrix4<f32>) -> Result<()> {
+        // the material uniforms don't exist in the overlay shader and are ignored
+        model.draw(&self.shader, "material", transform)
+    }
+}
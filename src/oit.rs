@@ -0,0 +1,145 @@
+use std::ffi::CString;
+
+use anyhow::Result;
+use gl::types::*;
+
+use crate::{
+    framebuffer::{self, AttachmentStorage, Framebuffer, FramebufferDesc},
+    render_state::{BlendState, DepthState},
+    shaders::Shader,
+};
+
+/// how the transparent draws of a scene are combined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransparencyMode {
+    /// blended back to front per object, exact for separate objects but wrong where they
+    /// intersect or overlap themselves
+    #[default]
+    Sorted,
+    /// order independent approximation, see `WeightedBlendedOit`
+    WeightedBlended,
+}
+
+#[allow(dead_code)]
+impl TransparencyMode {
+    pub fn toggled(self) -> TransparencyMode {
+        match self {
+            TransparencyMode::Sorted => TransparencyMode::WeightedBlended,
+            TransparencyMode::WeightedBlended => TransparencyMode::Sorted,
+        }
+    }
+}
+
+/// weighted blended order independent transparency (McGuire and Bavoil 2013).
+///
+/// transparent surfaces are drawn in any order into two targets: the sum of their premultiplied
+/// colours and alphas scaled by a depth based weight, and the revealage, the product of
+/// `1 - alpha`. a full screen pass then divides out the weights and blends the average colour
+/// over the opaque scene. the transparent shaders write `vec4(color.rgb * a * w, a)` to
+/// location 0 and `a * w` to location 1.
+pub struct WeightedBlendedOit {
+    /// RGBA16F accumulation (see above), R16F summed weights and a depth buffer filled with
+    /// the depth of the opaque scene so hidden transparent fragments are rejected
+    targets: Framebuffer,
+    /// empty, the composite pass builds its triangle from the vertex id
+    vao: GLuint,
+    composite: Shader,
+}
+
+#[allow(dead_code)]
+impl WeightedBlendedOit {
+    pub fn new(width: i32, height: i32) -> Result<WeightedBlendedOit> {
+        let composite = Shader::new("src/shaders/fullscreen.vs", "src/shaders/oit_composite.fs")?;
+        // same depth format as the default framebuffer, otherwise the depth blit fails
+        let targets = FramebufferDesc::new()
+            .with_color(gl::RGBA16F)
+            .with_color(gl::R16F)
+            .with_depth_stencil(gl::DEPTH24_STENCIL8, AttachmentStorage::Renderbuffer)
+            .build(width, height)?;
+        let mut oit = WeightedBlendedOit {
+            targets,
+            vao: 0,
+            composite,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut oit.vao);
+
+            oit.composite.use_program();
+            oit.composite.set_int(&CString::new("accumulation")?, 0);
+            oit.composite.set_int(&CString::new("weight")?, 1);
+        }
+        Ok(oit)
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        self.targets.size()
+    }
+
+    /// reallocate the targets, they have to match the framebuffer the scene is drawn to.
+    /// does nothing when the size is unchanged, so it can be called every frame
+    pub unsafe fn resize(&mut self, width: i32, height: i32) -> Result<()> {
+        self.targets.resize(width, height)
+    }
+
+    /// blending for the accumulation pass. both targets add up their rgb, the alpha of the
+    /// first target is multiplied by `1 - alpha` which turns it into the revealage
+    pub fn accumulation_blend() -> BlendState {
+        BlendState {
+            enabled: true,
+            src_rgb: gl::ONE,
+            dst_rgb: gl::ONE,
+            src_alpha: gl::ZERO,
+            dst_alpha: gl::ONE_MINUS_SRC_ALPHA,
+            ..BlendState::default()
+        }
+    }
+
+    /// start the transparent pass after the opaque scene was drawn to `scene_framebuffer`.
+    /// binds the accumulation targets with the depth of the scene and sets depth and blend
+    /// state, the transparent draws follow
+    pub unsafe fn begin(&self, scene_framebuffer: GLuint) {
+        let size = self.targets.size();
+        framebuffer::blit(
+            scene_framebuffer,
+            size,
+            self.targets.id(),
+            size,
+            gl::DEPTH_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.targets.id());
+        gl::ClearBufferfv(gl::COLOR, 0, [0.0, 0.0, 0.0, 1.0].as_ptr());
+        gl::ClearBufferfv(gl::COLOR, 1, [0.0; 4].as_ptr());
+
+        // hidden behind opaque geometry is rejected, but no transparent surface hides another
+        DepthState::read_only().apply();
+        Self::accumulation_blend().apply();
+    }
+
+    /// blend the resolved transparent layer over `scene_framebuffer`, which is left bound.
+    /// depth and blend state are reset to their defaults afterwards
+    pub unsafe fn composite(&self, scene_framebuffer: GLuint) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, scene_framebuffer);
+        DepthState::disabled().apply();
+        BlendState::alpha().apply();
+
+        self.composite.use_program();
+        self.targets.bind_color_texture(0, 0);
+        self.targets.bind_color_texture(1, 1);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        gl::BindVertexArray(0);
+        gl::ActiveTexture(gl::TEXTURE0);
+
+        DepthState::default().apply();
+        BlendState::disabled().apply();
+    }
+}
+
+impl Drop for WeightedBlendedOit {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::{ffi::CString, fmt, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use gl::types::*;
+use image::{Rgba, Rgba32FImage};
+
+use crate::{
+    framebuffer::{AttachmentStorage, Framebuffer, FramebufferDesc},
+    render_state::{BlendState, DepthState},
+    shaders::Shader,
+};
+
+/// a 3x3 convolution, the rows go from top to bottom as seen on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Kernel {
+    pub weights: [f32; 9],
+}
+
+#[allow(dead_code)]
+impl Kernel {
+    pub const SHARPEN: Kernel = Kernel {
+        weights: [-1.0, -1.0, -1.0, -1.0, 9.0, -1.0, -1.0, -1.0, -1.0],
+    };
+
+    pub const BLUR: Kernel = Kernel {
+        weights: [
+            1.0 / 16.0,
+            2.0 / 16.0,
+            1.0 / 16.0,
+            2.0 / 16.0,
+            4.0 / 16.0,
+            2.0 / 16.0,
+            1.0 / 16.0,
+            2.0 / 16.0,
+            1.0 / 16.0,
+        ],
+    };
+
+    pub const EDGE_DETECTION: Kernel = Kernel {
+        weights: [1.0, 1.0, 1.0, 1.0, -8.0, 1.0, 1.0, 1.0, 1.0],
+    };
+
+    /// the weights scaled to add up to 1, kernels that sum to 0 are returned unchanged
+    pub fn normalized(self) -> Kernel {
+        let sum: f32 = self.weights.iter().sum();
+        if sum.abs() < f32::EPSILON {
+            return self;
+        }
+        Kernel {
+            weights: self.weights.map(|weight| weight / sum),
+        }
+    }
+}
+
+/// one full screen pass of a `PostProcess` chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Invert,
+    Grayscale,
+    Kernel(Kernel),
+}
+
+/// the effect names `Effect::from_str` understands besides `kernel=...`
+pub const EFFECT_NAMES: [&str; 5] = ["invert", "grayscale", "sharpen", "blur", "edge"];
+
+impl FromStr for Effect {
+    type Err = anyhow::Error;
+
+    /// an effect name or `kernel=w0,w1,...,w8` with an optional `/divisor`, for example
+    /// `kernel=1,2,1,2,4,2,1,2,1/16`
+    fn from_str(text: &str) -> Result<Effect> {
+        let effect = match text {
+            "invert" | "inversion" => Effect::Invert,
+            "grayscale" | "greyscale" => Effect::Grayscale,
+            "sharpen" => Effect::Kernel(Kernel::SHARPEN),
+            "blur" => Effect::Kernel(Kernel::BLUR),
+            "edge" | "edge_detection" => Effect::Kernel(Kernel::EDGE_DETECTION),
+            _ => match text.strip_prefix("kernel=") {
+                Some(kernel) => {
+                    Effect::Kernel(parse_kernel(kernel).with_context(|| format!("in {:?}", text))?)
+                }
+                None => bail!(
+                    "unknown effect {:?}, expected one of {} or kernel=w0,...,w8",
+                    text,
+                    EFFECT_NAMES.join(", ")
+                ),
+            },
+        };
+        Ok(effect)
+    }
+}
+
+fn parse_kernel(text: &str) -> Result<Kernel> {
+    let (weights, divisor) = match text.split_once('/') {
+        Some((weights, divisor)) => {
+            let divisor: f32 = divisor
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid divisor {:?}", divisor))?;
+            if divisor == 0.0 {
+                bail!("the divisor can't be 0");
+            }
+            (weights, divisor)
+        }
+        None => (text, 1.0),
+    };
+    let values = weights
+        .split(',')
+        .map(|weight| {
+            weight
+                .trim()
+                .parse::<f32>()
+                .with_context(|| format!("invalid weight {:?}", weight))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Ok(weights) = <[f32; 9]>::try_from(values.as_slice()) else {
+        bail!("a kernel needs 9 weights, got {}", values.len());
+    };
+    Ok(Kernel {
+        weights: weights.map(|weight| weight / divisor),
+    })
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Effect::Invert => write!(f, "invert"),
+            Effect::Grayscale => write!(f, "grayscale"),
+            Effect::Kernel(kernel) if *kernel == Kernel::SHARPEN => write!(f, "sharpen"),
+            Effect::Kernel(kernel) if *kernel == Kernel::BLUR => write!(f, "blur"),
+            Effect::Kernel(kernel) if *kernel == Kernel::EDGE_DETECTION => write!(f, "edge"),
+            Effect::Kernel(kernel) => {
+                let weights: Vec<String> = kernel.weights.iter().map(f32::to_string).collect();
+                write!(f, "kernel={}", weights.join(","))
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Effect {
+    /// the value of the `effect` uniform in post_process.fs
+    fn shader_mode(&self) -> i32 {
+        match self {
+            Effect::Invert => 1,
+            Effect::Grayscale => 2,
+            Effect::Kernel(_) => 3,
+        }
+    }
+
+    /// what the shader computes, on the cpu. edges are clamped like the CLAMP_TO_EDGE
+    /// targets and the result is clamped to [0, 1] like the 8-bit targets store it. the image
+    /// rows go from top to bottom, alpha becomes 1
+    pub fn apply_cpu(&self, image: &Rgba32FImage) -> Rgba32FImage {
+        let (width, height) = image.dimensions();
+        Rgba32FImage::from_fn(width, height, |x, y| {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+            let color = match self {
+                Effect::Invert => [1.0 - r, 1.0 - g, 1.0 - b],
+                Effect::Grayscale => {
+                    let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                    [luma; 3]
+                }
+                Effect::Kernel(kernel) => {
+                    let mut sum = [0.0; 3];
+                    for (index, weight) in kernel.weights.iter().enumerate() {
+                        let sample_x = (x as i64 + index as i64 % 3 - 1).clamp(0, width as i64 - 1);
+                        let sample_y =
+                            (y as i64 + index as i64 / 3 - 1).clamp(0, height as i64 - 1);
+                        let sample = image.get_pixel(sample_x as u32, sample_y as u32).0;
+                        for channel in 0..3 {
+                            sum[channel] += weight * sample[channel];
+                        }
+                    }
+                    sum
+                }
+            };
+            let [r, g, b] = color.map(|value| value.clamp(0.0, 1.0));
+            Rgba([r, g, b, 1.0])
+        })
+    }
+}
+
+/// run `effects` one after the other on the cpu, the reference for a whole chain
+#[allow(dead_code)]
+pub fn apply_chain_cpu(effects: &[Effect], image: &Rgba32FImage) -> Rgba32FImage {
+    effects
+        .iter()
+        .fold(image.clone(), |image, effect| effect.apply_cpu(&image))
+}
+
+/// an effect of the chain that can be switched off without losing its place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainEntry {
+    pub effect: Effect,
+    pub enabled: bool,
+}
+
+/// the scene is drawn into an off-screen target, then every enabled effect runs as a full
+/// screen pass in order, ping-ponging between two more targets. the last pass draws into the
+/// window
+pub struct PostProcess {
+    pub chain: Vec<ChainEntry>,
+    scene: Framebuffer,
+    ping_pong: [Framebuffer; 2],
+    shader: Shader,
+    /// empty, the passes build their triangle from the vertex id
+    vao: GLuint,
+}
+
+#[allow(dead_code)]
+impl PostProcess {
+    pub fn new(chain: Vec<ChainEntry>, width: i32, height: i32) -> Result<PostProcess> {
+        let shader = Shader::new("src/shaders/fullscreen.vs", "src/shaders/post_process.fs")?;
+        let scene = FramebufferDesc::new()
+            .with_color(gl::RGBA8)
+            .with_depth_stencil(gl::DEPTH24_STENCIL8, AttachmentStorage::Renderbuffer)
+            .build(width, height)?;
+        let ping_pong = [
+            FramebufferDesc::new()
+                .with_color(gl::RGBA8)
+                .build(width, height)?,
+            FramebufferDesc::new()
+                .with_color(gl::RGBA8)
+                .build(width, height)?,
+        ];
+        let mut post_process = PostProcess {
+            chain,
+            scene,
+            ping_pong,
+            shader,
+            vao: 0,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut post_process.vao);
+            post_process.shader.use_program();
+            post_process
+                .shader
+                .set_int(&CString::new("screenTexture")?, 0);
+        }
+        Ok(post_process)
+    }
+
+    /// a chain of `effects`, all enabled
+    pub fn from_effects(effects: &[Effect], width: i32, height: i32) -> Result<PostProcess> {
+        let chain = effects
+            .iter()
+            .map(|effect| ChainEntry {
+                effect: *effect,
+                enabled: true,
+            })
+            .collect();
+        Self::new(chain, width, height)
+    }
+
+    /// switch entry `index` on or off, returns false when there is no such entry
+    pub fn toggle(&mut self, index: usize) -> bool {
+        match self.chain.get_mut(index) {
+            Some(entry) => {
+                entry.enabled = !entry.enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// the enabled effects in order
+    pub fn enabled_effects(&self) -> Vec<Effect> {
+        self.chain
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.effect)
+            .collect()
+    }
+
+    /// the targets have to match the window, does nothing when the size is unchanged
+    pub unsafe fn resize(&mut self, width: i32, height: i32) -> Result<()> {
+        self.scene.resize(width, height)?;
+        for target in &mut self.ping_pong {
+            target.resize(width, height)?;
+        }
+        Ok(())
+    }
+
+    /// direct the scene rendering into the off-screen target
+    pub unsafe fn begin(&self) {
+        self.scene.bind();
+    }
+
+    /// run the enabled effects on the scene and draw the result into the window. depth and
+    /// blend state are reset to their defaults afterwards
+    pub unsafe fn finish(&self) -> Result<()> {
+        DepthState::disabled().apply();
+        BlendState::disabled().apply();
+        self.shader.use_program();
+        gl::BindVertexArray(self.vao);
+
+        let effects = self.enabled_effects();
+        let mut source = &self.scene;
+        let pass_count = effects.len().max(1);
+        for pass in 0..pass_count {
+            if pass + 1 == pass_count {
+                Framebuffer::bind_default(self.scene.size());
+            } else {
+                self.ping_pong[pass % 2].bind();
+            }
+            match effects.get(pass) {
+                Some(effect) => {
+                    self.shader
+                        .set_int(&CString::new("effect")?, effect.shader_mode());
+                    if let Effect::Kernel(kernel) = effect {
+                        self.shader
+                            .set_floats(&CString::new("kernel")?, &kernel.weights);
+                    }
+                }
+                // nothing enabled, copy the scene
+                None => self.shader.set_int(&CString::new("effect")?, 0),
+            }
+            source.bind_color_texture(0, 0);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            source = &self.ping_pong[pass % 2];
+        }
+
+        gl::BindVertexArray(0);
+        DepthState::default().apply();
+        Ok(())
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a `size`x`size` black image with a white texel at `bright`
+    fn single_texel(size: u32, bright: (u32, u32)) -> Rgba32FImage {
+        Rgba32FImage::from_fn(size, size, |x, y| {
+            if (x, y) == bright {
+                Rgba([1.0, 1.0, 1.0, 1.0])
+            } else {
+                Rgba([0.0, 0.0, 0.0, 1.0])
+            }
+        })
+    }
+
+    /// the red channel row by row
+    fn red(image: &Rgba32FImage) -> Vec<Vec<f32>> {
+        image
+            .rows()
+            .map(|row| row.map(|pixel| pixel.0[0]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn invert_and_grayscale() {
+        let image = Rgba32FImage::from_pixel(1, 1, Rgba([0.25, 0.5, 1.0, 0.5]));
+        assert_eq!(
+            Effect::Invert.apply_cpu(&image).get_pixel(0, 0).0,
+            [0.75, 0.5, 0.0, 1.0]
+        );
+        let red = Rgba32FImage::from_pixel(1, 1, Rgba([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(
+            Effect::Grayscale.apply_cpu(&red).get_pixel(0, 0).0,
+            [0.2126, 0.2126, 0.2126, 1.0]
+        );
+        assert_eq!(
+            apply_chain_cpu(&[Effect::Grayscale, Effect::Invert], &red)
+                .get_pixel(0, 0)
+                .0,
+            [0.7874, 0.7874, 0.7874, 1.0]
+        );
+        assert_eq!(apply_chain_cpu(&[], &image), image);
+    }
+
+    #[test]
+    fn blur_spreads_a_texel() {
+        let blurred = Effect::Kernel(Kernel::BLUR).apply_cpu(&single_texel(3, (1, 1)));
+        assert_eq!(
+            red(&blurred),
+            [
+                [0.0625, 0.125, 0.0625],
+                [0.125, 0.25, 0.125],
+                [0.0625, 0.125, 0.0625]
+            ]
+        );
+        // the clamped edge repeats the corner texel into the 4 taps outside the image
+        let corner = Effect::Kernel(Kernel::BLUR).apply_cpu(&single_texel(2, (0, 0)));
+        assert_eq!(red(&corner), [[0.5625, 0.1875], [0.1875, 0.0625]]);
+    }
+
+    #[test]
+    fn edge_detection_of_a_texel() {
+        let edges = Effect::Kernel(Kernel::EDGE_DETECTION).apply_cpu(&single_texel(3, (1, 1)));
+        // the center is 1 - 8, clamped to 0
+        assert_eq!(
+            red(&edges),
+            [[1.0, 1.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0]]
+        );
+        // the corner sees itself in 3 taps besides the center, 3 - 8 is clamped as well
+        let corner = Effect::Kernel(Kernel::EDGE_DETECTION).apply_cpu(&single_texel(2, (0, 0)));
+        assert_eq!(red(&corner), [[0.0, 1.0], [1.0, 1.0]]);
+    }
+
+    #[test]
+    fn kernel_rows_go_from_top_to_bottom() {
+        // only the top left weight, every texel takes the value above and left of it
+        let mut weights = [0.0; 9];
+        weights[0] = 1.0;
+        let shifted = Effect::Kernel(Kernel { weights }).apply_cpu(&single_texel(3, (1, 1)));
+        assert_eq!(
+            red(&shifted),
+            [[0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn parse_kernels() {
+        assert_eq!(
+            "kernel=1,2,1,2,4,2,1,2,1/16".parse::<Effect>().unwrap(),
+            Effect::Kernel(Kernel::BLUR)
+        );
+        assert_eq!(
+            "kernel=1, 1,1,1,-8,1,1,1,1".parse::<Effect>().unwrap(),
+            Effect::Kernel(Kernel::EDGE_DETECTION)
+        );
+        assert_eq!("greyscale".parse::<Effect>().unwrap(), Effect::Grayscale);
+
+        let error = |text: &str| format!("{:#}", text.parse::<Effect>().unwrap_err());
+        assert_eq!(
+            error("kernel=1,2,3"),
+            "in \"kernel=1,2,3\": a kernel needs 9 weights, got 3"
+        );
+        assert!(error("kernel=1,1,1,1,1,1,1,1,1,1").contains("got 10"));
+        assert!(error("kernel=1,1,1,1,1,1,1,1,1/0").contains("the divisor can't be 0"));
+        assert!(error("kernel=1,1,1,1,1,1,1,1,1/x").contains("invalid divisor \"x\""));
+        assert!(error("kernel=1,1,1,1,a,1,1,1,1").contains("invalid weight \"a\""));
+        assert!(error("sepia").starts_with("unknown effect \"sepia\""));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let custom = Effect::Kernel(Kernel {
+            weights: [0.0, -0.5, 0.0, 0.1, 1.25, 0.1, 0.0, -0.5, 0.0],
+        });
+        assert_eq!(custom.to_string(), "kernel=0,-0.5,0,0.1,1.25,0.1,0,-0.5,0");
+        let mut effects: Vec<Effect> = EFFECT_NAMES
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect();
+        effects.push(custom);
+        for effect in effects {
+            assert_eq!(effect.to_string().parse::<Effect>().unwrap(), effect);
+        }
+        for name in EFFECT_NAMES {
+            assert_eq!(name.parse::<Effect>().unwrap().to_string(), name);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use cgmath::{ortho, perspective, Matrix4, Rad};
+
+use crate::compressed::{gl_version, has_extension};
+
+/// how depth is mapped into the depth buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    /// OpenGL default: clip z in -1..1, near maps to 0, far to 1, tested with LESS
+    #[default]
+    Standard,
+    /// clip z in 0..1 with near at 1 and far at 0, tested with GREATER. floating point depth is
+    /// densest close to 0, so this spreads precision evenly over the distance. it needs
+    /// glClipControl and only pays off fully with a floating point depth buffer
+    ReversedZ,
+}
+
+#[allow(dead_code)]
+impl DepthMode {
+    /// glClipControl is core in 4.5 and otherwise needs ARB_clip_control
+    pub unsafe fn reversed_z_supported() -> bool {
+        gl_version() >= (4, 5) || has_extension("GL_ARB_clip_control")
+    }
+
+    /// value to clear the depth buffer to, the far plane
+    pub fn clear_depth(self) -> f64 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::ReversedZ => 0.0,
+        }
+    }
+
+    /// the other mode, used for toggling at runtime
+    pub fn toggled(self) -> DepthMode {
+        match self {
+            DepthMode::Standard => DepthMode::ReversedZ,
+            DepthMode::ReversedZ => DepthMode::Standard,
+        }
+    }
+
+    /// set clip control, depth clear value and depth function. projection matrices have to be
+    /// built with the same mode
+    pub unsafe fn apply(self) {
+        let clip_control = Self::reversed_z_supported();
+        match self {
+            DepthMode::Standard => {
+                if clip_control {
+                    gl::ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+                }
+                gl::DepthFunc(gl::LESS);
+            }
+            DepthMode::ReversedZ => {
+                gl::ClipControl(gl::LOWER_LEFT, gl::ZERO_TO_ONE);
+                gl::DepthFunc(gl::GREATER);
+            }
+        }
+        gl::ClearDepth(self.clear_depth());
+    }
+}
+
+/// right handed perspective projection for the given depth mode
+pub fn perspective_projection<A: Into<Rad<f32>>>(
+    fovy: A,
+    aspect: f32,
+    near: f32,
+    far: f32,
+    depth: DepthMode,
+) -> Matrix4<f32> {
+    let mut projection = perspective(fovy, aspect, near, far);
+    if depth == DepthMode::ReversedZ {
+        // z_ndc = (z * a + b) / -z, with -near -> 1 and -far -> 0
+        projection.z.z = near / (far - near);
+        projection.w.z = near * far / (far - near);
+    }
+    projection
+}
+
+/// right handed orthographic projection for the given depth mode
+pub fn orthographic_projection(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+    depth: DepthMode,
+) -> Matrix4<f32> {
+    let mut projection = ortho(left, right, bottom, top, near, far);
+    if depth == DepthMode::ReversedZ {
+        // z_ndc = z * a + b, with -near -> 1 and -far -> 0
+        projection.z.z = 1.0 / (far - near);
+        projection.w.z = far / (far - near);
+    }
+    projection
+}
+
+/// projection parameters that are kept around so the matrix can be rebuilt on resize
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective {
+        fovy: Rad<f32>,
+        near: f32,
+        far: f32,
+    },
+    /// `height` world units are visible vertically, the width follows the aspect ratio
+    Orthographic {
+        height: f32,
+        near: f32,
+        far: f32,
+    },
+}
+
+#[allow(dead_code)]
+impl Projection {
+    pub fn matrix(&self, aspect: f32, depth: DepthMode) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fovy, near, far } => {
+                perspective_projection(fovy, aspect, near, far, depth)
+            }
+            Projection::Orthographic { height, near, far } => {
+                let (half_width, half_height) = (height * aspect / 2.0, height / 2.0);
+                orthographic_projection(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    near,
+                    far,
+                    depth,
+                )
+            }
+        }
+    }
+}
+
+/// width / height of a framebuffer, a minimized window reports 0x0
+pub fn aspect_ratio(width: i32, height: i32) -> f32 {
+    if width <= 0 || height <= 0 {
+        1.0
+    } else {
+        width as f32 / height as f32
+    }
+}
@@ -0,0 +1,269 @@
+use gl::types::*;
+
+/// depth test configuration, see glDepthFunc and glDepthMask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthState {
+    pub test: bool,
+    /// whether passing fragments write their depth
+    pub write: bool,
+    pub func: GLenum,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            test: true,
+            write: true,
+            func: gl::LESS,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl DepthState {
+    /// every fragment passes and the depth buffer is left alone
+    pub fn disabled() -> Self {
+        Self {
+            test: false,
+            write: false,
+            ..Self::default()
+        }
+    }
+
+    /// test against the depth buffer without writing to it
+    pub fn read_only() -> Self {
+        Self {
+            write: false,
+            ..Self::default()
+        }
+    }
+
+    pub unsafe fn apply(&self) {
+        if self.test {
+            gl::Enable(gl::DEPTH_TEST);
+        } else {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        gl::DepthFunc(self.func);
+        gl::DepthMask(if self.write { gl::TRUE } else { gl::FALSE });
+    }
+}
+
+/// stencil test configuration, see glStencilFunc, glStencilOp and glStencilMask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+    pub test: bool,
+    pub func: GLenum,
+    /// the value fragments are compared against and REPLACE writes
+    pub reference: GLint,
+    /// bits of the reference and the stored value that take part in the test
+    pub read_mask: GLuint,
+    /// bits the stencil ops are allowed to change
+    pub write_mask: GLuint,
+    /// op when the stencil test fails
+    pub stencil_fail: GLenum,
+    /// op when the stencil test passes but the depth test fails
+    pub depth_fail: GLenum,
+    /// op when both tests pass
+    pub depth_pass: GLenum,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            test: false,
+            func: gl::ALWAYS,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            stencil_fail: gl::KEEP,
+            depth_fail: gl::KEEP,
+            depth_pass: gl::KEEP,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl StencilState {
+    /// the test is off, the buffer is neither read nor written
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// draw everything and mark the visible fragments with `reference`
+    pub fn write(reference: GLint) -> Self {
+        Self {
+            test: true,
+            reference,
+            depth_pass: gl::REPLACE,
+            ..Self::default()
+        }
+    }
+
+    /// draw everything and leave the buffer untouched
+    pub fn keep() -> Self {
+        Self {
+            test: true,
+            write_mask: 0x00,
+            ..Self::default()
+        }
+    }
+
+    /// only draw where the stored value equals `reference`, without changing it
+    pub fn equal(reference: GLint) -> Self {
+        Self {
+            func: gl::EQUAL,
+            reference,
+            ..Self::keep()
+        }
+    }
+
+    /// only draw where the stored value differs from `reference`, without changing it
+    pub fn not_equal(reference: GLint) -> Self {
+        Self {
+            func: gl::NOTEQUAL,
+            reference,
+            ..Self::keep()
+        }
+    }
+
+    pub unsafe fn apply(&self) {
+        if self.test {
+            gl::Enable(gl::STENCIL_TEST);
+        } else {
+            gl::Disable(gl::STENCIL_TEST);
+        }
+        gl::StencilFunc(self.func, self.reference, self.read_mask);
+        gl::StencilOp(self.stencil_fail, self.depth_fail, self.depth_pass);
+        // glClear honours the write mask too
+        gl::StencilMask(self.write_mask);
+    }
+}
+
+/// blending configuration, see glBlendEquationSeparate, glBlendFuncSeparate and glBlendColor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+    pub enabled: bool,
+    pub equation_rgb: GLenum,
+    pub equation_alpha: GLenum,
+    pub src_rgb: GLenum,
+    pub dst_rgb: GLenum,
+    pub src_alpha: GLenum,
+    pub dst_alpha: GLenum,
+    /// used by the CONSTANT_COLOR and CONSTANT_ALPHA factors
+    pub constant: [f32; 4],
+}
+
+impl Default for BlendState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            equation_rgb: gl::FUNC_ADD,
+            equation_alpha: gl::FUNC_ADD,
+            src_rgb: gl::ONE,
+            dst_rgb: gl::ZERO,
+            src_alpha: gl::ONE,
+            dst_alpha: gl::ZERO,
+            constant: [0.0; 4],
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl BlendState {
+    /// fragments replace what is in the framebuffer
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// classic transparency for straight alpha. the destination alpha accumulates coverage
+    /// instead of being multiplied by the source alpha twice
+    pub fn alpha() -> Self {
+        Self {
+            enabled: true,
+            src_rgb: gl::SRC_ALPHA,
+            dst_rgb: gl::ONE_MINUS_SRC_ALPHA,
+            src_alpha: gl::ONE,
+            dst_alpha: gl::ONE_MINUS_SRC_ALPHA,
+            ..Self::default()
+        }
+    }
+
+    /// transparency for colours that are already multiplied by their alpha
+    pub fn premultiplied() -> Self {
+        Self {
+            src_rgb: gl::ONE,
+            ..Self::alpha()
+        }
+    }
+
+    /// light-like blending, the order of the draws doesn't matter
+    pub fn additive() -> Self {
+        Self {
+            enabled: true,
+            src_rgb: gl::SRC_ALPHA,
+            dst_rgb: gl::ONE,
+            src_alpha: gl::ONE,
+            dst_alpha: gl::ONE,
+            ..Self::default()
+        }
+    }
+
+    pub unsafe fn apply(&self) {
+        if self.enabled {
+            gl::Enable(gl::BLEND);
+        } else {
+            gl::Disable(gl::BLEND);
+        }
+        gl::BlendEquationSeparate(self.equation_rgb, self.equation_alpha);
+        gl::BlendFuncSeparate(self.src_rgb, self.dst_rgb, self.src_alpha, self.dst_alpha);
+        let [r, g, b, a] = self.constant;
+        gl::BlendColor(r, g, b, a);
+    }
+}
+
+/// face culling configuration, see glCullFace and glFrontFace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CullState {
+    pub enabled: bool,
+    /// the faces that are discarded
+    pub face: GLenum,
+    /// the winding of front faces
+    pub front_face: GLenum,
+}
+
+impl Default for CullState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            face: gl::BACK,
+            front_face: gl::CCW,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl CullState {
+    /// both sides are drawn
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// discard faces wound clockwise on screen
+    pub fn back() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    pub unsafe fn apply(&self) {
+        if self.enabled {
+            gl::Enable(gl::CULL_FACE);
+        } else {
+            gl::Disable(gl::CULL_FACE);
+        }
+        gl::CullFace(self.face);
+        gl::FrontFace(self.front_face);
+    }
+}
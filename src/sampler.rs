@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use gl::types::*;
+
+// GL_EXT_texture_filter_anisotropic, core only since 4.6 so the gl crate lacks them
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+/// everything that controls how a texture is sampled
+#[derive(Debug, Clone, Copy)]
+pub struct SamplerDesc {
+    pub wrap_s: GLenum,
+    pub wrap_t: GLenum,
+    pub wrap_r: GLenum,
+    pub min_filter: GLenum,
+    pub mag_filter: GLenum,
+    /// 1.0 disables anisotropic filtering, clamped to what the driver supports
+    pub max_anisotropy: f32,
+    /// only used by the CLAMP_TO_BORDER wrap mode
+    pub border_color: [f32; 4],
+    pub lod_bias: f32,
+    /// depth comparison function, `None` samples the raw depth value
+    pub compare_func: Option<GLenum>,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            wrap_s: gl::REPEAT,
+            wrap_t: gl::REPEAT,
+            wrap_r: gl::REPEAT,
+            min_filter: gl::LINEAR_MIPMAP_LINEAR,
+            mag_filter: gl::LINEAR,
+            max_anisotropy: 1.0,
+            border_color: [0.0; 4],
+            lod_bias: 0.0,
+            compare_func: None,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl SamplerDesc {
+    /// linear filtering with mipmaps and repeat wrapping, same as `default()`
+    pub fn linear_repeat() -> Self {
+        Self::default()
+    }
+
+    /// linear filtering without mipmaps, clamped at the edges (render targets, skyboxes)
+    pub fn linear_clamp() -> Self {
+        Self {
+            wrap_s: gl::CLAMP_TO_EDGE,
+            wrap_t: gl::CLAMP_TO_EDGE,
+            wrap_r: gl::CLAMP_TO_EDGE,
+            min_filter: gl::LINEAR,
+            ..Self::default()
+        }
+    }
+
+    /// blocky pixel art look
+    pub fn nearest_repeat() -> Self {
+        Self {
+            min_filter: gl::NEAREST,
+            mag_filter: gl::NEAREST,
+            ..Self::default()
+        }
+    }
+
+    /// depth texture sampled with hardware comparison, everything outside the map is lit
+    pub fn shadow_map() -> Self {
+        Self {
+            wrap_s: gl::CLAMP_TO_BORDER,
+            wrap_t: gl::CLAMP_TO_BORDER,
+            wrap_r: gl::CLAMP_TO_BORDER,
+            min_filter: gl::LINEAR,
+            border_color: [1.0; 4],
+            compare_func: Some(gl::LEQUAL),
+            ..Self::default()
+        }
+    }
+
+    /// true when the min filter reads from mipmap levels
+    pub fn uses_mipmaps(&self) -> bool {
+        matches!(
+            self.min_filter,
+            gl::NEAREST_MIPMAP_NEAREST
+                | gl::LINEAR_MIPMAP_NEAREST
+                | gl::NEAREST_MIPMAP_LINEAR
+                | gl::LINEAR_MIPMAP_LINEAR
+        )
+    }
+
+    /// set the parameters on the texture currently bound to `target`
+    pub unsafe fn apply(&self, target: GLenum) {
+        self.set_parameters(
+            |pname, value| gl::TexParameteri(target, pname, value),
+            |pname, value| gl::TexParameterf(target, pname, value),
+            |pname, value| gl::TexParameterfv(target, pname, value.as_ptr()),
+        );
+    }
+
+    unsafe fn set_parameters(
+        &self,
+        int: impl Fn(GLenum, GLint),
+        float: impl Fn(GLenum, GLfloat),
+        floats: impl Fn(GLenum, &[GLfloat; 4]),
+    ) {
+        int(gl::TEXTURE_WRAP_S, self.wrap_s as GLint);
+        int(gl::TEXTURE_WRAP_T, self.wrap_t as GLint);
+        int(gl::TEXTURE_WRAP_R, self.wrap_r as GLint);
+        int(gl::TEXTURE_MIN_FILTER, self.min_filter as GLint);
+        int(gl::TEXTURE_MAG_FILTER, self.mag_filter as GLint);
+        floats(gl::TEXTURE_BORDER_COLOR, &self.border_color);
+        float(gl::TEXTURE_LOD_BIAS, self.lod_bias);
+        match self.compare_func {
+            Some(func) => {
+                int(
+                    gl::TEXTURE_COMPARE_MODE,
+                    gl::COMPARE_REF_TO_TEXTURE as GLint,
+                );
+                int(gl::TEXTURE_COMPARE_FUNC, func as GLint);
+            }
+            None => int(gl::TEXTURE_COMPARE_MODE, gl::NONE as GLint),
+        }
+        if self.max_anisotropy > 1.0 {
+            // the query fails (leaving 0) when the extension is missing
+            let mut max_supported: GLfloat = 0.0;
+            gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max_supported);
+            if max_supported >= 1.0 {
+                float(
+                    TEXTURE_MAX_ANISOTROPY,
+                    self.max_anisotropy.min(max_supported),
+                );
+            }
+        }
+    }
+
+    /// every field as raw bits, floats included, so descriptions can be hashed
+    fn key(&self) -> [u32; 13] {
+        [
+            self.wrap_s,
+            self.wrap_t,
+            self.wrap_r,
+            self.min_filter,
+            self.mag_filter,
+            self.max_anisotropy.to_bits(),
+            self.border_color[0].to_bits(),
+            self.border_color[1].to_bits(),
+            self.border_color[2].to_bits(),
+            self.border_color[3].to_bits(),
+            self.lod_bias.to_bits(),
+            self.compare_func.is_some() as u32,
+            self.compare_func.unwrap_or(0),
+        ]
+    }
+}
+
+impl PartialEq for SamplerDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for SamplerDesc {}
+
+impl Hash for SamplerDesc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// a GL sampler object, overrides the parameters of whatever texture is bound to its unit
+pub struct Sampler {
+    pub id: GLuint,
+}
+
+#[allow(dead_code)]
+impl Sampler {
+    pub fn new(desc: SamplerDesc) -> Sampler {
+        let mut sampler = Sampler { id: 0 };
+        unsafe {
+            gl::GenSamplers(1, &mut sampler.id);
+            let id = sampler.id;
+            desc.set_parameters(
+                |pname, value| gl::SamplerParameteri(id, pname, value),
+                |pname, value| gl::SamplerParameterf(id, pname, value),
+                |pname, value| gl::SamplerParameterfv(id, pname, value.as_ptr()),
+            );
+        }
+        sampler
+    }
+
+    /// bind the sampler to the given texture unit
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::BindSampler(unit, self.id);
+    }
+
+    /// go back to the texture's own parameters on the given unit
+    pub unsafe fn unbind(unit: u32) {
+        gl::BindSampler(unit, 0);
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSamplers(1, &self.id);
+        }
+    }
+}
+
+/// hands out one shared sampler object per distinct description
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: HashMap<SamplerDesc, Rc<Sampler>>,
+}
+
+#[allow(dead_code)]
+impl SamplerCache {
+    pub fn get(&mut self, desc: &SamplerDesc) -> Rc<Sampler> {
+        self.samplers
+            .entry(*desc)
+            .or_insert_with(|| Rc::new(Sampler::new(*desc)))
+            .clone()
+    }
+
+    /// number of distinct sampler objects created so far
+    pub fn len(&self) -> usize {
+        self.samplers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samplers.is_empty()
+    }
+}
@@ -6,9 +6,11 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use cgmath::{Array, Matrix, Matrix4, Vector3};
+use cgmath::{Array, Matrix, Matrix3, Matrix4, Vector3, Vector4};
 use gl::types::*;
 
+use crate::uniform_buffer::BLOCK_BINDINGS;
+
 pub struct Shader {
     pub id: GLuint,
 }
@@ -16,6 +18,16 @@ pub struct Shader {
 #[allow(dead_code)]
 impl Shader {
     pub fn new(vertex_path: &str, fragment_path: &str) -> Result<Shader> {
+        Self::with_defines(vertex_path, fragment_path, &[])
+    }
+
+    /// like `new`, with `#define NAME value` lines inserted into both stages, e.g. to size
+    /// uniform arrays from rust
+    pub fn with_defines(
+        vertex_path: &str,
+        fragment_path: &str,
+        defines: &[(&str, String)],
+    ) -> Result<Shader> {
         let mut shader = Shader { id: 0 };
         // read shader code from system
         let mut vshader_file = File::open(vertex_path).context("vertex shader path")?;
@@ -26,6 +38,9 @@ impl Shader {
         let mut fragment_code = String::new();
         fshader_file.read_to_string(&mut fragment_code)?;
 
+        let vertex_code = insert_defines(&vertex_code, defines);
+        let fragment_code = insert_defines(&fragment_code, defines);
+
         let vertex_code = CString::new(vertex_code.as_bytes())?;
         let fragment_code = CString::new(fragment_code.as_bytes())?;
 
@@ -50,6 +65,7 @@ impl Shader {
             gl::DeleteShader(vertex);
             gl::DeleteShader(fragment);
             shader.id = id;
+            shader.bind_uniform_blocks()?;
         }
         Ok(shader)
     }
@@ -74,6 +90,14 @@ impl Shader {
         gl::Uniform1f(gl::GetUniformLocation(self.id, name.as_ptr()), value);
     }
     /// ------------------------------------------------------------------------
+    pub unsafe fn set_floats(&self, name: &CStr, values: &[f32]) {
+        gl::Uniform1fv(
+            gl::GetUniformLocation(self.id, name.as_ptr()),
+            values.len() as GLsizei,
+            values.as_ptr(),
+        );
+    }
+    /// ------------------------------------------------------------------------
     pub unsafe fn set_vector3(&self, name: &CStr, value: &Vector3<f32>) {
         gl::Uniform3fv(
             gl::GetUniformLocation(self.id, name.as_ptr()),
@@ -82,6 +106,14 @@ impl Shader {
         );
     }
     /// ------------------------------------------------------------------------
+    pub unsafe fn set_vector4(&self, name: &CStr, value: &Vector4<f32>) {
+        gl::Uniform4fv(
+            gl::GetUniformLocation(self.id, name.as_ptr()),
+            1,
+            value.as_ptr(),
+        );
+    }
+    /// ------------------------------------------------------------------------
     pub unsafe fn set_vec3(&self, name: &CStr, x: f32, y: f32, z: f32) {
         gl::Uniform3f(gl::GetUniformLocation(self.id, name.as_ptr()), x, y, z);
     }
@@ -94,6 +126,26 @@ impl Shader {
             mat.as_ptr(),
         );
     }
+    /// ------------------------------------------------------------------------
+    pub unsafe fn set_mat3(&self, name: &CStr, mat: &Matrix3<f32>) {
+        gl::UniformMatrix3fv(
+            gl::GetUniformLocation(self.id, name.as_ptr()),
+            1,
+            gl::FALSE,
+            mat.as_ptr(),
+        );
+    }
+
+    /// link the blocks of `BLOCK_BINDINGS` the program declares to their binding points
+    unsafe fn bind_uniform_blocks(&self) -> Result<()> {
+        for (name, binding) in BLOCK_BINDINGS {
+            let index = gl::GetUniformBlockIndex(self.id, CString::new(name)?.as_ptr());
+            if index != gl::INVALID_INDEX {
+                gl::UniformBlockBinding(self.id, index, binding);
+            }
+        }
+        Ok(())
+    }
 
     /// utility function for checking shader compilation/linking errors.
     /// ------------------------------------------------------------------------
@@ -151,7 +203,8 @@ impl Shader {
         Ok(())
     }
 
-    /// Only used in 4.9 Geometry shaders - ignore until then (shader.h in original C++)
+    /// like `new` with a geometry stage between the vertex and fragment stages, used by the
+    /// 4.9 geometry shader tutorials and `NormalOverlay`
     pub fn with_geometry_shader(
         vertex_path: &str,
         fragment_path: &str,
@@ -159,28 +212,19 @@ impl Shader {
     ) -> Result<Self> {
         let mut shader = Shader { id: 0 };
         // 1. retrieve the vertex/fragment source code from filesystem
-        let mut v_shader_file =
-            File::open(vertex_path).unwrap_or_else(|_| panic!("Failed to open {}", vertex_path));
-        let mut f_shader_file = File::open(fragment_path)
-            .unwrap_or_else(|_| panic!("Failed to open {}", fragment_path));
-        let mut g_shader_file = File::open(geometry_path)
-            .unwrap_or_else(|_| panic!("Failed to open {}", geometry_path));
+        let mut v_shader_file = File::open(vertex_path).context("vertex shader path")?;
+        let mut f_shader_file = File::open(fragment_path).context("fragment shader path")?;
+        let mut g_shader_file = File::open(geometry_path).context("geometry shader path")?;
         let mut vertex_code = String::new();
         let mut fragment_code = String::new();
         let mut geometry_code = String::new();
-        v_shader_file
-            .read_to_string(&mut vertex_code)
-            .expect("Failed to read vertex shader");
-        f_shader_file
-            .read_to_string(&mut fragment_code)
-            .expect("Failed to read fragment shader");
-        g_shader_file
-            .read_to_string(&mut geometry_code)
-            .expect("Failed to read geometry shader");
-
-        let v_shader_code = CString::new(vertex_code.as_bytes()).unwrap();
-        let f_shader_code = CString::new(fragment_code.as_bytes()).unwrap();
-        let g_shader_code = CString::new(geometry_code.as_bytes()).unwrap();
+        v_shader_file.read_to_string(&mut vertex_code)?;
+        f_shader_file.read_to_string(&mut fragment_code)?;
+        g_shader_file.read_to_string(&mut geometry_code)?;
+
+        let v_shader_code = CString::new(vertex_code.as_bytes())?;
+        let f_shader_code = CString::new(fragment_code.as_bytes())?;
+        let g_shader_code = CString::new(geometry_code.as_bytes())?;
 
         // 2. compile shaders
         unsafe {
@@ -212,11 +256,38 @@ impl Shader {
             gl::DeleteShader(fragment);
             gl::DeleteShader(geometry);
             shader.id = id;
+            shader.bind_uniform_blocks()?;
         }
 
         Ok(shader)
     }
 }
+
+/// put the defines right after the `#version` line, which has to stay first. `#line` keeps
+/// the line numbers of compile errors pointing at the file
+fn insert_defines(source: &str, defines: &[(&str, String)]) -> String {
+    if defines.is_empty() {
+        return source.to_string();
+    }
+    let (version, rest) = match source.split_once('\n') {
+        Some((first, rest)) if first.trim_start().starts_with("#version") => (first, rest),
+        _ => ("", source),
+    };
+    let mut code = String::new();
+    let mut next_line = 1;
+    if !version.is_empty() {
+        code.push_str(version);
+        code.push('\n');
+        next_line = 2;
+    }
+    for (name, value) in defines {
+        code.push_str(&format!("#define {} {}\n", name, value));
+    }
+    code.push_str(&format!("#line {}\n", next_line));
+    code.push_str(rest);
+    code
+}
+
 impl Drop for Shader {
     fn drop(&mut self) {
         unsafe {
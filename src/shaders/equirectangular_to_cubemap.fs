@@ -0,0 +1,24 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 LocalPos;
+
+uniform sampler2D equirectangularMap;
+
+// 1 / (2 pi), 1 / pi
+const vec2 invAtan = vec2(0.1591, 0.3183);
+
+// direction to longitude/latitude texture coordinates
+vec2 SampleSphericalMap(vec3 v)
+{
+    vec2 uv = vec2(atan(v.z, v.x), asin(v.y));
+    uv *= invAtan;
+    uv += 0.5;
+    return uv;
+}
+
+void main()
+{
+    vec2 uv = SampleSphericalMap(normalize(LocalPos));
+    FragColor = vec4(texture(equirectangularMap, uv).rgb, 1.0);
+}
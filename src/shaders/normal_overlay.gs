@@ -0,0 +1,36 @@
+#version 330 core
+layout (triangles) in;
+layout (line_strip, max_vertices = 6) out;
+
+in VS_OUT {
+    vec3 normal;
+} gs_in[];
+
+uniform mat4 projection;
+// length of the lines
+uniform float magnitude;
+// one line from the centre of every triangle instead of one per vertex
+uniform bool faceNormals;
+
+void emit_line(vec4 start, vec3 direction)
+{
+    gl_Position = projection * start;
+    EmitVertex();
+    gl_Position = projection * (start + vec4(direction * magnitude, 0.0));
+    EmitVertex();
+    EndPrimitive();
+}
+
+void main()
+{
+    if (faceNormals) {
+        // counter-clockwise triangles face along the cross product of their edges
+        vec3 a = gl_in[1].gl_Position.xyz - gl_in[0].gl_Position.xyz;
+        vec3 b = gl_in[2].gl_Position.xyz - gl_in[0].gl_Position.xyz;
+        vec4 center = (gl_in[0].gl_Position + gl_in[1].gl_Position + gl_in[2].gl_Position) / 3.0;
+        emit_line(center, normalize(cross(a, b)));
+    } else {
+        for (int i = 0; i < 3; ++i)
+            emit_line(gl_in[i].gl_Position, gs_in[i].normal);
+    }
+}
@@ -0,0 +1,19 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+
+out VS_OUT {
+    vec3 normal;
+} vs_out;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat3 normalMatrix;
+
+// view space, the geometry shader projects after moving along the normal so every line has
+// the same length in world units
+void main()
+{
+    gl_Position = view * model * vec4(aPos, 1.0);
+    vs_out.normal = normalize(mat3(view) * normalMatrix * aNormal);
+}
@@ -0,0 +1,22 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// rgb: sum of premultiplied colour * weight, a: revealage, the product of (1 - alpha)
+uniform sampler2D accumulation;
+// sum of alpha * weight
+uniform sampler2D weight;
+
+void main()
+{
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    vec4 accum = texelFetch(accumulation, texel, 0);
+    float revealage = accum.a;
+    // nothing transparent covers this pixel
+    if (revealage >= 1.0)
+        discard;
+    float total = texelFetch(weight, texel, 0).r;
+    vec3 average = accum.rgb / max(total, 1e-5);
+    FragColor = vec4(average, 1.0 - revealage);
+}
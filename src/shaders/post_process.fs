@@ -0,0 +1,44 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D screenTexture;
+// 0 copies the input, the others match `Effect` in post_process.rs
+uniform int effect;
+// rows top to bottom as seen on screen
+uniform float kernel[9];
+
+const int COPY = 0;
+const int INVERT = 1;
+const int GRAYSCALE = 2;
+const int KERNEL = 3;
+
+void main()
+{
+    vec3 color = texture(screenTexture, TexCoords).rgb;
+    if (effect == INVERT)
+    {
+        color = 1.0 - color;
+    }
+    else if (effect == GRAYSCALE)
+    {
+        // weighted by how sensitive the eye is to each channel
+        color = vec3(dot(color, vec3(0.2126, 0.7152, 0.0722)));
+    }
+    else if (effect == KERNEL)
+    {
+        // one texel apart, so every tap hits a texel centre and filtering doesn't blur
+        vec2 texel = 1.0 / vec2(textureSize(screenTexture, 0));
+        color = vec3(0.0);
+        for (int row = 0; row < 3; row++)
+        {
+            for (int column = 0; column < 3; column++)
+            {
+                vec2 offset = vec2(column - 1, 1 - row) * texel;
+                color += kernel[row * 3 + column] * texture(screenTexture, TexCoords + offset).rgb;
+            }
+        }
+    }
+    FragColor = vec4(color, 1.0);
+}
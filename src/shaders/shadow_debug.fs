@@ -0,0 +1,14 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// bound without comparison, so this reads the stored depth
+uniform sampler2D depthMap;
+
+// the light's projection is orthographic, so the depth is already linear
+void main()
+{
+    float depth = texture(depthMap, TexCoords).r;
+    FragColor = vec4(vec3(depth), 1.0);
+}
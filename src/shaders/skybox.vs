@@ -0,0 +1,18 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+out vec3 TexCoords;
+
+uniform mat4 projection;
+uniform mat4 view;
+// reversed-Z has its far plane at depth 0
+uniform bool reversedZ;
+
+void main()
+{
+    TexCoords = aPos;
+    vec4 pos = projection * view * vec4(aPos, 1.0);
+    // z = w so the depth after the perspective divide is always 1.0 (the far plane), or z = 0
+    // for 0.0 under reversed-Z
+    gl_Position = reversedZ ? vec4(pos.xy, 0.0, pos.w) : pos.xyww;
+}
@@ -0,0 +1,308 @@
+use std::{ffi::CString, fmt};
+
+use anyhow::Result;
+use cgmath::{vec3, EuclideanSpace, InnerSpace, Matrix4, Point3, Transform as _, Vector3};
+use gl::types::*;
+
+use crate::{
+    framebuffer::{AttachmentStorage, Framebuffer, FramebufferDesc},
+    lighting::{uniform_name, UniformStruct},
+    projection::{orthographic_projection, DepthMode},
+    render_state::CullState,
+    sampler::{Sampler, SamplerDesc},
+    shaders::Shader,
+};
+
+/// texture unit of the shadow map, after the units of `MapMaterial`
+pub const SHADOW_UNIT: u32 = 3;
+
+/// how the shadow map is sampled around the looked up position. every tap is a hardware
+/// comparison with linear filtering, which already blends the 2x2 nearest texels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShadowFilter {
+    Single,
+    /// 9 taps on a one texel grid
+    #[default]
+    Pcf3x3,
+    /// 16 taps on a poisson disk rotated per pixel, noise instead of banding
+    Poisson,
+}
+
+#[allow(dead_code)]
+impl ShadowFilter {
+    pub fn next(self) -> ShadowFilter {
+        match self {
+            ShadowFilter::Single => ShadowFilter::Pcf3x3,
+            ShadowFilter::Pcf3x3 => ShadowFilter::Poisson,
+            ShadowFilter::Poisson => ShadowFilter::Single,
+        }
+    }
+
+    /// the value of the `filterMode` field of the shader's `Shadow` struct
+    fn shader_mode(self) -> i32 {
+        match self {
+            ShadowFilter::Single => 0,
+            ShadowFilter::Pcf3x3 => 1,
+            ShadowFilter::Poisson => 2,
+        }
+    }
+}
+
+impl fmt::Display for ShadowFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShadowFilter::Single => write!(f, "single tap"),
+            ShadowFilter::Pcf3x3 => write!(f, "3x3 pcf"),
+            ShadowFilter::Poisson => write!(f, "poisson pcf"),
+        }
+    }
+}
+
+/// the fixes for shadow acne, surfaces shadowing themselves in stripes, and the peter-panning
+/// they cause in turn, shadows detaching from their casters. every one of them can be changed
+/// between frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowBias {
+    /// subtracted from the depth of every lookup, in depth units of the map
+    pub constant: f32,
+    /// added per unit of tan(angle between normal and light), surfaces at grazing angles
+    /// cover more depth per texel and need more
+    pub slope: f32,
+    /// upper limit of constant + slope bias, the slope term grows without bound
+    pub max: f32,
+    /// world units the lookup position moves out of the surface along its normal at grazing
+    /// angles, fixes acne without moving the shadow away from the caster
+    pub normal_offset: f32,
+    /// glPolygonOffset factor and units for the depth pass, `None` disables it
+    pub polygon_offset: Option<(f32, f32)>,
+    /// render only back faces into the map, so lit front faces never compare against
+    /// themselves. needs closed meshes
+    pub cull_front_faces: bool,
+}
+
+impl Default for ShadowBias {
+    fn default() -> Self {
+        Self {
+            constant: 0.0005,
+            slope: 0.001,
+            max: 0.01,
+            normal_offset: 0.02,
+            polygon_offset: None,
+            cull_front_faces: true,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ShadowBias {
+    /// no mitigation at all, shows the acne
+    pub fn none() -> Self {
+        Self {
+            constant: 0.0,
+            slope: 0.0,
+            max: 0.0,
+            normal_offset: 0.0,
+            polygon_offset: None,
+            cull_front_faces: false,
+        }
+    }
+}
+
+impl UniformStruct for ShadowBias {
+    /// sets `<name>.constant`, `<name>.slope`, `<name>.max` and `<name>.normalOffset`, the
+    /// depth pass options are state of `ShadowMap::begin_depth_pass`
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        shader.set_float(&uniform_name(name, "constant")?, self.constant);
+        shader.set_float(&uniform_name(name, "slope")?, self.slope);
+        shader.set_float(&uniform_name(name, "max")?, self.max);
+        shader.set_float(&uniform_name(name, "normalOffset")?, self.normal_offset);
+        Ok(())
+    }
+}
+
+/// view and orthographic projection of a directional light shining along `direction`, fitted
+/// tightly around the box `min`..`max` so the whole box lands in the shadow map and none of its
+/// resolution is spent outside
+pub fn fit_light_space(
+    direction: Vector3<f32>,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+) -> Matrix4<f32> {
+    let direction = direction.normalize();
+    let center = (min + max) / 2.0;
+    let radius = (max - min).magnitude() / 2.0;
+    // any up vector works as long as it isn't parallel to the light
+    let up = if direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+    let eye = Point3::from_vec(center - direction * radius);
+    let view = Matrix4::look_at_rh(eye, Point3::from_vec(center), up);
+
+    // the box's extent in light space
+    let mut low = vec3(f32::MAX, f32::MAX, f32::MAX);
+    let mut high = vec3(f32::MIN, f32::MIN, f32::MIN);
+    for corner in 0..8 {
+        let point = Point3::new(
+            if corner & 1 == 0 { min.x } else { max.x },
+            if corner & 2 == 0 { min.y } else { max.y },
+            if corner & 4 == 0 { min.z } else { max.z },
+        );
+        let point = view.transform_point(point);
+        low = vec3(low.x.min(point.x), low.y.min(point.y), low.z.min(point.z));
+        high = vec3(
+            high.x.max(point.x),
+            high.y.max(point.y),
+            high.z.max(point.z),
+        );
+    }
+    // the light looks down -z, the near plane is the box's largest z
+    let projection = orthographic_projection(
+        low.x,
+        high.x,
+        low.y,
+        high.y,
+        -high.z,
+        -low.z,
+        DepthMode::Standard,
+    );
+    projection * view
+}
+
+/// a depth map rendered from a directional light, and the settings for looking it up.
+///
+/// the scene is drawn twice per frame: first between `begin_depth_pass` and `end_depth_pass`
+/// with `depth_shader`, then normally with the shader's `Shadow` struct uploaded by
+/// `UniformStruct::upload`
+pub struct ShadowMap {
+    pub filter: ShadowFilter,
+    pub bias: ShadowBias,
+    /// spread of the poisson disk, in texels
+    pub poisson_radius: f32,
+    /// light view-projection, see `fit_light_space`
+    pub light_space: Matrix4<f32>,
+    /// depth only, the texture keeps its nearest filtering for the debug view
+    target: Framebuffer,
+    /// linear filtered comparison, bound over the texture's parameters for the lookups
+    compare_sampler: Sampler,
+    depth_shader: Shader,
+    debug_shader: Shader,
+    /// empty, the debug view builds its triangle from the vertex id
+    vao: GLuint,
+}
+
+#[allow(dead_code)]
+impl ShadowMap {
+    pub fn new(size: i32) -> Result<ShadowMap> {
+        let depth_shader =
+            Shader::new("src/shaders/shadow_depth.vs", "src/shaders/shadow_depth.fs")?;
+        let debug_shader = Shader::new("src/shaders/fullscreen.vs", "src/shaders/shadow_debug.fs")?;
+        let target = FramebufferDesc::new()
+            .with_depth_stencil(gl::DEPTH_COMPONENT24, AttachmentStorage::Texture)
+            .build(size, size)?;
+        let mut shadow_map = ShadowMap {
+            filter: ShadowFilter::default(),
+            bias: ShadowBias::default(),
+            poisson_radius: 1.5,
+            light_space: Matrix4::from_scale(1.0),
+            target,
+            compare_sampler: Sampler::new(SamplerDesc::shadow_map()),
+            depth_shader,
+            debug_shader,
+            vao: 0,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut shadow_map.vao);
+            shadow_map.debug_shader.use_program();
+            shadow_map
+                .debug_shader
+                .set_int(&CString::new("depthMap")?, SHADOW_UNIT as i32);
+        }
+        Ok(shadow_map)
+    }
+
+    /// the map is square, this is its width and height
+    pub fn size(&self) -> i32 {
+        self.target.size().0
+    }
+
+    /// reallocate the map, does nothing when the size is unchanged
+    pub unsafe fn resize(&mut self, size: i32) -> Result<()> {
+        self.target.resize(size, size)
+    }
+
+    /// the program for the depth pass, draws have to set its `model` uniform
+    pub fn depth_shader(&self) -> &Shader {
+        &self.depth_shader
+    }
+
+    /// bind and clear the map, set up the depth pass biasing and use `depth_shader`
+    pub unsafe fn begin_depth_pass(&self) -> Result<()> {
+        self.target.bind();
+        gl::Clear(gl::DEPTH_BUFFER_BIT);
+        if let Some((factor, units)) = self.bias.polygon_offset {
+            gl::Enable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonOffset(factor, units);
+        }
+        if self.bias.cull_front_faces {
+            CullState {
+                enabled: true,
+                face: gl::FRONT,
+                ..CullState::default()
+            }
+            .apply();
+        }
+        self.depth_shader.use_program();
+        self.depth_shader
+            .set_mat4(&CString::new("lightSpaceMatrix")?, &self.light_space);
+        Ok(())
+    }
+
+    /// back to the window of `size`, polygon offset and face culling are left disabled
+    pub unsafe fn end_depth_pass(&self, size: (i32, i32)) {
+        gl::Disable(gl::POLYGON_OFFSET_FILL);
+        CullState::disabled().apply();
+        Framebuffer::bind_default(size);
+    }
+
+    /// show the depth map over the current viewport, near is black and far is white
+    pub unsafe fn draw_debug(&self) {
+        gl::Disable(gl::DEPTH_TEST);
+        self.debug_shader.use_program();
+        self.target.bind_depth_texture(SHADOW_UNIT);
+        // the texture's own parameters, which return the depth instead of a comparison
+        Sampler::unbind(SHADOW_UNIT);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        gl::BindVertexArray(0);
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+impl UniformStruct for ShadowMap {
+    /// binds the map to `SHADOW_UNIT` and sets `<name>.map`, `<name>.lightSpaceMatrix`,
+    /// `<name>.filterMode`, `<name>.poissonRadius` and the `<name>.bias` struct
+    unsafe fn upload(&self, shader: &Shader, name: &str) -> Result<()> {
+        self.target.bind_depth_texture(SHADOW_UNIT);
+        self.compare_sampler.bind(SHADOW_UNIT);
+        gl::ActiveTexture(gl::TEXTURE0);
+        shader.set_int(&uniform_name(name, "map")?, SHADOW_UNIT as i32);
+        shader.set_mat4(&uniform_name(name, "lightSpaceMatrix")?, &self.light_space);
+        shader.set_int(
+            &uniform_name(name, "filterMode")?,
+            self.filter.shader_mode(),
+        );
+        shader.set_float(&uniform_name(name, "poissonRadius")?, self.poisson_radius);
+        self.bias.upload(shader, &format!("{}.bias", name))
+    }
+}
+
+impl Drop for ShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{ffi::CString, mem, ptr};
+
+use anyhow::Result;
+use cgmath::{Matrix3, Matrix4};
+use gl::types::*;
+
+use crate::{shaders::Shader, texture::TextureCube};
+
+#[rustfmt::skip]
+pub const SKYBOX_VERTICES: [f32; 108] = [
+    // positions
+    -1.0,  1.0, -1.0,
+    -1.0, -1.0, -1.0,
+     1.0, -1.0, -1.0,
+     1.0, -1.0, -1.0,
+     1.0,  1.0, -1.0,
+    -1.0,  1.0, -1.0,
+
+    -1.0, -1.0,  1.0,
+    -1.0, -1.0, -1.0,
+    -1.0,  1.0, -1.0,
+    -1.0,  1.0, -1.0,
+    -1.0,  1.0,  1.0,
+    -1.0, -1.0,  1.0,
+
+     1.0, -1.0, -1.0,
+     1.0, -1.0,  1.0,
+     1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,
+     1.0,  1.0, -1.0,
+     1.0, -1.0, -1.0,
+
+    -1.0, -1.0,  1.0,
+    -1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,
+     1.0, -1.0,  1.0,
+    -1.0, -1.0,  1.0,
+
+    -1.0,  1.0, -1.0,
+     1.0,  1.0, -1.0,
+     1.0,  1.0,  1.0,
+     1.0,  1.0,  1.0,
+    -1.0,  1.0,  1.0,
+    -1.0,  1.0, -1.0,
+
+    -1.0, -1.0, -1.0,
+    -1.0, -1.0,  1.0,
+     1.0, -1.0, -1.0,
+     1.0, -1.0, -1.0,
+    -1.0, -1.0,  1.0,
+     1.0, -1.0,  1.0,
+];
+
+/// keep only the rotation of a view matrix so the skybox never gets closer
+pub fn strip_translation(view: &Matrix4<f32>) -> Matrix4<f32> {
+    Matrix4::from(Matrix3::from_cols(
+        view.x.truncate(),
+        view.y.truncate(),
+        view.z.truncate(),
+    ))
+}
+
+/// draws a cubemap around the camera, behind everything else in the scene
+pub struct Skybox {
+    vao: GLuint,
+    vbo: GLuint,
+    shader: Shader,
+    pub cubemap: TextureCube,
+}
+
+impl Skybox {
+    pub fn new(cubemap: TextureCube) -> Result<Skybox> {
+        let shader = Shader::new("src/shaders/skybox.vs", "src/shaders/skybox.fs")?;
+
+        let mut skybox = Skybox {
+            vao: 0,
+            vbo: 0,
+            shader,
+            cubemap,
+        };
+        unsafe {
+            gl::GenVertexArrays(1, &mut skybox.vao);
+            gl::GenBuffers(1, &mut skybox.vbo);
+            gl::BindVertexArray(skybox.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, skybox.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&SKYBOX_VERTICES) as GLsizeiptr,
+                SKYBOX_VERTICES.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                3 * mem::size_of::<GLfloat>() as GLsizei,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+            gl::BindVertexArray(0);
+
+            skybox.shader.use_program();
+            skybox.shader.set_int(&CString::new("skybox")?, 0);
+        }
+        Ok(skybox)
+    }
+
+    /// draw the skybox last, after the opaque geometry, so only uncovered pixels are shaded.
+    /// works with either `DepthMode`, which is told apart by the current depth function. the
+    /// caller's depth function is restored afterwards
+    pub unsafe fn draw(&self, view: &Matrix4<f32>, projection: &Matrix4<f32>) -> Result<()> {
+        // the skybox sits exactly on the far plane, which fails a strict LESS or GREATER test
+        let mut previous_func: GLint = 0;
+        gl::GetIntegerv(gl::DEPTH_FUNC, &mut previous_func);
+        let previous_func = previous_func as GLenum;
+        gl::DepthFunc(inclusive_depth_func(previous_func));
+        self.shader.use_program();
+        self.shader.set_bool(
+            &CString::new("reversedZ")?,
+            matches!(previous_func, gl::GREATER | gl::GEQUAL),
+        );
+        self.shader
+            .set_mat4(&CString::new("view")?, &strip_translation(view));
+        self.shader
+            .set_mat4(&CString::new("projection")?, projection);
+
+        gl::BindVertexArray(self.vao);
+        self.cubemap.bind(0);
+        gl::DrawArrays(gl::TRIANGLES, 0, 36);
+        gl::BindVertexArray(0);
+        gl::DepthFunc(previous_func);
+        Ok(())
+    }
+}
+
+/// the depth function that also passes fragments at the same depth, LEQUAL for the standard
+/// LESS and GEQUAL for reversed-Z's GREATER
+fn inclusive_depth_func(func: GLenum) -> GLenum {
+    match func {
+        gl::LESS => gl::LEQUAL,
+        gl::GREATER => gl::GEQUAL,
+        func => func,
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+        }
+    }
+}
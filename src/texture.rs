@@ -0,0 +1,636 @@
+use std::{borrow::Cow, ffi::c_void, path::Path};
+
+use anyhow::{bail, Context, Result};
+use gl::types::*;
+use image::{ColorType, DynamicImage, Rgba, RgbaImage};
+
+use crate::{
+    compressed::{BlockFormat, DataFormat, TextureData},
+    sampler::SamplerDesc,
+};
+
+/// how an image should be turned into a texture
+#[derive(Debug, Clone, Copy)]
+pub struct TextureOptions {
+    /// images are stored top row first, OpenGL expects the bottom row first
+    pub flip_vertically: bool,
+    /// treat 8-bit colour data as sRGB so sampling returns linear values
+    pub srgb: bool,
+    /// wrap/filter parameters stored on the texture itself
+    pub sampler: SamplerDesc,
+    pub generate_mipmaps: bool,
+    /// convert the image to floating point before uploading, `None` keeps its own type.
+    /// `.hdr` and `.exr` files are float already, this mostly matters for 16-bit images
+    pub float: Option<FloatPrecision>,
+}
+
+/// storage precision of floating point textures
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatPrecision {
+    /// RGB16F/RGBA16F, enough range for HDR colours at half the memory
+    Half,
+    /// RGB32F/RGBA32F
+    Full,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            flip_vertically: true,
+            srgb: false,
+            sampler: SamplerDesc::default(),
+            generate_mipmaps: true,
+            float: None,
+        }
+    }
+}
+
+/// the formats passed to glTexImage2D for one image colour type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    pub internal_format: GLenum,
+    pub format: GLenum,
+    pub data_type: GLenum,
+    /// swizzle applied so that gray images sample as gray instead of red
+    pub swizzle: Option<[GLenum; 4]>,
+}
+
+impl PixelFormat {
+    /// map an `image` colour type to matching internal/external formats
+    pub fn from_color_type(color: ColorType, srgb: bool) -> Result<PixelFormat> {
+        const GRAY: [GLenum; 4] = [gl::RED, gl::RED, gl::RED, gl::ONE];
+        const GRAY_ALPHA: [GLenum; 4] = [gl::RED, gl::RED, gl::RED, gl::GREEN];
+
+        // there are no 16-bit or float sRGB formats, those are always treated as linear
+        let (internal_format, format, data_type, swizzle) = match color {
+            ColorType::L8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE, Some(GRAY)),
+            ColorType::La8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE, Some(GRAY_ALPHA)),
+            ColorType::Rgb8 if srgb => (gl::SRGB8, gl::RGB, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgb8 => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgba8 if srgb => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE, None),
+            ColorType::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE, None),
+            ColorType::L16 => (gl::R16, gl::RED, gl::UNSIGNED_SHORT, Some(GRAY)),
+            ColorType::La16 => (gl::RG16, gl::RG, gl::UNSIGNED_SHORT, Some(GRAY_ALPHA)),
+            ColorType::Rgb16 => (gl::RGB16, gl::RGB, gl::UNSIGNED_SHORT, None),
+            ColorType::Rgba16 => (gl::RGBA16, gl::RGBA, gl::UNSIGNED_SHORT, None),
+            ColorType::Rgb32F => (gl::RGB32F, gl::RGB, gl::FLOAT, None),
+            ColorType::Rgba32F => (gl::RGBA32F, gl::RGBA, gl::FLOAT, None),
+            other => bail!("unsupported image color type {:?}", other),
+        };
+
+        Ok(PixelFormat {
+            internal_format,
+            format,
+            data_type,
+            swizzle,
+        })
+    }
+
+    /// float data uploaded from an `Rgb32F`/`Rgba32F` image into the given precision
+    pub fn float(alpha: bool, precision: FloatPrecision) -> PixelFormat {
+        let (internal_format, format) = match (alpha, precision) {
+            (false, FloatPrecision::Half) => (gl::RGB16F, gl::RGB),
+            (true, FloatPrecision::Half) => (gl::RGBA16F, gl::RGBA),
+            (false, FloatPrecision::Full) => (gl::RGB32F, gl::RGB),
+            (true, FloatPrecision::Full) => (gl::RGBA32F, gl::RGBA),
+        };
+        PixelFormat {
+            internal_format,
+            format,
+            data_type: gl::FLOAT,
+            swizzle: None,
+        }
+    }
+}
+
+/// largest row alignment that the given row size in bytes satisfies
+pub fn unpack_alignment(row_bytes: usize) -> GLint {
+    [8, 4, 2]
+        .into_iter()
+        .find(|alignment| row_bytes.is_multiple_of(*alignment))
+        .unwrap_or(1) as GLint
+}
+
+#[allow(dead_code)]
+impl TextureOptions {
+    /// cubemap faces are stored top row first and are sampled by direction, so no flip
+    pub fn cubemap() -> Self {
+        Self {
+            flip_vertically: false,
+            sampler: SamplerDesc::linear_clamp(),
+            generate_mipmaps: false,
+            ..Self::default()
+        }
+    }
+
+    /// HDR images and environment maps: float storage, clamped and without mipmaps
+    pub fn hdr() -> Self {
+        Self {
+            sampler: SamplerDesc::linear_clamp(),
+            generate_mipmaps: false,
+            float: Some(FloatPrecision::Half),
+            ..Self::default()
+        }
+    }
+
+    /// the sampler actually used, without mipmaps a mipmapped min filter leaves the
+    /// texture incomplete (black)
+    fn effective_sampler(&self) -> SamplerDesc {
+        if !self.generate_mipmaps && self.sampler.uses_mipmaps() {
+            SamplerDesc {
+                min_filter: gl::LINEAR,
+                ..self.sampler
+            }
+        } else {
+            self.sampler
+        }
+    }
+}
+
+fn open_image(path: &Path) -> Result<DynamicImage> {
+    image::open(path).with_context(|| format!("opening texture {}", path.display()))
+}
+
+/// upload formats for images of the given colour type, honouring `options.float`
+fn pixel_format(color: ColorType, options: &TextureOptions) -> Result<PixelFormat> {
+    match options.float {
+        Some(precision) => Ok(PixelFormat::float(color.has_alpha(), precision)),
+        None => PixelFormat::from_color_type(color, options.srgb),
+    }
+}
+
+/// convert integer images to float when `options.float` asks for it. the data is normalized
+/// to 0..1, 8-bit sRGB data is *not* linearized here
+fn convert_image(image: DynamicImage, options: &TextureOptions) -> DynamicImage {
+    match image {
+        _ if options.float.is_none() => image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => image,
+        image if image.color().has_alpha() => DynamicImage::ImageRgba32F(image.to_rgba32f()),
+        image => DynamicImage::ImageRgb32F(image.to_rgb32f()),
+    }
+}
+
+/// upload one image to `target` (a 2D texture or a single cubemap face) of the bound texture
+unsafe fn upload_image(target: GLenum, image: &DynamicImage, pixel_format: &PixelFormat) {
+    let row_bytes = image.width() as usize * image.color().bytes_per_pixel() as usize;
+
+    // rows are tightly packed, e.g. an odd width RGB image is not 4 byte aligned
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, unpack_alignment(row_bytes));
+    gl::TexImage2D(
+        target,
+        0,
+        pixel_format.internal_format as GLint,
+        image.width() as GLint,
+        image.height() as GLint,
+        0,
+        pixel_format.format,
+        pixel_format.data_type,
+        image.as_bytes().as_ptr() as *const c_void,
+    );
+    // restore the default so raw uploads elsewhere are not affected
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+}
+
+/// set sampling parameters and the gray swizzle on the texture bound to `target`
+unsafe fn apply_parameters(target: GLenum, options: &TextureOptions, pixel_format: &PixelFormat) {
+    options.effective_sampler().apply(target);
+    if let Some(swizzle) = pixel_format.swizzle {
+        let swizzle = swizzle.map(|channel| channel as GLint);
+        gl::TexParameteriv(target, gl::TEXTURE_SWIZZLE_RGBA, swizzle.as_ptr());
+    }
+}
+
+/// how the images of a container end up on the GPU
+enum ContainerUpload {
+    Raw(PixelFormat),
+    /// compressed blocks passed straight to the driver
+    Compressed(GLenum),
+    /// blocks the driver can't sample, decoded to RGBA8 first
+    Decoded(BlockFormat, PixelFormat),
+}
+
+/// upload every image of a KTX2/DDS container to the texture bound to `target`, which is
+/// TEXTURE_2D, TEXTURE_CUBE_MAP or TEXTURE_2D_ARRAY. block compressed data can't be flipped,
+/// so `flip_vertically` is ignored and the data has to be authored bottom row first.
+unsafe fn upload_container(
+    target: GLenum,
+    data: &TextureData,
+    options: &TextureOptions,
+) -> Result<()> {
+    let upload = match data.format {
+        DataFormat::Uncompressed { format, .. } => ContainerUpload::Raw(format),
+        DataFormat::Compressed { block, srgb } => match block.gl_internal_format(srgb) {
+            Some(internal_format) if block.driver_supports(srgb) => {
+                ContainerUpload::Compressed(internal_format)
+            }
+            _ => ContainerUpload::Decoded(
+                block,
+                PixelFormat::from_color_type(ColorType::Rgba8, srgb)?,
+            ),
+        },
+    };
+
+    // glGenerateMipmap can't write compressed levels, use whatever the file contains
+    let generate = !matches!(upload, ContainerUpload::Compressed(_))
+        && data.levels.len() == 1
+        && (data.generate_mipmaps || options.generate_mipmaps);
+    let sampler_options = TextureOptions {
+        generate_mipmaps: data.levels.len() > 1 || generate,
+        ..*options
+    };
+    sampler_options.effective_sampler().apply(target);
+    if !generate {
+        // a short mip chain is only complete if the texture knows where it ends
+        gl::TexParameteri(
+            target,
+            gl::TEXTURE_MAX_LEVEL,
+            data.levels.len() as GLint - 1,
+        );
+    }
+
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    for level in 0..data.levels.len() {
+        let (width, height) = data.level_size(level);
+        let mut array_data = Vec::new();
+        for layer in 0..data.layers {
+            for face in 0..data.faces {
+                let image = data.image(level, layer, face);
+                let image: Cow<[u8]> = match upload {
+                    ContainerUpload::Decoded(block, _) => {
+                        Cow::Owned(block.decode(image, width, height)?)
+                    }
+                    _ => Cow::Borrowed(image),
+                };
+                if target == gl::TEXTURE_2D_ARRAY {
+                    array_data.extend_from_slice(&image);
+                    continue;
+                }
+                let image_target = if target == gl::TEXTURE_CUBE_MAP {
+                    CUBE_FACE_TARGETS[face as usize]
+                } else {
+                    target
+                };
+                match &upload {
+                    ContainerUpload::Compressed(internal_format) => gl::CompressedTexImage2D(
+                        image_target,
+                        level as GLint,
+                        *internal_format,
+                        width as GLsizei,
+                        height as GLsizei,
+                        0,
+                        image.len() as GLsizei,
+                        image.as_ptr() as *const c_void,
+                    ),
+                    ContainerUpload::Raw(format) | ContainerUpload::Decoded(_, format) => {
+                        gl::TexImage2D(
+                            image_target,
+                            level as GLint,
+                            format.internal_format as GLint,
+                            width as GLsizei,
+                            height as GLsizei,
+                            0,
+                            format.format,
+                            format.data_type,
+                            image.as_ptr() as *const c_void,
+                        )
+                    }
+                }
+            }
+        }
+
+        if target == gl::TEXTURE_2D_ARRAY {
+            match &upload {
+                ContainerUpload::Compressed(internal_format) => gl::CompressedTexImage3D(
+                    target,
+                    level as GLint,
+                    *internal_format,
+                    width as GLsizei,
+                    height as GLsizei,
+                    data.layers as GLsizei,
+                    0,
+                    array_data.len() as GLsizei,
+                    array_data.as_ptr() as *const c_void,
+                ),
+                ContainerUpload::Raw(format) | ContainerUpload::Decoded(_, format) => {
+                    gl::TexImage3D(
+                        target,
+                        level as GLint,
+                        format.internal_format as GLint,
+                        width as GLsizei,
+                        height as GLsizei,
+                        data.layers as GLsizei,
+                        0,
+                        format.format,
+                        format.data_type,
+                        array_data.as_ptr() as *const c_void,
+                    )
+                }
+            }
+        }
+    }
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+
+    if generate {
+        gl::GenerateMipmap(target);
+    }
+    Ok(())
+}
+
+/// KTX2 and DDS files go through the container loader instead of `image`
+fn is_container_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("ktx2") || extension.eq_ignore_ascii_case("dds")
+        })
+}
+
+#[allow(dead_code)]
+pub struct Texture2D {
+    pub id: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[allow(dead_code)]
+impl Texture2D {
+    pub fn from_path<P: AsRef<Path>>(path: P, options: TextureOptions) -> Result<Texture2D> {
+        let path = path.as_ref();
+        if is_container_path(path) {
+            return Self::from_container(&TextureData::open(path)?, options);
+        }
+        Self::from_image(open_image(path)?, options)
+    }
+
+    /// upload a KTX2/DDS texture, see `upload_container` for the details
+    pub fn from_container(data: &TextureData, options: TextureOptions) -> Result<Texture2D> {
+        if data.layers != 1 || data.faces != 1 {
+            bail!(
+                "expected a plain 2D texture, got {} layers and {} faces",
+                data.layers,
+                data.faces
+            );
+        }
+        let mut texture = Texture2D {
+            id: 0,
+            width: data.width,
+            height: data.height,
+        };
+        unsafe {
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+            upload_container(gl::TEXTURE_2D, data, &options)?;
+        }
+        Ok(texture)
+    }
+
+    pub fn from_image(image: DynamicImage, options: TextureOptions) -> Result<Texture2D> {
+        let image = if options.flip_vertically {
+            image.flipv()
+        } else {
+            image
+        };
+        let pixel_format = pixel_format(image.color(), &options)?;
+        let image = convert_image(image, &options);
+
+        let mut texture = Texture2D {
+            id: 0,
+            width: image.width(),
+            height: image.height(),
+        };
+        unsafe {
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+            apply_parameters(gl::TEXTURE_2D, &options, &pixel_format);
+            upload_image(gl::TEXTURE_2D, &image, &pixel_format);
+            if options.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+        }
+        Ok(texture)
+    }
+
+    /// a 1x1 texture of one colour, e.g. a black stand-in for a missing emission map
+    pub fn solid(rgba: [u8; 4]) -> Result<Texture2D> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(rgba)));
+        Self::from_image(
+            image,
+            TextureOptions {
+                generate_mipmaps: false,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// bind the texture to the given texture unit
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_2D, self.id);
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+/// face order used everywhere: +X, -X, +Y, -Y, +Z, -Z (right, left, top, bottom, front, back)
+pub const CUBE_FACE_TARGETS: [GLenum; 6] = [
+    gl::TEXTURE_CUBE_MAP_POSITIVE_X,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_X,
+    gl::TEXTURE_CUBE_MAP_POSITIVE_Y,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+    gl::TEXTURE_CUBE_MAP_POSITIVE_Z,
+    gl::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+];
+
+/// split a single image holding all six faces into separate faces, in `CUBE_FACE_TARGETS` order.
+///
+/// supported layouts, detected from the aspect ratio:
+/// - horizontal cross (4:3): `+Y` above and `-Y` below `+Z`, row `-X +Z +X -Z`
+/// - vertical cross (3:4): same as the horizontal cross but with `-Z` under `-Y`, upside down
+/// - horizontal (6:1) and vertical (1:6) strips in face order
+pub fn split_cube_layout(image: &DynamicImage) -> Result<[DynamicImage; 6]> {
+    let (width, height) = (image.width(), image.height());
+    // (column, row) of every face in units of the face size
+    let (size, cells, flip_back) = if width * 3 == height * 4 {
+        let cells = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+        (width / 4, cells, false)
+    } else if width * 4 == height * 3 {
+        let cells = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)];
+        (width / 3, cells, true)
+    } else if width == height * 6 {
+        let cells = [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)];
+        (height, cells, false)
+    } else if width * 6 == height {
+        let cells = [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)];
+        (width, cells, false)
+    } else {
+        bail!(
+            "{}x{} is not a cross or strip cubemap layout",
+            width,
+            height
+        );
+    };
+
+    let mut faces =
+        cells.map(|(column, row)| image.crop_imm(column * size, row * size, size, size));
+    if flip_back {
+        faces[5] = faces[5].rotate180();
+    }
+    Ok(faces)
+}
+
+#[allow(dead_code)]
+pub struct TextureCube {
+    pub id: GLuint,
+    /// width and height of every face
+    pub size: u32,
+}
+
+#[allow(dead_code)]
+impl TextureCube {
+    /// load six separate face images given in `CUBE_FACE_TARGETS` order
+    pub fn from_faces<P: AsRef<Path>>(
+        paths: [P; 6],
+        options: TextureOptions,
+    ) -> Result<TextureCube> {
+        let mut faces = Vec::with_capacity(6);
+        for path in &paths {
+            faces.push(open_image(path.as_ref())?);
+        }
+        let faces: [DynamicImage; 6] = faces.try_into().expect("exactly six faces");
+        Self::from_images(faces, options)
+    }
+
+    /// load a single image with all faces in a cross or strip layout, see `split_cube_layout`
+    pub fn from_layout<P: AsRef<Path>>(path: P, options: TextureOptions) -> Result<TextureCube> {
+        let path = path.as_ref();
+        let faces = split_cube_layout(&open_image(path)?)
+            .with_context(|| format!("splitting cubemap {}", path.display()))?;
+        Self::from_images(faces, options)
+    }
+
+    /// upload a KTX2/DDS cubemap, see `upload_container` for the details
+    pub fn from_container(data: &TextureData, options: TextureOptions) -> Result<TextureCube> {
+        if !data.is_cubemap() || data.layers != 1 {
+            bail!(
+                "expected a cubemap, got {} layers and {} faces",
+                data.layers,
+                data.faces
+            );
+        }
+        let mut texture = TextureCube {
+            id: 0,
+            size: data.width,
+        };
+        unsafe {
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture.id);
+            upload_container(gl::TEXTURE_CUBE_MAP, data, &options)?;
+        }
+        Ok(texture)
+    }
+
+    pub fn from_images(faces: [DynamicImage; 6], options: TextureOptions) -> Result<TextureCube> {
+        let size = faces[0].width();
+        let color = faces[0].color();
+        for (index, face) in faces.iter().enumerate() {
+            if face.width() != face.height() {
+                bail!("cubemap face {} is not square", index);
+            }
+            if face.width() != size || face.color() != color {
+                bail!(
+                    "cubemap face {} does not match the size/format of face 0",
+                    index
+                );
+            }
+        }
+        let pixel_format = pixel_format(color, &options)?;
+
+        let mut texture = TextureCube { id: 0, size };
+        unsafe {
+            // filter across face edges instead of showing seams, this is global state
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture.id);
+            apply_parameters(gl::TEXTURE_CUBE_MAP, &options, &pixel_format);
+            for (target, face) in CUBE_FACE_TARGETS.iter().zip(faces) {
+                let face = if options.flip_vertically {
+                    face.flipv()
+                } else {
+                    face
+                };
+                upload_image(*target, &convert_image(face, &options), &pixel_format);
+            }
+            if options.generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            }
+        }
+        Ok(texture)
+    }
+
+    /// bind the cubemap to the given texture unit
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, self.id);
+    }
+}
+
+impl Drop for TextureCube {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub struct Texture2DArray {
+    pub id: GLuint,
+    pub width: u32,
+    pub height: u32,
+    pub layers: u32,
+}
+
+#[allow(dead_code)]
+impl Texture2DArray {
+    /// upload every layer of a KTX2/DDS array texture, see `upload_container` for the details
+    pub fn from_container(data: &TextureData, options: TextureOptions) -> Result<Texture2DArray> {
+        if data.faces != 1 {
+            bail!("cubemap arrays are not supported");
+        }
+        let mut texture = Texture2DArray {
+            id: 0,
+            width: data.width,
+            height: data.height,
+            layers: data.layers,
+        };
+        unsafe {
+            gl::GenTextures(1, &mut texture.id);
+            gl::BindTexture(gl::TEXTURE_2D_ARRAY, texture.id);
+            upload_container(gl::TEXTURE_2D_ARRAY, data, &options)?;
+        }
+        Ok(texture)
+    }
+
+    /// bind the array texture to the given texture unit
+    pub unsafe fn bind(&self, unit: u32) {
+        gl::ActiveTexture(gl::TEXTURE0 + unit);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, self.id);
+    }
+}
+
+impl Drop for Texture2DArray {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::ops::Mul;
+
+use cgmath::{
+    ElementWise, EuclideanSpace, InnerSpace, Matrix, Matrix3, Matrix4, One, Point3, Quaternion,
+    Rad, Rotation3, SquareMatrix, Vector3, Zero,
+};
+
+/// translation, rotation and non-uniform scale, applied to points in the order
+/// scale -> rotate -> translate (the matrix is `T * R * S`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Transform {
+    pub fn from_translation(translation: Vector3<f32>) -> Transform {
+        Transform {
+            translation,
+            ..Default::default()
+        }
+    }
+
+    /// rotation of `angle` around `axis`, the axis does not need to be normalized
+    pub fn from_axis_angle<A: Into<Rad<f32>>>(axis: Vector3<f32>, angle: A) -> Transform {
+        Transform {
+            rotation: Quaternion::from_axis_angle(axis.normalize(), angle),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_scale(scale: f32) -> Transform {
+        Self::from_nonuniform_scale(Vector3::new(scale, scale, scale))
+    }
+
+    pub fn from_nonuniform_scale(scale: Vector3<f32>) -> Transform {
+        Transform {
+            scale,
+            ..Default::default()
+        }
+    }
+
+    /// the model matrix `T * R * S`
+    pub fn to_matrix(self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    /// matrix for transforming normals: the inverse transpose of the upper 3x3, which for
+    /// `R * S` is simply `R * S^-1`
+    pub fn normal_matrix(&self) -> Matrix3<f32> {
+        let inverse_scale = self.scale.map(|s| 1.0 / s);
+        Matrix3::from(self.rotation) * Matrix3::from_diagonal(inverse_scale)
+    }
+
+    /// `self` applied after `child`, the same as `self.to_matrix() * child.to_matrix()`.
+    /// only exact when `self` has a uniform scale, a rotated child under a non-uniform scale
+    /// is sheared, which a `Transform` can't represent. use the matrices for that case
+    pub fn compose(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.translation
+                + self.rotation * self.scale.mul_element_wise(child.translation),
+            rotation: self.rotation * child.rotation,
+            scale: self.scale.mul_element_wise(child.scale),
+        }
+    }
+
+    /// the transform undoing this one. like `compose` this is only exact for a uniform scale,
+    /// `inverse_matrix` is exact for every scale
+    pub fn inverse(&self) -> Transform {
+        let scale = self.scale.map(|s| 1.0 / s);
+        let rotation = self.rotation.conjugate();
+        Transform {
+            translation: -(rotation * self.translation).mul_element_wise(scale),
+            rotation,
+            scale,
+        }
+    }
+
+    /// `S^-1 * R^-1 * T^-1`, cheaper and more precise than inverting `to_matrix`
+    pub fn inverse_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_nonuniform_scale(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z)
+            * Matrix4::from(self.rotation.conjugate())
+            * Matrix4::from_translation(-self.translation)
+    }
+
+    pub fn transform_point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::from_vec(self.transform_vector(point.to_vec()) + self.translation)
+    }
+
+    /// directions ignore the translation
+    pub fn transform_vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        self.rotation * self.scale.mul_element_wise(vector)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, child: Transform) -> Transform {
+        self.compose(&child)
+    }
+}
+
+/// normal matrix of an arbitrary model matrix, e.g. one read from a file or accumulated down a
+/// node hierarchy: the inverse transpose of its upper 3x3
+pub fn normal_matrix(model: &Matrix4<f32>) -> Matrix3<f32> {
+    let upper = Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate());
+    upper
+        .invert()
+        .map(|inverse| inverse.transpose())
+        .unwrap_or_else(Matrix3::identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{assert_abs_diff_eq, vec3, Deg, Vector4};
+
+    use super::*;
+
+    fn rotated_scaled() -> Transform {
+        Transform {
+            translation: vec3(1.0, -2.0, 0.5),
+            scale: vec3(2.0, 0.5, 3.0),
+            ..Transform::from_axis_angle(vec3(1.0, 1.0, 0.0), Deg(30.0))
+        }
+    }
+
+    #[test]
+    fn matrix_is_translate_rotate_scale() {
+        let transform = Transform {
+            translation: vec3(1.0, 2.0, 3.0),
+            scale: vec3(2.0, 3.0, 4.0),
+            ..Transform::from_axis_angle(Vector3::unit_z(), Deg(90.0))
+        };
+        // scaled x ends up on y, scaled y on -x
+        #[rustfmt::skip]
+        let expected = Matrix4::new(
+            0.0, 2.0, 0.0, 0.0,
+            -3.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 4.0, 0.0,
+            1.0, 2.0, 3.0, 1.0,
+        );
+        assert_abs_diff_eq!(transform.to_matrix(), expected, epsilon = 1e-6);
+        assert_abs_diff_eq!(
+            transform.transform_point(Point3::new(1.0, 1.0, 1.0)),
+            Point3::new(-2.0, 4.0, 7.0),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn normal_matrix_under_non_uniform_scale() {
+        let transform = Transform::from_nonuniform_scale(vec3(2.0, 1.0, 1.0));
+        assert_abs_diff_eq!(
+            transform.normal_matrix(),
+            Matrix3::from_diagonal(vec3(0.5, 1.0, 1.0)),
+            epsilon = 1e-6
+        );
+
+        // normals stay perpendicular to the surface, which the model matrix alone breaks
+        let transform = rotated_scaled();
+        let (normal, tangent) = (vec3(1.0, 1.0, 0.0), vec3(1.0, -1.0, 0.0));
+        let model = transform.to_matrix();
+        let tangent = (model * tangent.extend(0.0)).truncate();
+        assert_abs_diff_eq!(
+            tangent.dot(transform.normal_matrix() * normal),
+            0.0,
+            epsilon = 1e-5
+        );
+        assert!(tangent.dot((model * normal.extend(0.0)).truncate()).abs() > 0.1);
+        assert_abs_diff_eq!(
+            transform.normal_matrix(),
+            normal_matrix(&model),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn inverse_matrix_undoes_the_matrix() {
+        let transform = rotated_scaled();
+        assert_abs_diff_eq!(
+            transform.inverse_matrix() * transform.to_matrix(),
+            Matrix4::identity(),
+            epsilon = 1e-5
+        );
+
+        let uniform = Transform {
+            scale: vec3(2.0, 2.0, 2.0),
+            ..rotated_scaled()
+        };
+        assert_abs_diff_eq!(
+            uniform.inverse().to_matrix(),
+            uniform.inverse_matrix(),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn compose_matches_the_matrix_product() {
+        let parent = Transform {
+            translation: vec3(-3.0, 0.0, 4.0),
+            scale: vec3(1.5, 1.5, 1.5),
+            ..Transform::from_axis_angle(vec3(0.0, 1.0, 0.2), Deg(-45.0))
+        };
+        let child = rotated_scaled();
+        assert_abs_diff_eq!(
+            parent.compose(&child).to_matrix(),
+            parent.to_matrix() * child.to_matrix(),
+            epsilon = 1e-5
+        );
+        assert_abs_diff_eq!(
+            (parent * child).to_matrix() * Vector4::new(1.0, 2.0, 3.0, 1.0),
+            parent.to_matrix() * child.to_matrix() * Vector4::new(1.0, 2.0, 3.0, 1.0),
+            epsilon = 1e-4
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{ffi::c_void, mem, ptr};
+
+use cgmath::{Matrix4, Vector3};
+use gl::types::*;
+
+/// binding point of the `Matrices` block
+pub const MATRICES_BINDING: GLuint = 0;
+
+/// uniform blocks with a fixed binding point. `Shader` links every block it declares from
+/// this list right after compiling, so the shaders don't need to know about the buffers
+pub const BLOCK_BINDINGS: [(&str, GLuint); 1] = [("Matrices", MATRICES_BINDING)];
+
+/// the contents of the `Matrices` block, shared by every shader declaring
+///
+/// ```glsl
+/// layout (std140) uniform Matrices {
+///     mat4 projection;
+///     mat4 view;
+///     vec3 cameraPosition;
+///     float time;
+/// };
+/// ```
+///
+/// the field order matches std140: the matrices take 64 bytes each and the float fills the
+/// padding after the vec3
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrices {
+    pub projection: Matrix4<f32>,
+    pub view: Matrix4<f32>,
+    pub camera_position: Vector3<f32>,
+    /// seconds since the start
+    pub time: f32,
+}
+
+// the std140 size of the block
+const _: () = assert!(mem::size_of::<Matrices>() == 144);
+
+/// the buffer behind the `Matrices` block, bound to `MATRICES_BINDING` when it is created.
+/// update it once per frame before drawing
+pub struct MatricesBuffer {
+    id: GLuint,
+}
+
+#[allow(dead_code)]
+impl MatricesBuffer {
+    pub fn new() -> MatricesBuffer {
+        let mut buffer = MatricesBuffer { id: 0 };
+        unsafe {
+            gl::GenBuffers(1, &mut buffer.id);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, buffer.id);
+            gl::BufferData(
+                gl::UNIFORM_BUFFER,
+                mem::size_of::<Matrices>() as GLsizeiptr,
+                ptr::null(),
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, MATRICES_BINDING, buffer.id);
+        }
+        buffer
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub unsafe fn update(&self, matrices: &Matrices) {
+        gl::BindBuffer(gl::UNIFORM_BUFFER, self.id);
+        gl::BufferSubData(
+            gl::UNIFORM_BUFFER,
+            0,
+            mem::size_of::<Matrices>() as GLsizeiptr,
+            matrices as *const Matrices as *const c_void,
+        );
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+    }
+}
+
+impl Drop for MatricesBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
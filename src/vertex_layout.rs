@@ -0,0 +1,193 @@
+use std::{ffi::c_void, mem};
+
+use anyhow::{bail, Result};
+use gl::types::*;
+
+/// the type of a float vertex attribute as the shader declares it
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    /// four consecutive locations, one per column
+    Mat4,
+}
+
+#[allow(dead_code)]
+impl AttributeType {
+    /// how many attribute locations the type takes up
+    pub fn locations(self) -> u32 {
+        match self {
+            AttributeType::Mat4 => 4,
+            _ => 1,
+        }
+    }
+
+    /// floats per location
+    fn components(self) -> GLint {
+        match self {
+            AttributeType::Float => 1,
+            AttributeType::Vec2 => 2,
+            AttributeType::Vec3 => 3,
+            AttributeType::Vec4 | AttributeType::Mat4 => 4,
+        }
+    }
+
+    pub fn size(self) -> usize {
+        self.locations() as usize * self.components() as usize * mem::size_of::<f32>()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    /// the first location, a `Mat4` also uses the three after it
+    pub location: GLuint,
+    pub kind: AttributeType,
+    /// byte offset inside one element of the buffer
+    pub offset: usize,
+}
+
+/// how the attributes are laid out in one buffer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VertexLayout {
+    /// bytes from one element to the next
+    pub stride: usize,
+    pub attributes: Vec<Attribute>,
+    /// 0 advances the attributes every vertex, n advances them every n instances
+    pub divisor: GLuint,
+}
+
+#[allow(dead_code)]
+impl VertexLayout {
+    /// attributes read once per vertex from elements of `stride` bytes
+    pub fn per_vertex(stride: usize) -> VertexLayout {
+        VertexLayout {
+            stride,
+            ..Default::default()
+        }
+    }
+
+    /// attributes read once per instance from elements of `stride` bytes
+    pub fn per_instance(stride: usize) -> VertexLayout {
+        VertexLayout {
+            stride,
+            divisor: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn with(mut self, location: GLuint, kind: AttributeType, offset: usize) -> Self {
+        self.attributes.push(Attribute {
+            location,
+            kind,
+            offset,
+        });
+        self
+    }
+
+    /// tightly packed attributes in the given order, the stride is their total size
+    pub fn packed(attributes: &[(GLuint, AttributeType)], divisor: GLuint) -> VertexLayout {
+        let mut layout = VertexLayout {
+            divisor,
+            ..Default::default()
+        };
+        for &(location, kind) in attributes {
+            let offset = layout.stride;
+            layout = layout.with(location, kind, offset);
+            layout.stride += kind.size();
+        }
+        layout
+    }
+
+    /// point the attributes at the buffer bound to ARRAY_BUFFER, for the bound vertex array
+    pub unsafe fn apply(&self) {
+        for attribute in &self.attributes {
+            let components = attribute.kind.components();
+            for column in 0..attribute.kind.locations() {
+                let location = attribute.location + column;
+                let offset = attribute.offset + column as usize * components as usize * 4;
+                gl::VertexAttribPointer(
+                    location,
+                    components,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    self.stride as GLsizei,
+                    offset as *const c_void,
+                );
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, self.divisor);
+            }
+        }
+    }
+}
+
+/// per-instance data in its own buffer, attached to vertex arrays next to their vertex buffer
+pub struct InstanceBuffer {
+    id: GLuint,
+    pub layout: VertexLayout,
+    len: usize,
+}
+
+#[allow(dead_code)]
+impl InstanceBuffer {
+    /// upload `instances`, the layout's stride has to be the size of `T`
+    pub fn new<T: Copy>(instances: &[T], layout: VertexLayout) -> Result<InstanceBuffer> {
+        if layout.stride != mem::size_of::<T>() {
+            bail!(
+                "layout stride {} doesn't match the instance size {}",
+                layout.stride,
+                mem::size_of::<T>()
+            );
+        }
+        let mut buffer = InstanceBuffer {
+            id: 0,
+            layout,
+            len: 0,
+        };
+        unsafe {
+            gl::GenBuffers(1, &mut buffer.id);
+            buffer.update(instances);
+        }
+        Ok(buffer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// replace the contents, the vertex arrays it is attached to see the new data
+    pub unsafe fn update<T: Copy>(&mut self, instances: &[T]) {
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.id);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            mem::size_of_val(instances) as GLsizeiptr,
+            instances.as_ptr() as *const c_void,
+            gl::DYNAMIC_DRAW,
+        );
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        self.len = instances.len();
+    }
+
+    /// add the instance attributes to `vao`
+    pub unsafe fn attach(&self, vao: GLuint) {
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.id);
+        self.layout.apply();
+        gl::BindVertexArray(0);
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+    }
+}
+
+impl Drop for InstanceBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}